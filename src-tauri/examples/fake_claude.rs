@@ -0,0 +1,378 @@
+//! Stand-in for the `claude` CLI, used by the integration tests.
+//!
+//! It accepts the same command line and speaks the same stream-json protocol
+//! as the real CLI, but replays a scripted NDJSON fixture instead of talking
+//! to the API. The script is read from `.fake-claude/script.ndjson` in the
+//! working directory (the session's project path), or from
+//! `.fake-claude/resume.ndjson` when started with `--resume` and that file
+//! exists.
+//!
+//! Script lines are written to stdout as-is, with `{{session_id}}` replaced
+//! by the session ID (the `--resume` value, otherwise `fake-session`).
+//! Lines with a `"fake"` key are directives instead:
+//!
+//! - `{"fake":"await_turn"}` — wait for the next user turn on stdin; exit 0 on EOF
+//! - `{"fake":"stderr","text":"..."}` — write a line to stderr
+//! - `{"fake":"sleep","ms":100}` — pause
+//! - `{"fake":"exit","code":1}` — exit right away, like a crash
+//! - `{"fake":"permission","tool_name":"Bash","input":{...}}` — ask the
+//!   `--permission-prompt-tool` (an MCP server from `--mcp-config`, started on
+//!   first use) whether the tool may run, and append its decision to
+//!   `.fake-claude/permissions.ndjson` (`null` without a prompt tool)
+//!
+//! Once the script is exhausted the process waits for stdin to close and
+//! exits 0, like the real CLI does.
+//!
+//! With `--input-format ndjson` it takes turns in the test provider's
+//! dialect (`{"type":"prompt","text":"..."}`) instead, so the same binary and
+//! scripts can stand in for a second agent.
+//!
+//! Each invocation's arguments and environment are appended to
+//! `.fake-claude/invocations.ndjson` and each user turn received to
+//! `.fake-claude/turns.ndjson` (its text, plus its content blocks with
+//! stream-json input), so tests can check what was sent. `--version` prints
+//! `$FAKE_CLAUDE_VERSION` (default `1.0.99 (Claude Code)`); with
+//! `FAKE_CLAUDE_LEGACY=1`, `--help` leaves out stream-json input to mimic an
+//! old CLI.
+//!
+//! Started with `--permission-mcp` it runs the app's permission-prompt MCP
+//! server instead, so tests can hand this binary to the CLI as that server.
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{self, Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use openclaudgents_lib::claude::permission_mcp;
+use serde_json::Value;
+
+const FIXTURE_DIR: &str = ".fake-claude";
+
+const FLAGS: &[&str] = &[
+    "--print",
+    "--output-format",
+    "--verbose",
+    "--resume",
+    "--model",
+    "--fallback-model",
+    "--permission-mode",
+    "--allowedTools",
+    "--disallowedTools",
+    "--append-system-prompt",
+    "--add-dir",
+    "--max-turns",
+    "--mcp-config",
+    "--permission-prompt-tool",
+];
+
+/// Flags an old CLI doesn't have
+const STREAM_INPUT_FLAGS: &[&str] = &["--input-format", "--include-partial-messages"];
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.first().map(String::as_str) == Some(permission_mcp::SERVER_ARG) {
+        process::exit(permission_mcp::serve());
+    }
+
+    if args.iter().any(|a| a == "--version") {
+        let version = std::env::var("FAKE_CLAUDE_VERSION")
+            .unwrap_or_else(|_| "1.0.99 (Claude Code)".to_string());
+        println!("{}", version);
+        return;
+    }
+    if args.iter().any(|a| a == "--help") {
+        print_help();
+        return;
+    }
+
+    let env: BTreeMap<String, String> = std::env::vars().collect();
+    append_log(
+        "invocations.ndjson",
+        &serde_json::json!({ "args": args, "env": env }),
+    );
+
+    let resume_id = flag_value(&args, "--resume");
+    let input_format = flag_value(&args, "--input-format");
+    let session_id = resume_id
+        .clone()
+        .unwrap_or_else(|| "fake-session".to_string());
+
+    let resume_script = Path::new(FIXTURE_DIR).join("resume.ndjson");
+    let script_path = if resume_id.is_some() && resume_script.exists() {
+        resume_script
+    } else {
+        Path::new(FIXTURE_DIR).join("script.ndjson")
+    };
+    let script = match fs::read_to_string(&script_path) {
+        Ok(script) => script,
+        Err(e) => {
+            eprintln!("fake-claude: cannot read {}: {}", script_path.display(), e);
+            process::exit(2);
+        }
+    };
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let stdout = io::stdout();
+    let mut permission_prompt: Option<PermissionPrompt> = None;
+
+    for line in script.lines().filter(|l| !l.trim().is_empty()) {
+        let directive = serde_json::from_str::<Value>(line)
+            .ok()
+            .filter(|v| v.get("fake").is_some());
+
+        let Some(directive) = directive else {
+            let mut out = stdout.lock();
+            let _ = writeln!(out, "{}", line.replace("{{session_id}}", &session_id));
+            let _ = out.flush();
+            continue;
+        };
+
+        match directive["fake"].as_str().unwrap_or("") {
+            "await_turn" => match read_turn(&mut input, input_format.as_deref()) {
+                Some(turn) => append_log("turns.ndjson", &turn),
+                None => process::exit(0),
+            },
+            "stderr" => {
+                eprintln!("{}", directive["text"].as_str().unwrap_or(""));
+            }
+            "permission" => {
+                let tool_name = directive["tool_name"].as_str().unwrap_or("Bash");
+                if permission_prompt.is_none() {
+                    permission_prompt = PermissionPrompt::start(&args);
+                }
+                let decision = permission_prompt
+                    .as_mut()
+                    .map(|prompt| prompt.ask(&directive))
+                    .unwrap_or(Value::Null);
+                append_log(
+                    "permissions.ndjson",
+                    &serde_json::json!({ "tool_name": tool_name, "decision": decision }),
+                );
+            }
+            "sleep" => {
+                let ms = directive["ms"].as_u64().unwrap_or(0);
+                thread::sleep(Duration::from_millis(ms));
+            }
+            "exit" => {
+                let code = directive["code"].as_i64().unwrap_or(1);
+                process::exit(code as i32);
+            }
+            other => {
+                eprintln!("fake-claude: unknown directive {}", other);
+                process::exit(2);
+            }
+        }
+    }
+
+    // Like the real CLI, stay alive until there is no more input
+    let _ = io::copy(&mut input, &mut io::sink());
+}
+
+/// Read the next user turn. With stream-json or ndjson input that is one
+/// line; otherwise the whole of stdin is the prompt. `None` once stdin is closed.
+fn read_turn(input: &mut impl BufRead, input_format: Option<&str>) -> Option<Value> {
+    let Some(input_format) = input_format else {
+        let mut prompt = String::new();
+        input.read_to_string(&mut prompt).ok()?;
+        return (!prompt.is_empty()).then(|| serde_json::json!({ "text": prompt }));
+    };
+
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message: Value = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                eprintln!("fake-claude: invalid {} input: {}", input_format, e);
+                process::exit(2);
+            }
+        };
+        if input_format == "ndjson" && message["type"] == "prompt" {
+            return Some(serde_json::json!({ "text": message["text"] }));
+        }
+        if message["type"] != "user" {
+            eprintln!(
+                "fake-claude: expected a user message, got {}",
+                message["type"]
+            );
+            process::exit(2);
+        }
+
+        let text = message["message"]["content"]
+            .as_array()
+            .map(|blocks| {
+                blocks
+                    .iter()
+                    .filter_map(|b| b["text"].as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .unwrap_or_default();
+        return Some(serde_json::json!({
+            "text": text,
+            "content": message["message"]["content"],
+        }));
+    }
+}
+
+/// The MCP server named by `--permission-prompt-tool`, started from its
+/// `--mcp-config` entry and kept running like the real CLI does
+struct PermissionPrompt {
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    tool: String,
+    next_id: u64,
+}
+
+impl PermissionPrompt {
+    /// `None` without a prompt tool; exits if the server can't be started
+    fn start(args: &[String]) -> Option<Self> {
+        let prompt_tool = flag_value(args, "--permission-prompt-tool")?;
+        let (server_name, tool) = prompt_tool
+            .strip_prefix("mcp__")
+            .and_then(|rest| rest.split_once("__"))
+            .unwrap_or_else(|| fail(&format!("bad permission prompt tool {}", prompt_tool)));
+        // Like the real CLI, the config is inline JSON or a file holding it
+        let config: Value = flag_value(args, "--mcp-config")
+            .and_then(|c| {
+                serde_json::from_str(&c)
+                    .ok()
+                    .or_else(|| serde_json::from_str(&fs::read_to_string(&c).ok()?).ok())
+            })
+            .unwrap_or_else(|| fail("--permission-prompt-tool needs --mcp-config"));
+        let server = &config["mcpServers"][server_name];
+
+        let mut command = Command::new(server["command"].as_str().unwrap_or_default());
+        for arg in server["args"].as_array().into_iter().flatten() {
+            command.arg(arg.as_str().unwrap_or_default());
+        }
+        for (key, value) in server["env"].as_object().into_iter().flatten() {
+            command.env(key, value.as_str().unwrap_or_default());
+        }
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|e| fail(&format!("cannot start MCP server {}: {}", server_name, e)));
+
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            fail("MCP server has no stdio");
+        };
+        let mut prompt = Self {
+            stdin,
+            stdout: BufReader::new(stdout),
+            _child: child,
+            tool: tool.to_string(),
+            next_id: 0,
+        };
+        prompt.call(
+            "initialize",
+            serde_json::json!({ "protocolVersion": "2025-06-18" }),
+        );
+        prompt.notify("notifications/initialized");
+        Some(prompt)
+    }
+
+    /// Call the prompt tool for a `permission` directive and return the
+    /// decision it answered with
+    fn ask(&mut self, directive: &Value) -> Value {
+        let result = self.call(
+            "tools/call",
+            serde_json::json!({
+                "name": self.tool,
+                "arguments": {
+                    "tool_name": directive["tool_name"].as_str().unwrap_or("Bash"),
+                    "input": directive.get("input").cloned().unwrap_or(serde_json::json!({})),
+                    "tool_use_id": directive["tool_use_id"].as_str().unwrap_or("toolu_fake"),
+                },
+            }),
+        );
+        result["content"][0]["text"]
+            .as_str()
+            .and_then(|text| serde_json::from_str(text).ok())
+            .unwrap_or_else(|| fail(&format!("unexpected tool result {}", result)))
+    }
+
+    fn call(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id,
+            "method": method,
+            "params": params,
+        });
+        self.send(&request);
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line).unwrap_or(0) == 0 {
+            fail("MCP server closed its stdout");
+        }
+        let reply: Value = serde_json::from_str(&line)
+            .unwrap_or_else(|e| fail(&format!("invalid MCP reply: {}", e)));
+        if reply["id"] != self.next_id || reply.get("error").is_some() {
+            fail(&format!("MCP {} failed: {}", method, reply));
+        }
+        reply["result"].clone()
+    }
+
+    fn notify(&mut self, method: &str) {
+        self.send(&serde_json::json!({ "jsonrpc": "2.0", "method": method }));
+    }
+
+    fn send(&mut self, message: &Value) {
+        if writeln!(self.stdin, "{}", message)
+            .and_then(|_| self.stdin.flush())
+            .is_err()
+        {
+            fail("MCP server closed its stdin");
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("fake-claude: {}", message);
+    process::exit(2);
+}
+
+fn print_help() {
+    let legacy = std::env::var("FAKE_CLAUDE_LEGACY").is_ok_and(|v| v == "1");
+    println!("Usage: claude [options] [prompt]");
+    println!();
+    println!("Options:");
+    for flag in FLAGS {
+        println!("  {} <value>", flag);
+    }
+    if !legacy {
+        for flag in STREAM_INPUT_FLAGS {
+            println!("  {} <value>", flag);
+        }
+    }
+}
+
+fn flag_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
+
+fn append_log(file: &str, entry: &Value) {
+    let path = Path::new(FIXTURE_DIR).join(file);
+    let written = fs::create_dir_all(FIXTURE_DIR).and_then(|_| {
+        let mut log = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(log, "{}", entry)
+    });
+    if let Err(e) = written {
+        eprintln!("fake-claude: cannot write {}: {}", path.display(), e);
+    }
+}
@@ -0,0 +1,7 @@
+-- Launch settings (JSON) reapplied whenever the session is respawned,
+-- including after an app restart
+ALTER TABLE sessions ADD COLUMN launch_settings TEXT;
+
+-- PID of the session's CLI process while it runs, used on startup to find
+-- processes orphaned by a crash
+ALTER TABLE sessions ADD COLUMN pid INTEGER;
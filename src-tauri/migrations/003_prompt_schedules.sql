@@ -0,0 +1,38 @@
+-- Prompts sent to a session or project at a set time or on a cron schedule
+CREATE TABLE IF NOT EXISTS prompt_schedules (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    -- Session that receives the prompt; NULL starts a new session per run
+    session_id TEXT,
+    project_path TEXT NOT NULL,
+    prompt TEXT NOT NULL,
+    -- ScheduleSpec as JSON: {"kind":"once","at":...} or {"kind":"cron","expr":...}
+    spec TEXT NOT NULL,
+    -- LaunchSettings (JSON) for sessions started by a run
+    launch_settings TEXT,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    next_run_at TEXT,
+    last_run_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- One row per run of a schedule, kept for review
+CREATE TABLE IF NOT EXISTS schedule_runs (
+    id TEXT PRIMARY KEY,
+    schedule_id TEXT NOT NULL,
+    session_id TEXT,
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    -- running, the turn's result subtype, or cancelled/exited/failed/interrupted
+    outcome TEXT NOT NULL,
+    is_error INTEGER NOT NULL DEFAULT 0,
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    cost_usd REAL,
+    -- RunDiff (JSON): files changed relative to the commit the run started on
+    diff_summary TEXT,
+    error TEXT,
+    FOREIGN KEY (schedule_id) REFERENCES prompt_schedules(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_schedule_runs_schedule ON schedule_runs(schedule_id, started_at);
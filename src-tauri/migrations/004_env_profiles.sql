@@ -0,0 +1,13 @@
+-- Named sets of environment variables applied to agent processes at spawn
+CREATE TABLE IF NOT EXISTS env_profiles (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    -- Project whose sessions use the profile by default; at most one per project
+    project_path TEXT,
+    -- EnvVar list as JSON: [{"key":...,"value":...,"secret":...}]
+    vars TEXT NOT NULL DEFAULT '[]',
+    -- Directories put in front of PATH, as a JSON array
+    path_prepend TEXT NOT NULL DEFAULT '[]',
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
@@ -0,0 +1,12 @@
+-- What happened in a session besides the conversation itself, e.g. hook runs
+CREATE TABLE IF NOT EXISTS session_activity (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    -- Entry type, e.g. 'hook'
+    kind TEXT NOT NULL,
+    -- The entry as JSON, shaped by its kind
+    payload TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_activity_session ON session_activity(session_id, id);
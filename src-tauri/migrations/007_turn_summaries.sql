@@ -0,0 +1,23 @@
+-- One row per finished turn, from the CLI's result event
+CREATE TABLE IF NOT EXISTS turn_summaries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    -- Queued prompt the turn ran, if it came from the queue
+    prompt_id TEXT,
+    subtype TEXT NOT NULL,
+    is_error INTEGER NOT NULL DEFAULT 0,
+    duration_ms INTEGER,
+    duration_api_ms INTEGER,
+    num_turns INTEGER,
+    cost_usd REAL,
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+    result TEXT,
+    -- Refused tool calls as a JSON array
+    permission_denials TEXT NOT NULL DEFAULT '[]',
+    finished_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_turn_summaries_session ON turn_summaries(session_id, id);
@@ -0,0 +1,5 @@
+-- API message each usage row counts, so a message reported again (e.g. when
+-- a resumed CLI replays it) is only counted once
+ALTER TABLE usage_log ADD COLUMN message_id TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_usage_message ON usage_log(session_id, message_id);
@@ -0,0 +1,12 @@
+-- At most one default profile per project. Where there are several, the
+-- most recently updated one stays the default.
+UPDATE env_profiles SET project_path = NULL
+WHERE project_path IS NOT NULL AND EXISTS (
+    SELECT 1 FROM env_profiles AS newer
+    WHERE newer.project_path = env_profiles.project_path
+      AND (newer.updated_at > env_profiles.updated_at
+           OR (newer.updated_at = env_profiles.updated_at AND newer.id > env_profiles.id))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_env_profiles_project
+    ON env_profiles(project_path) WHERE project_path IS NOT NULL;
@@ -0,0 +1,167 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use tokio::process::Command;
+
+use super::content::{self, ContentBlock};
+use super::locator::CliLocator;
+use super::permission_mcp::{self, PermissionServer};
+use super::process::{SessionMode, SpawnOptions};
+use super::provider::{AgentCommand, AgentProvider, LineParser, DEFAULT_PROVIDER};
+use super::session_store::{self, DiscoveredSession, ParsedMessage};
+use super::stream_parser::StreamParser;
+
+/// The Claude Code CLI and its stream-json protocol.
+///
+/// Processes are launched with `--input-format stream-json` so one process
+/// serves the whole session. Older CLIs without stream-json input fall back
+/// to [`SessionMode::Respawn`]: `-p` reads one plain-text prompt from stdin,
+/// responds, then exits, and the next turn starts a new process with
+/// `--resume <claude-session-id>`.
+#[derive(Default)]
+pub struct ClaudeCliProvider {
+    cli: CliLocator,
+}
+
+impl ClaudeCliProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Finds the `claude` binary and caches what its version supports
+    pub fn cli(&self) -> &CliLocator {
+        &self.cli
+    }
+
+    async fn build_command(
+        &self,
+        opts: &SpawnOptions,
+        permissions: Option<&PermissionServer>,
+    ) -> Result<AgentCommand, String> {
+        let cli = self.cli.locate(opts.claude_cli_path.clone()).await?;
+        let mode = if cli.supports("--input-format") {
+            SessionMode::Persistent
+        } else {
+            log::info!(
+                "[process] {} has no stream-json input, falling back to one process per turn",
+                cli.path
+            );
+            SessionMode::Respawn
+        };
+
+        let mut cmd = Command::new(&cli.path);
+        cmd.arg("-p");
+        if mode == SessionMode::Persistent {
+            cmd.arg("--input-format").arg("stream-json");
+        }
+        cmd.arg("--output-format")
+            .arg("stream-json")
+            .arg("--verbose");
+        if cli.supports("--include-partial-messages") {
+            cmd.arg("--include-partial-messages");
+        }
+
+        // Strip env vars that prevent CLI from running inside other Claude sessions
+        cmd.env_remove("CLAUDECODE");
+        cmd.env_remove("CLAUDE_CODE_ENTRY_POINT");
+
+        if let Some(ref resume_id) = opts.resume_session_id {
+            cmd.arg("--resume").arg(resume_id);
+        }
+
+        cmd.args(opts.launch.to_args(&cli)?);
+
+        // Ask the app before tool use, through the permission-prompt MCP server
+        let mut mcp_config = None;
+        if let Some(server) = permissions {
+            if cli.supports("--permission-prompt-tool") && cli.supports("--mcp-config") {
+                let config = server.write_mcp_config()?;
+                cmd.arg("--mcp-config")
+                    .arg(config.path())
+                    .arg("--permission-prompt-tool")
+                    .arg(permission_mcp::PERMISSION_TOOL);
+                mcp_config = Some(config);
+            } else {
+                log::info!(
+                    "[process] {} can't route tool approvals, leaving them to its permission mode",
+                    cli.path
+                );
+            }
+        }
+
+        Ok(AgentCommand {
+            command: cmd,
+            mode,
+            mcp_config,
+        })
+    }
+}
+
+impl AgentProvider for ClaudeCliProvider {
+    fn id(&self) -> &'static str {
+        DEFAULT_PROVIDER
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Claude Code"
+    }
+
+    fn command<'a>(
+        &'a self,
+        opts: &'a SpawnOptions,
+        permissions: Option<&'a PermissionServer>,
+    ) -> Pin<Box<dyn Future<Output = Result<AgentCommand, String>> + Send + 'a>> {
+        Box::pin(self.build_command(opts, permissions))
+    }
+
+    /// Persistent processes get a stream-json user message; respawned ones
+    /// get the plain prompt, so they can't take attachments
+    fn encode_turn(&self, mode: SessionMode, blocks: &[ContentBlock]) -> Result<String, String> {
+        match mode {
+            SessionMode::Persistent => {
+                let mut line = serde_json::json!({
+                    "type": "user",
+                    "message": {
+                        "role": "user",
+                        "content": blocks,
+                    },
+                })
+                .to_string();
+                line.push('\n');
+                Ok(line)
+            }
+            SessionMode::Respawn => {
+                if blocks.iter().any(|b| !matches!(b, ContentBlock::Text { .. })) {
+                    return Err("This Claude CLI only takes plain-text prompts — update it \
+                                to send images or documents"
+                        .to_string());
+                }
+                Ok(content::text_of(blocks))
+            }
+        }
+    }
+
+    fn parser(&self) -> Box<dyn LineParser> {
+        Box::new(StreamParser::new())
+    }
+
+    fn discover_sessions(&self) -> Vec<DiscoveredSession> {
+        session_store::discover_sessions()
+    }
+
+    fn has_transcript(&self, agent_session_id: &str) -> bool {
+        session_store::session_file_exists(agent_session_id)
+    }
+
+    fn session_messages(&self, agent_session_id: &str) -> Vec<ParsedMessage> {
+        session_store::parse_session_messages(agent_session_id)
+    }
+
+    fn fork_transcript(
+        &self,
+        agent_session_id: &str,
+        message_uuid: &str,
+    ) -> Result<String, String> {
+        session_store::fork_session_file(agent_session_id, message_uuid)
+    }
+}
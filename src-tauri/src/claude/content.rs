@@ -0,0 +1,359 @@
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// Largest image the API accepts, after decoding
+const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+/// Largest PDF or text document the API accepts
+const MAX_DOCUMENT_BYTES: usize = 32 * 1024 * 1024;
+/// Combined size of all attachments in one turn, after decoding
+const MAX_TURN_BYTES: usize = 32 * 1024 * 1024;
+const MAX_IMAGES_PER_TURN: usize = 20;
+
+/// Image formats the API accepts
+const IMAGE_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+/// One part of a user turn as the composer sends it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ContentInput {
+    Text {
+        text: String,
+    },
+    /// An image file inside the project; relative paths are taken from
+    /// the project root
+    ImageFile {
+        path: String,
+    },
+    /// Base64 image bytes (or a data URL), e.g. a screenshot pasted from
+    /// the clipboard
+    #[serde(rename_all = "camelCase")]
+    ImageData {
+        media_type: String,
+        data: String,
+    },
+    /// A PDF or plain-text file inside the project
+    DocumentFile {
+        path: String,
+    },
+}
+
+/// A content block of a stream-json user message, in the API's shape
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlock {
+    Text {
+        text: String,
+    },
+    Image {
+        source: BlockSource,
+    },
+    Document {
+        source: BlockSource,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BlockSource {
+    Base64 { media_type: String, data: String },
+    Text { media_type: String, data: String },
+}
+
+impl ContentBlock {
+    pub fn text(text: &str) -> Self {
+        ContentBlock::Text {
+            text: text.to_string(),
+        }
+    }
+
+    /// Short description for queue listings, e.g. `image/png, 231 KB`
+    pub fn label(&self) -> Option<String> {
+        let (source, title) = match self {
+            ContentBlock::Text { .. } => return None,
+            ContentBlock::Image { source } => (source, None),
+            ContentBlock::Document { source, title } => (source, title.as_deref()),
+        };
+        let (media_type, size) = match source {
+            BlockSource::Base64 { media_type, data } => (media_type, data.len() / 4 * 3),
+            BlockSource::Text { media_type, data } => (media_type, data.len()),
+        };
+        let size = format!("{} KB", size.div_ceil(1024));
+        Some(match title {
+            Some(title) => format!("{} ({}, {})", title, media_type, size),
+            None => format!("{}, {}", media_type, size),
+        })
+    }
+}
+
+/// The text of a turn's text blocks, for display and logs
+pub fn text_of(content: &[ContentBlock]) -> String {
+    content
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Read attachments from disk and validate every part of a turn against the
+/// API's formats and size limits. Relative paths are resolved against
+/// `project_path`, and files outside it are refused.
+pub async fn resolve(
+    inputs: Vec<ContentInput>,
+    project_path: &str,
+) -> Result<Vec<ContentBlock>, String> {
+    let mut blocks = Vec::with_capacity(inputs.len());
+    let mut total_bytes = 0;
+    let mut images = 0;
+
+    for input in inputs {
+        let (block, bytes) = match input {
+            ContentInput::Text { text } => {
+                if text.trim().is_empty() {
+                    continue;
+                }
+                (ContentBlock::Text { text }, 0)
+            }
+            ContentInput::ImageFile { path } => {
+                let bytes = read_file(project_path, &path, MAX_IMAGE_BYTES).await?;
+                image_block(&bytes, &path)?
+            }
+            ContentInput::ImageData { media_type, data } => {
+                let bytes = BASE64
+                    .decode(strip_data_url(&data))
+                    .map_err(|e| format!("Pasted image is not valid base64: {}", e))?;
+                // The format is taken from the bytes; the declared type only names it
+                image_block(&bytes, &format!("Pasted {}", media_type))?
+            }
+            ContentInput::DocumentFile { path } => {
+                let bytes = read_file(project_path, &path, MAX_DOCUMENT_BYTES).await?;
+                document_block(bytes, &path)?
+            }
+        };
+
+        if matches!(block, ContentBlock::Image { .. }) {
+            images += 1;
+            if images > MAX_IMAGES_PER_TURN {
+                return Err(format!(
+                    "At most {} images can be sent in one message",
+                    MAX_IMAGES_PER_TURN
+                ));
+            }
+        }
+        total_bytes += bytes;
+        if total_bytes > MAX_TURN_BYTES {
+            return Err(format!(
+                "Attachments add up to more than {} MB",
+                MAX_TURN_BYTES / (1024 * 1024)
+            ));
+        }
+        blocks.push(block);
+    }
+
+    if blocks.is_empty() {
+        return Err("Message is empty".to_string());
+    }
+    Ok(blocks)
+}
+
+/// Read a file that must resolve (after symlinks and `..`) to a path under
+/// `project_path`, so a session can't be handed files from elsewhere
+async fn read_file(project_path: &str, path: &str, limit: usize) -> Result<Vec<u8>, String> {
+    let root = tokio::fs::canonicalize(project_path)
+        .await
+        .map_err(|e| format!("Failed to open project {}: {}", project_path, e))?;
+    let full = tokio::fs::canonicalize(Path::new(project_path).join(path))
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if !full.starts_with(&root) {
+        return Err(format!("{} is outside the project", path));
+    }
+    let size = tokio::fs::metadata(&full)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", full.display(), e))?
+        .len();
+    if size > limit as u64 {
+        return Err(format!(
+            "{} is {} — the limit is {} MB",
+            path,
+            human_size(size as usize),
+            limit / (1024 * 1024)
+        ));
+    }
+    tokio::fs::read(&full)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", full.display(), e))
+}
+
+/// Build an image block, taking the format from the bytes themselves
+fn image_block(bytes: &[u8], name: &str) -> Result<(ContentBlock, usize), String> {
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err(format!(
+            "{} is {} — images can be at most {} MB",
+            name,
+            human_size(bytes.len()),
+            MAX_IMAGE_BYTES / (1024 * 1024)
+        ));
+    }
+    let media_type = sniff_image(bytes).ok_or_else(|| {
+        format!(
+            "{} is not a supported image (use {})",
+            name,
+            IMAGE_TYPES.join(", ")
+        )
+    })?;
+    let block = ContentBlock::Image {
+        source: BlockSource::Base64 {
+            media_type: media_type.to_string(),
+            data: BASE64.encode(bytes),
+        },
+    };
+    Ok((block, bytes.len()))
+}
+
+/// Build a document block: PDFs are sent as base64, anything else must be UTF-8 text
+fn document_block(bytes: Vec<u8>, path: &str) -> Result<(ContentBlock, usize), String> {
+    let size = bytes.len();
+    let title = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string());
+    let source = if bytes.starts_with(b"%PDF-") {
+        BlockSource::Base64 {
+            media_type: "application/pdf".to_string(),
+            data: BASE64.encode(&bytes),
+        }
+    } else {
+        let text = String::from_utf8(bytes)
+            .map_err(|_| format!("{} is neither a PDF nor UTF-8 text", path))?;
+        BlockSource::Text {
+            media_type: "text/plain".to_string(),
+            data: text,
+        }
+    };
+    Ok((ContentBlock::Document { source, title }, size))
+}
+
+fn sniff_image(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Accept both bare base64 and `data:image/png;base64,...` URLs
+fn strip_data_url(data: &str) -> &str {
+    match data.split_once(";base64,") {
+        Some((prefix, rest)) if prefix.starts_with("data:") => rest,
+        _ => data,
+    }
+}
+
+fn human_size(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{} KB", bytes.div_ceil(1024))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    const GIF: &str = "R0lGODlhAQABAAAAACw=";
+
+    fn pasted(data: String) -> ContentInput {
+        ContentInput::ImageData {
+            media_type: "image/png".to_string(),
+            data,
+        }
+    }
+
+    fn project() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("content-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("project")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn image_formats_come_from_the_bytes() {
+        assert_eq!(sniff_image(PNG), Some("image/png"));
+        assert_eq!(sniff_image(b"RIFF\x24\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff_image(b"RIFF\x24\0\0\0WAVEfmt "), None);
+        assert_eq!(sniff_image(b"RIFF"), None);
+        assert_eq!(sniff_image(b"not an image"), None);
+    }
+
+    #[test]
+    fn data_urls_are_stripped_to_their_base64() {
+        assert_eq!(strip_data_url("data:image/gif;base64,R0lG"), "R0lG");
+        assert_eq!(strip_data_url("R0lG"), "R0lG");
+        assert_eq!(strip_data_url("text;base64,R0lG"), "text;base64,R0lG");
+    }
+
+    #[tokio::test]
+    async fn a_turn_holds_at_most_twenty_images() {
+        let images = |n| (0..n).map(|_| pasted(GIF.to_string())).collect::<Vec<_>>();
+        assert_eq!(resolve(images(20), "/").await.unwrap().len(), 20);
+        let err = resolve(images(21), "/").await.unwrap_err();
+        assert!(err.contains("At most 20 images"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn attachments_are_limited_to_32_mb_per_turn() {
+        let mut image = PNG.to_vec();
+        image.resize(MAX_IMAGE_BYTES, 0);
+        let image = BASE64.encode(&image);
+        // Six images fit in 32 MB, the seventh doesn't
+        let images = |n| (0..n).map(|_| pasted(image.clone())).collect::<Vec<_>>();
+        assert_eq!(resolve(images(6), "/").await.unwrap().len(), 6);
+        let err = resolve(images(7), "/").await.unwrap_err();
+        assert!(err.contains("more than 32 MB"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn files_outside_the_project_are_refused() {
+        let dir = project();
+        let root = dir.join("project");
+        std::fs::write(root.join("notes.txt"), "inside").unwrap();
+        std::fs::write(dir.join("secret.txt"), "outside").unwrap();
+        let root = root.to_string_lossy().into_owned();
+        let document = |path: &str| {
+            vec![ContentInput::DocumentFile {
+                path: path.to_string(),
+            }]
+        };
+
+        assert!(resolve(document("notes.txt"), &root).await.is_ok());
+        let err = resolve(document("../secret.txt"), &root).await.unwrap_err();
+        assert!(err.contains("outside the project"), "{}", err);
+        let absolute = dir.join("secret.txt").to_string_lossy().into_owned();
+        let err = resolve(document(&absolute), &root).await.unwrap_err();
+        assert!(err.contains("outside the project"), "{}", err);
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.join("secret.txt"), dir.join("project/link.txt"))
+                .unwrap();
+            let err = resolve(document("link.txt"), &root).await.unwrap_err();
+            assert!(err.contains("outside the project"), "{}", err);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
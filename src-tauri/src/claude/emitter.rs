@@ -0,0 +1,232 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use super::host::Host;
+use crate::events;
+
+/// How long a text, tool-input or thinking delta may wait for the next one to merge with
+const COALESCE_WINDOW: Duration = Duration::from_millis(16);
+
+/// Events buffered between a session's stdout reader and its emitter task.
+/// When full, the reader waits, which in turn stops it draining the CLI's stdout.
+const CHANNEL_CAPACITY: usize = 512;
+
+/// An event on its way to the frontend
+#[derive(Debug, Clone)]
+pub struct OutboundEvent {
+    pub name: &'static str,
+    pub payload: Value,
+}
+
+impl OutboundEvent {
+    pub fn new(name: &'static str, payload: Value) -> Self {
+        Self { name, payload }
+    }
+
+    /// Raw CLI events mirrored for debugging
+    fn is_raw(&self) -> bool {
+        self.name == events::CLAUDE_STREAM_EVENT
+    }
+
+    /// The string field that adjacent events of this kind concatenate
+    fn merge_field(&self) -> Option<&'static str> {
+        match self.name {
+            events::CLAUDE_TEXT_DELTA => Some("text"),
+            events::CLAUDE_TOOL_INPUT_DELTA => Some("partialJson"),
+            events::CLAUDE_THINKING_DELTA => Some("thinking"),
+            _ => None,
+        }
+    }
+
+    /// Append `next` to this event if both are deltas of the same kind, for
+    /// the same content block
+    fn try_merge(&mut self, next: &OutboundEvent) -> bool {
+        let Some(field) = self.merge_field() else {
+            return false;
+        };
+        if next.name != self.name || !self.same_block(next, field) {
+            return false;
+        }
+        let (Some(current), Some(extra)) = (
+            self.payload.get(field).and_then(|v| v.as_str()),
+            next.payload.get(field).and_then(|v| v.as_str()),
+        ) else {
+            return false;
+        };
+        self.payload[field] = Value::String(format!("{}{}", current, extra));
+        true
+    }
+
+    /// Whether every field but the merged one matches
+    fn same_block(&self, next: &OutboundEvent, field: &str) -> bool {
+        let (Some(current), Some(other)) = (self.payload.as_object(), next.payload.as_object())
+        else {
+            return false;
+        };
+        current.len() == other.len()
+            && current
+                .iter()
+                .all(|(key, value)| key == field || other.get(key) == Some(value))
+    }
+}
+
+/// Counters for one session's event stream (across respawns)
+#[derive(Debug, Default)]
+pub struct EventMetrics {
+    received: AtomicU64,
+    emitted: AtomicU64,
+    merged: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Point-in-time copy of [`EventMetrics`] for the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventMetricsSnapshot {
+    /// Events produced by the stream parser
+    pub received: u64,
+    /// Events actually sent to the frontend
+    pub emitted: u64,
+    /// Deltas folded into a preceding delta
+    pub merged: u64,
+    /// Raw mirror events skipped because mirroring is off or the channel was full
+    pub dropped: u64,
+}
+
+impl EventMetrics {
+    pub fn snapshot(&self) -> EventMetricsSnapshot {
+        EventMetricsSnapshot {
+            received: self.received.load(Ordering::Relaxed),
+            emitted: self.emitted.load(Ordering::Relaxed),
+            merged: self.merged.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Per-session emission settings and counters, shared with the live pipeline
+#[derive(Debug, Default, Clone)]
+pub struct SessionEvents {
+    /// Whether raw `claude:stream_event` mirroring is on
+    pub mirror_raw: Arc<AtomicBool>,
+    pub metrics: Arc<EventMetrics>,
+}
+
+enum Command {
+    Event(OutboundEvent),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Bounded, coalescing path from a session's stdout reader to the frontend.
+///
+/// Adjacent text and tool-input deltas arriving within [`COALESCE_WINDOW`]
+/// are merged into one event. All other events keep their order relative to
+/// each other and to the deltas. Raw mirror events are optional and have an
+/// order of their own: they may overtake a delta that is still being merged,
+/// and they are the only events dropped when the channel is full.
+pub struct EventPipeline {
+    tx: mpsc::Sender<Command>,
+    settings: SessionEvents,
+    task: JoinHandle<()>,
+}
+
+impl EventPipeline {
+    pub fn spawn(host: Host, settings: SessionEvents) -> Self {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let task = tokio::spawn(run(rx, host, settings.metrics.clone()));
+        Self { tx, settings, task }
+    }
+
+    /// Queue an event, waiting for room in the channel (raw events never wait)
+    pub async fn send(&self, event: OutboundEvent) {
+        let metrics = &self.settings.metrics;
+        metrics.received.fetch_add(1, Ordering::Relaxed);
+
+        if event.is_raw() {
+            let sent = self.settings.mirror_raw.load(Ordering::Relaxed)
+                && self.tx.try_send(Command::Event(event)).is_ok();
+            if !sent {
+                metrics.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            return;
+        }
+        let _ = self.tx.send(Command::Event(event)).await;
+    }
+
+    /// Wait until every event sent so far has been emitted, so events the
+    /// caller emits next (e.g. a status change) can't overtake them
+    pub async fn flush(&self) {
+        let (ack, done) = oneshot::channel();
+        if self.tx.send(Command::Flush(ack)).await.is_ok() {
+            let _ = done.await;
+        }
+    }
+
+    /// Emit whatever is still buffered and stop the emitter task
+    pub async fn close(self) {
+        drop(self.tx);
+        let _ = self.task.await;
+    }
+}
+
+async fn run(mut rx: mpsc::Receiver<Command>, host: Host, metrics: Arc<EventMetrics>) {
+    let emit = |event: OutboundEvent| {
+        let _ = host.emit(event.name, event.payload);
+        metrics.emitted.fetch_add(1, Ordering::Relaxed);
+    };
+    let mut pending: Option<(OutboundEvent, Instant)> = None;
+
+    loop {
+        let command = match &pending {
+            Some((_, deadline)) => match tokio::time::timeout_at(*deadline, rx.recv()).await {
+                Ok(command) => command,
+                Err(_) => {
+                    if let Some((event, _)) = pending.take() {
+                        emit(event);
+                    }
+                    continue;
+                }
+            },
+            None => rx.recv().await,
+        };
+
+        match command {
+            None => {
+                if let Some((event, _)) = pending.take() {
+                    emit(event);
+                }
+                return;
+            }
+            Some(Command::Flush(ack)) => {
+                if let Some((event, _)) = pending.take() {
+                    emit(event);
+                }
+                let _ = ack.send(());
+            }
+            Some(Command::Event(event)) if event.is_raw() => emit(event),
+            Some(Command::Event(event)) => {
+                if let Some((current, _)) = pending.as_mut() {
+                    if current.try_merge(&event) {
+                        metrics.merged.fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    }
+                }
+                if let Some((current, _)) = pending.take() {
+                    emit(current);
+                }
+                if event.merge_field().is_some() {
+                    pending = Some((event, Instant::now() + COALESCE_WINDOW));
+                } else {
+                    emit(event);
+                }
+            }
+        }
+    }
+}
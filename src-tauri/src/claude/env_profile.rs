@@ -0,0 +1,293 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+/// Shown instead of a secret value; sending it back keeps the stored value
+pub const MASKED_VALUE: &str = "••••••••";
+
+/// Key fragments that mark a variable as secret even if the user didn't
+const SECRET_KEY_HINTS: &[&str] = &["KEY", "TOKEN", "SECRET", "PASSWORD", "CREDENTIAL"];
+
+/// One environment variable of a profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvVar {
+    pub key: String,
+    pub value: String,
+    /// Masked whenever the profile is sent to the frontend
+    #[serde(default)]
+    pub secret: bool,
+}
+
+/// A named set of environment variables applied to agent processes, e.g. a
+/// base-URL override pointing at a local proxy, a different config dir and
+/// extra PATH entries.
+///
+/// A session uses the profile named in its launch settings, or else the
+/// profile set as its project's default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvProfile {
+    pub id: String,
+    pub name: String,
+    /// Project whose sessions use this profile unless they pick another
+    pub project_path: Option<String>,
+    pub vars: Vec<EnvVar>,
+    /// Directories put in front of `PATH`
+    pub path_prepend: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// The user-editable part of a profile
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvProfileInput {
+    pub name: String,
+    pub project_path: Option<String>,
+    #[serde(default)]
+    pub vars: Vec<EnvVar>,
+    #[serde(default)]
+    pub path_prepend: Vec<String>,
+}
+
+impl EnvProfile {
+    /// Build a profile from user input. Secret values left masked are taken
+    /// from `existing`, the stored version of the same profile.
+    pub fn from_input(
+        id: String,
+        input: EnvProfileInput,
+        existing: Option<&EnvProfile>,
+        now: String,
+    ) -> Result<Self, String> {
+        let name = input.name.trim().to_string();
+        if name.is_empty() {
+            return Err("Environment profile needs a name".to_string());
+        }
+
+        let mut vars = Vec::with_capacity(input.vars.len());
+        for mut var in input.vars {
+            var.key = var.key.trim().to_string();
+            validate_key(&var.key)?;
+            if vars.iter().any(|v: &EnvVar| v.key == var.key) {
+                return Err(format!("{} is set twice", var.key));
+            }
+            var.secret = var.secret || looks_secret(&var.key);
+            if var.value == MASKED_VALUE {
+                var.value = existing
+                    .and_then(|p| p.vars.iter().find(|v| v.key == var.key && v.secret))
+                    .map(|v| v.value.clone())
+                    .ok_or_else(|| format!("Enter a value for {}", var.key))?;
+            }
+            if var.value.contains('\0') {
+                return Err(format!("Value of {} contains a NUL byte", var.key));
+            }
+            vars.push(var);
+        }
+
+        let path_prepend = input
+            .path_prepend
+            .into_iter()
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect();
+
+        Ok(Self {
+            id,
+            name,
+            project_path: input.project_path.filter(|p| !p.is_empty()),
+            vars,
+            path_prepend,
+            created_at: existing
+                .map(|p| p.created_at.clone())
+                .unwrap_or(now.clone()),
+            updated_at: now,
+        })
+    }
+
+    /// A copy that is safe to send to the frontend
+    pub fn masked(&self) -> Self {
+        let mut profile = self.clone();
+        for var in profile.vars.iter_mut().filter(|v| v.secret) {
+            var.value = MASKED_VALUE.to_string();
+        }
+        profile
+    }
+
+    /// Set the profile's variables on a command about to be spawned
+    pub fn apply(&self, cmd: &mut Command) -> Result<(), String> {
+        for var in &self.vars {
+            cmd.env(&var.key, &var.value);
+        }
+        if self.path_prepend.is_empty() {
+            return Ok(());
+        }
+
+        // Prepend to the profile's own PATH if it sets one
+        let base = self
+            .vars
+            .iter()
+            .find(|v| v.key == "PATH")
+            .map(|v| OsString::from(&v.value))
+            .or_else(|| std::env::var_os("PATH"))
+            .unwrap_or_default();
+        let entries = self
+            .path_prepend
+            .iter()
+            .map(|p| expand_home(p))
+            .chain(std::env::split_paths(&base));
+        let path = std::env::join_paths(entries)
+            .map_err(|e| format!("Invalid PATH entry in profile '{}': {}", self.name, e))?;
+        cmd.env("PATH", path);
+        Ok(())
+    }
+}
+
+fn validate_key(key: &str) -> Result<(), String> {
+    let valid = !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{}' is not a valid environment variable name",
+            key
+        ))
+    }
+}
+
+fn looks_secret(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    SECRET_KEY_HINTS.iter().any(|hint| key.contains(hint))
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(key: &str, value: &str) -> EnvVar {
+        EnvVar {
+            key: key.to_string(),
+            value: value.to_string(),
+            secret: false,
+        }
+    }
+
+    fn input(vars: Vec<EnvVar>, path_prepend: &[&str]) -> EnvProfileInput {
+        EnvProfileInput {
+            name: "proxy".to_string(),
+            project_path: None,
+            vars,
+            path_prepend: path_prepend.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    fn build(input: EnvProfileInput, existing: Option<&EnvProfile>) -> Result<EnvProfile, String> {
+        EnvProfile::from_input("p1".to_string(), input, existing, "now".to_string())
+    }
+
+    /// The value `apply` sets for `key` on a command
+    fn applied(profile: &EnvProfile, key: &str) -> Option<OsString> {
+        let mut cmd = Command::new("true");
+        profile.apply(&mut cmd).unwrap();
+        cmd.as_std()
+            .get_envs()
+            .find(|(k, _)| *k == key)
+            .and_then(|(_, v)| v.map(OsString::from))
+    }
+
+    #[test]
+    fn keys_must_be_valid_variable_names() {
+        for key in ["PATH", "ANTHROPIC_BASE_URL", "_private", "x1"] {
+            assert!(validate_key(key).is_ok(), "{} should be valid", key);
+        }
+        for key in ["", "1PASSWORD", "MY-VAR", "A B", "A=B", "KEY\u{e9}"] {
+            assert!(validate_key(key).is_err(), "{:?} should be invalid", key);
+        }
+    }
+
+    #[test]
+    fn keys_naming_credentials_are_secret() {
+        for key in [
+            "ANTHROPIC_API_KEY",
+            "github_token",
+            "DB_PASSWORD",
+            "AWS_SECRET_ACCESS_KEY",
+        ] {
+            assert!(looks_secret(key), "{} should be secret", key);
+        }
+        for key in ["ANTHROPIC_BASE_URL", "CLAUDE_CONFIG_DIR", "PATH"] {
+            assert!(!looks_secret(key), "{} should not be secret", key);
+        }
+
+        let profile = build(input(vec![var("API_KEY", "sk-123")], &[]), None).unwrap();
+        assert!(profile.vars[0].secret);
+        assert_eq!(profile.masked().vars[0].value, MASKED_VALUE);
+        assert_eq!(profile.vars[0].value, "sk-123");
+    }
+
+    #[test]
+    fn masked_values_keep_the_stored_secret() {
+        let stored = build(input(vec![var("API_KEY", "sk-123")], &[]), None).unwrap();
+        let resent = input(vec![var("API_KEY", MASKED_VALUE)], &[]);
+        let updated = build(resent.clone(), Some(&stored)).unwrap();
+        assert_eq!(updated.vars[0].value, "sk-123");
+
+        // Nothing stored to keep: a new profile, or a variable that wasn't secret
+        let err = build(resent, None).unwrap_err();
+        assert_eq!(err, "Enter a value for API_KEY");
+        let plain = build(input(vec![var("BASE_URL", "http://proxy")], &[]), None).unwrap();
+        let err = build(
+            input(vec![var("BASE_URL", MASKED_VALUE)], &[]),
+            Some(&plain),
+        )
+        .unwrap_err();
+        assert_eq!(err, "Enter a value for BASE_URL");
+    }
+
+    #[test]
+    fn path_entries_are_prepended() {
+        let profile = build(
+            input(Vec::new(), &["/opt/proxy/bin", " ", "/usr/local/go/bin"]),
+            None,
+        )
+        .unwrap();
+        let path = applied(&profile, "PATH").unwrap();
+        let entries: Vec<PathBuf> = std::env::split_paths(&path).collect();
+        assert_eq!(entries[0], PathBuf::from("/opt/proxy/bin"));
+        assert_eq!(entries[1], PathBuf::from("/usr/local/go/bin"));
+        let inherited = std::env::var_os("PATH").unwrap_or_default();
+        assert_eq!(
+            entries[2..].to_vec(),
+            std::env::split_paths(&inherited).collect::<Vec<_>>()
+        );
+
+        // In front of the profile's own PATH, if it sets one
+        let join = |entries: &[&str]| std::env::join_paths(entries).unwrap();
+        let own_path = join(&["/bin", "/usr/bin"]).into_string().unwrap();
+        let own = build(
+            input(vec![var("PATH", &own_path)], &["/opt/proxy/bin"]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            applied(&own, "PATH").unwrap(),
+            join(&["/opt/proxy/bin", "/bin", "/usr/bin"])
+        );
+
+        // No entries leaves PATH alone
+        let none = build(input(vec![var("BASE_URL", "http://proxy")], &[]), None).unwrap();
+        assert_eq!(applied(&none, "PATH"), None);
+        assert_eq!(applied(&none, "BASE_URL").unwrap(), "http://proxy");
+    }
+}
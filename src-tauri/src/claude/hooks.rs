@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::types::SystemEvent;
+
+/// Hook events that run around a single tool call
+const TOOL_HOOK_EVENTS: &[&str] = &[
+    "PreToolUse",
+    "PostToolUse",
+    "PostToolUseFailure",
+    "PermissionRequest",
+];
+
+/// Exit code with which a hook blocks the action it ran for
+const BLOCKING_EXIT_CODE: i32 = 2;
+
+/// A CLI hook starting or finishing, from the `hook_started` and
+/// `hook_response` system events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookEvent {
+    /// Pairs a hook's start with its response, when the CLI provides it
+    pub hook_id: Option<String>,
+    pub phase: HookPhase,
+    /// Lifecycle event the hook ran for, e.g. `PreToolUse`
+    pub hook_event: String,
+    /// Hook as configured, e.g. `PreToolUse:Bash`
+    pub hook_name: String,
+    /// Tool whose call triggered the hook, for tool hooks
+    pub tool_name: Option<String>,
+    pub exit_code: Option<i32>,
+    /// `success`, `error` or `cancelled`, once finished
+    pub outcome: Option<String>,
+    pub stdout: String,
+    pub stderr: String,
+    /// What the hook decided about the action, if it decided anything
+    pub decision: Option<HookDecision>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookPhase {
+    Started,
+    Finished,
+}
+
+/// A hook's verdict, e.g. `deny` from a PreToolUse hook or `block` from a
+/// hook that exited with status 2
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookDecision {
+    /// `allow`, `deny`, `ask`, `block`, `approve` or `stop`, as the hook said it
+    pub decision: String,
+    pub reason: Option<String>,
+}
+
+impl HookEvent {
+    /// Read a hook system event; `None` for other subtypes
+    pub fn from_system_event(event: &SystemEvent) -> Option<Self> {
+        let (phase, run) = match event {
+            SystemEvent::HookStarted(run) => (HookPhase::Started, run),
+            SystemEvent::HookResponse(run) => (HookPhase::Finished, run),
+            _ => return None,
+        };
+
+        let hook_name = run.hook_name.clone();
+        let hook_event = run
+            .hook_event
+            .clone()
+            .or_else(|| hook_name.split(':').next().map(String::from))
+            .unwrap_or_default();
+        let tool_name = run.tool_name.clone().or_else(|| {
+            let (_, matcher) = hook_name.split_once(':')?;
+            TOOL_HOOK_EVENTS
+                .contains(&hook_event.as_str())
+                .then(|| matcher.to_string())
+        });
+        let stdout = run
+            .stdout
+            .clone()
+            .or_else(|| run.output.clone())
+            .unwrap_or_default();
+        let stderr = run.stderr.clone().unwrap_or_default();
+        let decision = match phase {
+            HookPhase::Started => None,
+            HookPhase::Finished => decision_of(run.exit_code, &stdout, &stderr),
+        };
+
+        Some(Self {
+            hook_id: run.hook_id.clone(),
+            phase,
+            hook_event,
+            hook_name,
+            tool_name,
+            exit_code: run.exit_code,
+            outcome: run.outcome.clone(),
+            stdout,
+            stderr,
+            decision,
+        })
+    }
+
+    /// Whether the hook stopped the action it ran for
+    pub fn blocked(&self) -> bool {
+        self.decision
+            .as_ref()
+            .is_some_and(|d| matches!(d.decision.as_str(), "deny" | "block" | "stop"))
+    }
+}
+
+/// Work out a finished hook's decision from its exit status and its JSON
+/// output, if it printed any
+fn decision_of(exit_code: Option<i32>, stdout: &str, stderr: &str) -> Option<HookDecision> {
+    if exit_code == Some(BLOCKING_EXIT_CODE) {
+        return Some(HookDecision {
+            decision: "block".to_string(),
+            reason: Some(stderr.trim().to_string()).filter(|r| !r.is_empty()),
+        });
+    }
+
+    let output: Value = serde_json::from_str(stdout.trim()).ok()?;
+    let reason = |key: &str, within: &Value| within[key].as_str().map(String::from);
+    let specific = &output["hookSpecificOutput"];
+    if let Some(decision) = specific["permissionDecision"].as_str() {
+        return Some(HookDecision {
+            decision: decision.to_string(),
+            reason: reason("permissionDecisionReason", specific),
+        });
+    }
+    if let Some(decision) = output["decision"].as_str() {
+        return Some(HookDecision {
+            decision: decision.to_string(),
+            reason: reason("reason", &output),
+        });
+    }
+    if output["continue"] == false {
+        return Some(HookDecision {
+            decision: "stop".to_string(),
+            reason: reason("stopReason", &output),
+        });
+    }
+    None
+}
@@ -0,0 +1,58 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use sqlx::SqlitePool;
+use tauri::Emitter;
+
+/// What the orchestration layer needs from the app around it: a place to
+/// send events and the app database.
+///
+/// The running app passes its `tauri::AppHandle`. Integration tests plug in
+/// a recorder instead, so `ProcessManager` and `StreamParser` can run against
+/// the fake CLI without a webview.
+pub trait AgentHost: Send + Sync + 'static {
+    /// Send an event to the frontend
+    fn emit(&self, event: &str, payload: serde_json::Value) -> Result<(), String>;
+
+    /// The app database (fails when the host has none)
+    fn database(&self) -> Pin<Box<dyn Future<Output = Result<SqlitePool, String>> + Send + '_>>;
+}
+
+/// Shared handle to the host, passed through the orchestration layer
+pub type Host = Arc<dyn AgentHost>;
+
+/// Wrap the app handle for the orchestration layer
+pub fn tauri_host(app: tauri::AppHandle) -> Host {
+    Arc::new(app)
+}
+
+impl AgentHost for tauri::AppHandle {
+    fn emit(&self, event: &str, payload: serde_json::Value) -> Result<(), String> {
+        Emitter::emit(self, event, payload).map_err(|e| format!("Failed to emit {}: {}", event, e))
+    }
+
+    fn database(&self) -> Pin<Box<dyn Future<Output = Result<SqlitePool, String>> + Send + '_>> {
+        Box::pin(crate::db::pool(self))
+    }
+}
+
+/// Host for unit tests: events are dropped and the database is `pool`
+#[cfg(test)]
+pub(crate) fn test_host(pool: SqlitePool) -> Host {
+    struct TestHost(SqlitePool);
+
+    impl AgentHost for TestHost {
+        fn emit(&self, _event: &str, _payload: serde_json::Value) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn database(
+            &self,
+        ) -> Pin<Box<dyn Future<Output = Result<SqlitePool, String>> + Send + '_>> {
+            Box::pin(async { Ok(self.0.clone()) })
+        }
+    }
+
+    Arc::new(TestHost(pool))
+}
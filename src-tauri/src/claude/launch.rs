@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+use super::locator::CliInfo;
+
+/// How the CLI asks for permission before using tools
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionMode {
+    Default,
+    AcceptEdits,
+    Plan,
+    BypassPermissions,
+}
+
+impl PermissionMode {
+    /// Value for `--permission-mode`
+    pub fn as_cli_arg(&self) -> &'static str {
+        match self {
+            PermissionMode::Default => "default",
+            PermissionMode::AcceptEdits => "acceptEdits",
+            PermissionMode::Plan => "plan",
+            PermissionMode::BypassPermissions => "bypassPermissions",
+        }
+    }
+}
+
+/// Per-session CLI launch settings.
+///
+/// Stored by the `ProcessManager` for each session and reapplied every time
+/// the session's process is (re)spawned.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LaunchSettings {
+    /// Agent that runs the session, by provider ID; the Claude CLI if unset
+    pub provider: Option<String>,
+    /// Environment profile ID; the project's default profile if unset
+    pub env_profile: Option<String>,
+    pub model: Option<String>,
+    pub fallback_model: Option<String>,
+    pub permission_mode: Option<PermissionMode>,
+    /// Tools (or tool patterns like `Bash(git:*)`) allowed without prompting
+    pub allowed_tools: Vec<String>,
+    pub disallowed_tools: Vec<String>,
+    /// Extra instructions appended to the default system prompt
+    pub append_system_prompt: Option<String>,
+    /// Directories outside the project the agent may access
+    pub additional_dirs: Vec<String>,
+    pub max_turns: Option<u32>,
+}
+
+impl LaunchSettings {
+    /// Build the CLI arguments for these settings.
+    ///
+    /// Fails if a setting needs a flag this CLI version doesn't have, rather
+    /// than silently dropping e.g. a tool restriction.
+    pub fn to_args(&self, cli: &CliInfo) -> Result<Vec<String>, String> {
+        let mut args = Vec::new();
+        let mut push = |flag: &str, values: &[String]| -> Result<(), String> {
+            if !cli.supports(flag) {
+                return Err(format!(
+                    "Claude CLI {} does not support {} — please update it",
+                    cli.version.as_deref().unwrap_or("(unknown version)"),
+                    flag
+                ));
+            }
+            args.push(flag.to_string());
+            args.extend(values.iter().cloned());
+            Ok(())
+        };
+
+        if let Some(ref model) = self.model {
+            push("--model", std::slice::from_ref(model))?;
+        }
+        if let Some(ref model) = self.fallback_model {
+            push("--fallback-model", std::slice::from_ref(model))?;
+        }
+        if let Some(mode) = self.permission_mode {
+            push("--permission-mode", &[mode.as_cli_arg().to_string()])?;
+        }
+        if !self.allowed_tools.is_empty() {
+            push("--allowedTools", &self.allowed_tools)?;
+        }
+        if !self.disallowed_tools.is_empty() {
+            push("--disallowedTools", &self.disallowed_tools)?;
+        }
+        if let Some(ref prompt) = self.append_system_prompt {
+            push("--append-system-prompt", std::slice::from_ref(prompt))?;
+        }
+        if !self.additional_dirs.is_empty() {
+            push("--add-dir", &self.additional_dirs)?;
+        }
+        if let Some(max_turns) = self.max_turns {
+            push("--max-turns", &[max_turns.to_string()])?;
+        }
+
+        Ok(args)
+    }
+}
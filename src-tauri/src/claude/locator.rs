@@ -0,0 +1,361 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+/// Oldest CLI release with the stream-json protocol we speak
+const MIN_CLI_VERSION: (u32, u32, u32) = (1, 0, 0);
+
+/// Flags every supported CLI must accept
+const REQUIRED_FLAGS: &[&str] = &["--output-format", "--resume", "--verbose"];
+
+/// Upper bound for `--version` / `--help` probes so a broken install can't hang spawning
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+const INSTALL_HINT: &str = "Install via Homebrew (brew install claude-code) or npm (npm install -g @anthropic-ai/claude-code)";
+
+/// A probed Claude CLI binary
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CliInfo {
+    pub path: String,
+    /// Raw `claude --version` output, e.g. "1.0.98 (Claude Code)"
+    pub version: Option<String>,
+    /// Long flags listed in `claude --help`
+    pub flags: BTreeSet<String>,
+}
+
+impl CliInfo {
+    /// Whether this CLI version accepts the given long flag (e.g. `--input-format`)
+    pub fn supports(&self, flag: &str) -> bool {
+        self.flags.contains(flag)
+    }
+
+    /// Parsed `major.minor.patch` from the version string
+    pub fn semver(&self) -> Option<(u32, u32, u32)> {
+        parse_semver(self.version.as_deref()?)
+    }
+
+    /// Reject CLIs that are too old or are missing flags we rely on
+    pub fn check_supported(&self) -> Result<(), String> {
+        if let Some(version) = self.semver() {
+            if version < MIN_CLI_VERSION {
+                let (major, minor, patch) = MIN_CLI_VERSION;
+                return Err(format!(
+                    "Claude CLI {}.{}.{} at {} is too old — OpenClaudgents needs {}.{}.{} or newer. {}",
+                    version.0, version.1, version.2, self.path, major, minor, patch, INSTALL_HINT
+                ));
+            }
+        }
+
+        let missing: Vec<&str> = REQUIRED_FLAGS
+            .iter()
+            .copied()
+            .filter(|flag| !self.supports(flag))
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "Claude CLI at {} does not support {} — please update it. {}",
+                self.path,
+                missing.join(", "),
+                INSTALL_HINT
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Single place for finding and probing the `claude` binary.
+///
+/// Search order: the user override (persisted in the `settings` table), then
+/// every directory on `PATH`, then well-known install locations (GUI apps
+/// often don't inherit the shell `PATH`). Probe results are cached per path.
+#[derive(Default)]
+pub struct CliLocator {
+    override_path: Mutex<Option<String>>,
+    cache: Mutex<HashMap<String, CliInfo>>,
+}
+
+impl CliLocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set or clear the user-chosen CLI path
+    pub async fn set_override(&self, path: Option<String>) {
+        *self.override_path.lock().await = path;
+    }
+
+    pub async fn override_path(&self) -> Option<String> {
+        self.override_path.lock().await.clone()
+    }
+
+    /// Find the CLI path without probing it
+    pub async fn find_path(&self) -> Option<String> {
+        if let Some(path) = self.override_path().await {
+            return Some(path);
+        }
+
+        candidate_paths()
+            .into_iter()
+            .find(|p| is_executable(p))
+            .map(|p| p.to_string_lossy().to_string())
+    }
+
+    /// Locate and probe the CLI, failing if it is missing or unsupported.
+    /// `provided` takes precedence over the normal search order.
+    pub async fn locate(&self, provided: Option<String>) -> Result<CliInfo, String> {
+        let path = match provided {
+            Some(path) => path,
+            None => self
+                .find_path()
+                .await
+                .ok_or_else(|| format!("Claude CLI not found. {}", INSTALL_HINT))?,
+        };
+
+        let info = self.probe(&path).await?;
+        info.check_supported()?;
+        Ok(info)
+    }
+
+    /// Run `--version` and `--help` for a CLI path, reusing a cached result
+    pub async fn probe(&self, path: &str) -> Result<CliInfo, String> {
+        if let Some(info) = self.cache.lock().await.get(path) {
+            return Ok(info.clone());
+        }
+
+        let version_output = run_probe(path, "--version").await?;
+        let help_output = run_probe(path, "--help").await?;
+
+        let version = version_output
+            .lines()
+            .next()
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty());
+        let info = CliInfo {
+            path: path.to_string(),
+            version,
+            flags: parse_flags(&help_output),
+        };
+
+        log::info!(
+            "[cli-locator] {} version={:?}, {} flags",
+            path,
+            info.version,
+            info.flags.len()
+        );
+
+        self.cache
+            .lock()
+            .await
+            .insert(path.to_string(), info.clone());
+        Ok(info)
+    }
+
+    /// Forget cached probe results (e.g. after the user updates the CLI)
+    pub async fn clear_cache(&self) {
+        self.cache.lock().await.clear();
+    }
+}
+
+/// PATH entries followed by well-known install locations, without duplicates
+fn candidate_paths() -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = std::env::var_os("PATH")
+        .map(|path| {
+            std::env::split_paths(&path)
+                .map(|dir| dir.join("claude"))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    candidates.extend(
+        [
+            "/opt/homebrew/bin/claude",
+            "/usr/local/bin/claude",
+            "/usr/bin/claude",
+        ]
+        .iter()
+        .map(PathBuf::from),
+    );
+
+    if let Some(home) = dirs::home_dir() {
+        candidates.push(home.join(".claude").join("local").join("claude"));
+        candidates.push(home.join(".npm").join("bin").join("claude"));
+        candidates.push(home.join(".local").join("bin").join("claude"));
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    candidates.retain(|p| seen.insert(p.clone()));
+    candidates
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+async fn run_probe(path: &str, arg: &str) -> Result<String, String> {
+    let mut cmd = Command::new(path);
+    cmd.arg(arg)
+        .env_remove("CLAUDECODE")
+        .env_remove("CLAUDE_CODE_ENTRY_POINT")
+        .kill_on_drop(true);
+
+    let output = tokio::time::timeout(PROBE_TIMEOUT, cmd.output())
+        .await
+        .map_err(|_| format!("`{} {}` timed out", path, arg))?
+        .map_err(|e| format!("Failed to run `{} {}`: {}", path, arg, e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "`{} {}` failed: {}",
+            path,
+            arg,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Collect every `--long-flag` mentioned in help output
+fn parse_flags(help: &str) -> BTreeSet<String> {
+    help.split(|c: char| c.is_whitespace() || c == ',' || c == '=' || c == '[' || c == '<')
+        .filter(|token| token.starts_with("--") && token.len() > 2)
+        .map(|token| {
+            token
+                .trim_end_matches(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+                .to_string()
+        })
+        .collect()
+}
+
+/// Parse the leading `major.minor.patch` of a version string
+fn parse_semver(version: &str) -> Option<(u32, u32, u32)> {
+    let token = version.split_whitespace().next()?;
+    let mut parts = token
+        .split(['.', '-', '+'])
+        .map(|p| p.parse::<u32>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().and_then(|p| p.ok()).unwrap_or(0);
+    let patch = parts.next().and_then(|p| p.ok()).unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `claude --help` of a 1.0.x release
+    const HELP: &str = include_str!("../../tests/fixtures/claude_help.txt");
+
+    fn info(version: &str, help: &str) -> CliInfo {
+        CliInfo {
+            path: "/usr/local/bin/claude".to_string(),
+            version: Some(version.to_string()),
+            flags: parse_flags(help),
+        }
+    }
+
+    #[test]
+    fn versions_are_parsed_from_version_output() {
+        assert_eq!(parse_semver("1.0.98 (Claude Code)"), Some((1, 0, 98)));
+        assert_eq!(parse_semver("2.0.14"), Some((2, 0, 14)));
+        assert_eq!(parse_semver("1.2"), Some((1, 2, 0)));
+        // Pre-release and build suffixes are dropped
+        assert_eq!(parse_semver("2.1.0-beta.3 (Claude Code)"), Some((2, 1, 0)));
+        assert_eq!(parse_semver("1.0.5+build.7"), Some((1, 0, 5)));
+    }
+
+    #[test]
+    fn garbage_version_output_has_no_version() {
+        assert_eq!(parse_semver(""), None);
+        assert_eq!(parse_semver("zsh: command not found: claude"), None);
+        assert_eq!(parse_semver("v1.0.98"), None);
+    }
+
+    #[test]
+    fn flags_are_collected_from_help_output() {
+        let flags = parse_flags(HELP);
+        for flag in [
+            "--verbose",
+            "--print",
+            "--output-format",
+            "--input-format",
+            "--resume",
+            "--allowedTools",
+            "--allowed-tools",
+            "--permission-mode",
+            "--fork-session",
+            "--help",
+        ] {
+            assert!(flags.contains(flag), "{} not found", flag);
+        }
+        // Mentions inside descriptions are trimmed of punctuation and values
+        let clean = |flag: &String| {
+            flag[2..]
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        };
+        assert!(flags.iter().all(clean), "{:?}", flags);
+        // Short flags aren't collected
+        assert!(!flags.contains("-p"));
+    }
+
+    #[test]
+    fn a_current_cli_is_supported() {
+        assert!(info("1.0.98 (Claude Code)", HELP).check_supported().is_ok());
+        assert!(info("2.1.0-beta.3 (Claude Code)", HELP)
+            .check_supported()
+            .is_ok());
+    }
+
+    #[test]
+    fn a_cli_below_the_minimum_is_rejected() {
+        let err = info("0.2.125 (Claude Code)", HELP)
+            .check_supported()
+            .unwrap_err();
+        assert!(err.contains("0.2.125"), "{}", err);
+        assert!(err.contains("too old"), "{}", err);
+    }
+
+    #[test]
+    fn a_cli_missing_a_flag_is_rejected() {
+        let help: String = HELP
+            .lines()
+            .filter(|line| !line.contains("--resume"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let cli = info("1.0.98 (Claude Code)", &help);
+        assert!(!cli.supports("--resume"));
+
+        let err = cli.check_supported().unwrap_err();
+        assert!(err.contains("does not support --resume "), "{}", err);
+    }
+
+    #[test]
+    fn garbage_output_is_rejected_for_its_missing_flags() {
+        let cli = info("Segmentation fault", "Segmentation fault (core dumped)");
+        assert_eq!(cli.semver(), None);
+        assert!(cli.flags.is_empty());
+
+        let err = cli.check_supported().unwrap_err();
+        assert!(
+            err.contains("--output-format, --resume, --verbose"),
+            "{}",
+            err
+        );
+    }
+}
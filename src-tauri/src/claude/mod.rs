@@ -1,5 +1,22 @@
 pub mod agent_teams;
+pub mod claude_cli;
+pub mod content;
+pub mod emitter;
+pub mod env_profile;
+pub mod hooks;
+pub mod host;
+pub mod launch;
+pub mod locator;
+pub mod orphans;
+pub mod permission_mcp;
+pub mod permissions;
 pub mod process;
+pub mod provider;
+pub mod queue;
+pub mod scheduler;
 pub mod session_store;
 pub mod stream_parser;
+pub mod turn_summary;
 pub mod types;
+pub mod usage;
+pub mod watchdog;
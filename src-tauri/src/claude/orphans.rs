@@ -0,0 +1,112 @@
+#[cfg(unix)]
+use std::time::Duration;
+
+#[cfg(unix)]
+use super::process::{process_alive, signal_group};
+
+/// How long an orphan gets to exit after each signal
+#[cfg(unix)]
+const ORPHAN_GRACE: Duration = Duration::from_secs(3);
+
+/// Stop a CLI process tree left running by a previous app instance that crashed.
+///
+/// Its stdio pipes died with that instance, so it can't be adopted back into
+/// a session — the session is resumed from its JSONL transcript instead. The
+/// CLI led its own process group, so the whole group gets SIGINT first (so
+/// the transcript is flushed), then SIGTERM and SIGKILL. Returns whether a
+/// live CLI process was found under `pid`.
+#[cfg(unix)]
+pub async fn reap(pid: u32) -> bool {
+    if !process_alive(pid) {
+        // Nothing can have taken over the PID, so a group by that ID is
+        // still ours — clear out whatever the agent left running
+        signal_group(pid, libc::SIGKILL);
+        return false;
+    }
+    if !is_claude_process(pid).await {
+        return false;
+    }
+
+    log::warn!("[orphans] reaping orphaned claude process {}", pid);
+    for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGKILL] {
+        signal_group(pid, signal);
+        if wait_until_gone(pid, ORPHAN_GRACE).await {
+            // Members that ignored the gentler signals
+            signal_group(pid, libc::SIGKILL);
+            return true;
+        }
+    }
+    log::warn!("[orphans] process {} survived SIGKILL", pid);
+    true
+}
+
+#[cfg(not(unix))]
+pub async fn reap(_pid: u32) -> bool {
+    false
+}
+
+/// Whether `pid` still looks like a stream-json CLI process.
+/// PIDs get reused, so anything else under that ID is left alone.
+#[cfg(unix)]
+async fn is_claude_process(pid: u32) -> bool {
+    let pid_arg = pid.to_string();
+    let output = tokio::process::Command::new("ps")
+        .args(["-o", "command=", "-p", &pid_arg])
+        .output()
+        .await;
+    match output {
+        Ok(output) => is_cli_command(&String::from_utf8_lossy(&output.stdout)),
+        Err(e) => {
+            log::warn!("[orphans] failed to inspect process {}: {}", pid, e);
+            false
+        }
+    }
+}
+
+/// Whether a command line is the CLI started for a session
+#[cfg(unix)]
+fn is_cli_command(command: &str) -> bool {
+    command.contains("claude") && command.contains("stream-json")
+}
+
+#[cfg(unix)]
+async fn wait_until_gone(pid: u32, timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while process_alive(pid) {
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    true
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_cli_command_lines_match() {
+        for command in [
+            "claude --output-format stream-json --verbose --input-format stream-json",
+            "node /opt/homebrew/bin/claude --output-format stream-json --verbose --resume 3f1c",
+            "/Users/me/.claude/local/node_modules/.bin/claude -p --output-format=stream-json\n",
+        ] {
+            assert!(is_cli_command(command), "{}", command);
+        }
+    }
+
+    #[test]
+    fn other_command_lines_dont_match() {
+        for command in [
+            "",
+            "sleep 30",
+            // An interactive CLI the user started themselves
+            "claude",
+            "vim /tmp/claude-notes.md",
+            "tail -f stream-json.log",
+        ] {
+            assert!(!is_cli_command(command), "{}", command);
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+/// First argument that makes the app binary run as the permission-prompt
+/// MCP server instead of the GUI
+pub const SERVER_ARG: &str = "--permission-mcp";
+
+/// Name the server is registered under in the CLI's MCP config
+const SERVER_NAME: &str = "openclaudgents";
+const TOOL_NAME: &str = "approve";
+/// Value for the CLI's `--permission-prompt-tool`
+pub const PERMISSION_TOOL: &str = "mcp__openclaudgents__approve";
+
+/// MCP revision answered when the client doesn't name one
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Environment the server is started with, set by the `PermissionBroker`
+pub(crate) const ADDR_ENV: &str = "OPENCLAUDGENTS_PERMISSION_ADDR";
+pub(crate) const TOKEN_ENV: &str = "OPENCLAUDGENTS_PERMISSION_TOKEN";
+pub(crate) const SESSION_ENV: &str = "OPENCLAUDGENTS_SESSION_ID";
+
+/// How the CLI starts the permission-prompt server for one session
+#[derive(Debug, Clone)]
+pub struct PermissionServer {
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+}
+
+impl PermissionServer {
+    /// The server as a `--mcp-config` document
+    pub fn mcp_config(&self) -> Value {
+        let env: serde_json::Map<String, Value> = self
+            .env
+            .iter()
+            .map(|(key, value)| (key.clone(), Value::from(value.as_str())))
+            .collect();
+        serde_json::json!({
+            "mcpServers": {
+                SERVER_NAME: {
+                    "type": "stdio",
+                    "command": self.command,
+                    "args": self.args,
+                    "env": env,
+                }
+            }
+        })
+    }
+
+    /// Write the `--mcp-config` document to a file only the current user can
+    /// read. The server's environment holds the broker token, which must not
+    /// reach the CLI's argv where other local users could see it.
+    pub fn write_mcp_config(&self) -> Result<McpConfigFile, String> {
+        let path =
+            std::env::temp_dir().join(format!("openclaudgents-mcp-{}.json", uuid::Uuid::new_v4()));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options
+            .open(&path)
+            .map_err(|e| format!("Failed to create MCP config {}: {}", path.display(), e))?;
+        // Removed on drop from here on, also if the write fails
+        let config = McpConfigFile { path };
+        file.write_all(self.mcp_config().to_string().as_bytes())
+            .map_err(|e| format!("Failed to write MCP config: {}", e))?;
+        Ok(config)
+    }
+}
+
+/// An MCP config file written for one process, removed when dropped
+#[derive(Debug)]
+pub struct McpConfigFile {
+    path: PathBuf,
+}
+
+impl McpConfigFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for McpConfigFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Run the permission-prompt MCP server on stdin/stdout until the CLI
+/// closes stdin, returning the process exit code.
+///
+/// The CLI calls the `approve` tool before each tool use that needs
+/// permission. Every call is forwarded over a loopback connection to the
+/// app's `PermissionBroker`, which asks the user (or applies a rule or the
+/// timeout default) and answers with the decision the CLI expects:
+/// `{"behavior":"allow","updatedInput":{...}}` or
+/// `{"behavior":"deny","message":"..."}`.
+pub fn serve() -> i32 {
+    let Some(bridge) = Bridge::from_env() else {
+        eprintln!("{}: {} and {} must be set", SERVER_ARG, ADDR_ENV, TOKEN_ENV);
+        return 2;
+    };
+
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = match serde_json::from_str::<Value>(&line) {
+            // Notifications (no ID) need no reply
+            Ok(message) => match message.get("id").cloned() {
+                Some(id) => match handle(&bridge, &message) {
+                    Ok(result) => {
+                        serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": result })
+                    }
+                    Err((code, error)) => serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": code, "message": error },
+                    }),
+                },
+                None => continue,
+            },
+            Err(e) => serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": -32700, "message": format!("Parse error: {}", e) },
+            }),
+        };
+        if writeln!(stdout, "{}", reply)
+            .and_then(|_| stdout.flush())
+            .is_err()
+        {
+            break;
+        }
+    }
+    0
+}
+
+fn handle(bridge: &Bridge, message: &Value) -> Result<Value, (i64, String)> {
+    let params = &message["params"];
+    match message["method"].as_str().unwrap_or("") {
+        "initialize" => Ok(serde_json::json!({
+            "protocolVersion": params["protocolVersion"].as_str().unwrap_or(PROTOCOL_VERSION),
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": SERVER_NAME, "version": env!("CARGO_PKG_VERSION") },
+        })),
+        "ping" => Ok(serde_json::json!({})),
+        "tools/list" => Ok(serde_json::json!({ "tools": [tool_definition()] })),
+        "tools/call" => {
+            if params["name"] != TOOL_NAME {
+                return Err((-32602, format!("Unknown tool {}", params["name"])));
+            }
+            let decision = bridge.ask(&params["arguments"]);
+            Ok(serde_json::json!({
+                "content": [{ "type": "text", "text": decision.to_string() }],
+            }))
+        }
+        other => Err((-32601, format!("Method not found: {}", other))),
+    }
+}
+
+fn tool_definition() -> Value {
+    serde_json::json!({
+        "name": TOOL_NAME,
+        "description": "Ask the OpenClaudgents user whether a tool call may run",
+        "inputSchema": {
+            "type": "object",
+            "properties": {
+                "tool_name": { "type": "string" },
+                "input": { "type": "object" },
+                "tool_use_id": { "type": "string" },
+            },
+            "required": ["tool_name", "input"],
+        },
+    })
+}
+
+/// Connection details of the broker in the app process
+struct Bridge {
+    addr: String,
+    token: String,
+    session_id: String,
+}
+
+impl Bridge {
+    fn from_env() -> Option<Self> {
+        Some(Self {
+            addr: std::env::var(ADDR_ENV).ok()?,
+            token: std::env::var(TOKEN_ENV).ok()?,
+            session_id: std::env::var(SESSION_ENV).unwrap_or_default(),
+        })
+    }
+
+    /// Forward one permission request and wait for the decision. Denies
+    /// the tool call if the app can't be reached.
+    fn ask(&self, arguments: &Value) -> Value {
+        let request = serde_json::json!({
+            "token": self.token,
+            "sessionId": self.session_id,
+            "toolName": arguments["tool_name"].as_str().unwrap_or("unknown"),
+            "toolUseId": arguments["tool_use_id"].as_str(),
+            "input": arguments.get("input").cloned().unwrap_or(Value::Null),
+        });
+        self.exchange(&request).unwrap_or_else(|e| {
+            serde_json::json!({
+                "behavior": "deny",
+                "message": format!("Couldn't reach OpenClaudgents to ask for approval: {}", e),
+            })
+        })
+    }
+
+    fn exchange(&self, request: &Value) -> io::Result<Value> {
+        let mut stream = TcpStream::connect(&self.addr)?;
+        writeln!(stream, "{}", request)?;
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply)?;
+        serde_json::from_str(&reply).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
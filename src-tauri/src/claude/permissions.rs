@@ -0,0 +1,654 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use subtle::ConstantTimeEq;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{oneshot, Mutex};
+
+use super::host::Host;
+use super::permission_mcp::{self, PermissionServer};
+use crate::db;
+
+/// Whether tool approvals are routed to the app and what happens when
+/// nobody answers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionSettings {
+    /// Ask the app before tool calls; when off, the CLI's permission mode
+    /// alone decides. Applies to processes spawned afterwards. Off by
+    /// default, as a request nobody answers holds its call until the timeout.
+    pub enabled: bool,
+    /// How long a request waits for the user
+    pub timeout_secs: u64,
+    /// Answer given once a request times out
+    #[serde(default)]
+    pub on_timeout: PermissionBehavior,
+}
+
+impl Default for PermissionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_secs: 300,
+            on_timeout: PermissionBehavior::default(),
+        }
+    }
+}
+
+impl PermissionSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.timeout_secs == 0 {
+            return Err("Approval timeout must be at least 1 second".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionBehavior {
+    Allow,
+    #[default]
+    Deny,
+}
+
+/// The user's answer to a permission request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "behavior", rename_all = "camelCase")]
+pub enum PermissionResponse {
+    /// Run the tool — with `updated_input` instead of the requested input
+    /// if the user edited it. `always` allows the tool in this project from
+    /// now on.
+    #[serde(rename_all = "camelCase")]
+    Allow {
+        #[serde(default)]
+        updated_input: Option<Value>,
+        #[serde(default)]
+        always: bool,
+    },
+    Deny {
+        #[serde(default)]
+        message: Option<String>,
+    },
+}
+
+/// How a permission request was settled
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    User,
+    /// An "always allow" rule covered it
+    Rule,
+    /// Nobody answered in time; the policy default applied
+    Timeout,
+    /// The agent went away before an answer
+    Cancelled,
+}
+
+/// A tool the user chose to always allow in one project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionRule {
+    pub id: String,
+    pub project_path: String,
+    pub tool_name: String,
+    pub created_at: String,
+}
+
+impl PermissionRule {
+    fn covers(&self, project_path: &str, tool_name: &str) -> bool {
+        self.project_path == project_path && self.tool_name == tool_name
+    }
+}
+
+/// A tool call waiting for the user, as sent in `claude:permission_request`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionRequest {
+    pub request_id: String,
+    pub session_id: String,
+    pub project_path: String,
+    pub tool_name: String,
+    pub tool_use_id: Option<String>,
+    pub input: Value,
+    pub requested_at: String,
+    pub timeout_secs: u64,
+}
+
+/// What the permission-prompt server sends for each tool call. The project
+/// isn't part of it: rules apply to the project the session's server was
+/// started for.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BridgeRequest {
+    token: String,
+    session_id: String,
+    tool_name: String,
+    tool_use_id: Option<String>,
+    input: Value,
+}
+
+/// What a session's permission-prompt servers were started with
+struct Grant {
+    token: String,
+    project_path: String,
+}
+
+struct Pending {
+    request: PermissionRequest,
+    reply: oneshot::Sender<(PermissionResponse, Resolution)>,
+}
+
+/// Answers the CLI's permission prompts.
+///
+/// Each agent process gets a small stdio MCP server
+/// ([`permission_mcp::serve`]) as its permission-prompt tool. That server
+/// forwards every request over a loopback connection to the broker, which
+/// allows it outright if an "always allow" rule covers the tool, and
+/// otherwise sends it to the frontend and waits for the user's answer, up to
+/// the configured timeout.
+pub struct PermissionBroker {
+    /// Token issued to each session's MCP servers, by session ID. A request
+    /// is only trusted for the session its token was issued to, so other
+    /// local processes — or another session's server — can't answer in the
+    /// user's place.
+    grants: Mutex<HashMap<String, Grant>>,
+    /// Loopback address the MCP servers connect to, once listening
+    addr: Mutex<Option<SocketAddr>>,
+    /// Binary that serves MCP when run with [`permission_mcp::SERVER_ARG`];
+    /// this executable if unset
+    server_command: Mutex<Option<String>>,
+    settings: Mutex<PermissionSettings>,
+    rules: Mutex<Vec<PermissionRule>>,
+    /// Requests waiting for the user, by request ID
+    pending: Mutex<HashMap<String, Pending>>,
+}
+
+impl Default for PermissionBroker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PermissionBroker {
+    pub fn new() -> Self {
+        Self {
+            grants: Mutex::new(HashMap::new()),
+            addr: Mutex::new(None),
+            server_command: Mutex::new(None),
+            settings: Mutex::new(PermissionSettings::default()),
+            rules: Mutex::new(Vec::new()),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Use another binary as the permission-prompt server
+    pub async fn set_server_command(&self, command: Option<String>) {
+        *self.server_command.lock().await = command;
+    }
+
+    pub async fn set_settings(&self, settings: PermissionSettings) {
+        *self.settings.lock().await = settings;
+    }
+
+    pub async fn settings(&self) -> PermissionSettings {
+        self.settings.lock().await.clone()
+    }
+
+    /// Replace the "always allow" rules, e.g. after loading them from the database
+    pub async fn set_rules(&self, rules: Vec<PermissionRule>) {
+        *self.rules.lock().await = rules;
+    }
+
+    pub async fn rules(&self) -> Vec<PermissionRule> {
+        self.rules.lock().await.clone()
+    }
+
+    /// Requests waiting for the user, oldest first
+    pub async fn pending(&self) -> Vec<PermissionRequest> {
+        let pending = self.pending.lock().await;
+        let mut requests: Vec<_> = pending.values().map(|p| p.request.clone()).collect();
+        requests.sort_by(|a, b| a.requested_at.cmp(&b.requested_at));
+        requests
+    }
+
+    /// Whether a session's agent is blocked on the user
+    pub async fn is_waiting(&self, session_id: &str) -> bool {
+        let pending = self.pending.lock().await;
+        pending.values().any(|p| p.request.session_id == session_id)
+    }
+
+    /// The permission-prompt server for a session's next process, starting
+    /// the broker's listener on first use
+    pub async fn server(
+        self: &Arc<Self>,
+        session_id: &str,
+        project_path: &str,
+        host: &Host,
+    ) -> Result<PermissionServer, String> {
+        let addr = self.listen(host).await?;
+        let command = match self.server_command.lock().await.clone() {
+            Some(command) => command,
+            None => std::env::current_exe()
+                .map_err(|e| format!("Failed to locate the permission server: {}", e))?
+                .to_string_lossy()
+                .to_string(),
+        };
+        let token = {
+            let mut grants = self.grants.lock().await;
+            let grant = grants
+                .entry(session_id.to_string())
+                .or_insert_with(|| Grant {
+                    token: uuid::Uuid::new_v4().to_string(),
+                    project_path: String::new(),
+                });
+            grant.project_path = project_path.to_string();
+            grant.token.clone()
+        };
+        let env = [
+            (permission_mcp::ADDR_ENV, addr.to_string()),
+            (permission_mcp::TOKEN_ENV, token),
+            (permission_mcp::SESSION_ENV, session_id.to_string()),
+        ];
+        Ok(PermissionServer {
+            command,
+            args: vec![permission_mcp::SERVER_ARG.to_string()],
+            env: env.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+        })
+    }
+
+    /// Stop trusting a session's permission-prompt servers, once its process
+    /// is gone. Its next process is issued a new token.
+    pub async fn revoke(&self, session_id: &str) {
+        self.grants.lock().await.remove(session_id);
+    }
+
+    /// Answer a pending request. Allowing with `always` adds a rule for the
+    /// tool in the request's project (returned, and saved to the database)
+    /// and allows the project's other pending requests for the same tool.
+    pub async fn respond(
+        &self,
+        request_id: &str,
+        response: PermissionResponse,
+        host: &Host,
+    ) -> Result<Option<PermissionRule>, String> {
+        let mut pending = self.pending.lock().await;
+        let entry = pending
+            .remove(request_id)
+            .ok_or_else(|| format!("Permission request {} is no longer pending", request_id))?;
+        let request = entry.request;
+        let rule = match response {
+            PermissionResponse::Allow { always: true, .. } => Some(PermissionRule {
+                id: uuid::Uuid::new_v4().to_string(),
+                project_path: request.project_path.clone(),
+                tool_name: request.tool_name.clone(),
+                created_at: Utc::now().to_rfc3339(),
+            }),
+            _ => None,
+        };
+        let _ = entry.reply.send((response, Resolution::User));
+
+        let Some(rule) = rule else {
+            return Ok(None);
+        };
+        let covered: Vec<String> = pending
+            .iter()
+            .filter(|(_, p)| rule.covers(&p.request.project_path, &p.request.tool_name))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in covered {
+            if let Some(entry) = pending.remove(&id) {
+                let allow = PermissionResponse::Allow {
+                    updated_input: None,
+                    always: false,
+                };
+                let _ = entry.reply.send((allow, Resolution::Rule));
+            }
+        }
+        drop(pending);
+
+        {
+            let mut rules = self.rules.lock().await;
+            if rules
+                .iter()
+                .any(|r| r.covers(&rule.project_path, &rule.tool_name))
+            {
+                return Ok(Some(rule));
+            }
+            rules.push(rule.clone());
+        }
+        let saved = match host.database().await {
+            Ok(pool) => db::permission_rules::save(&pool, &rule).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = saved {
+            log::warn!(
+                "[permissions] failed to save rule for {}: {}",
+                rule.tool_name,
+                e
+            );
+        }
+        Ok(Some(rule))
+    }
+
+    async fn listen(self: &Arc<Self>, host: &Host) -> Result<SocketAddr, String> {
+        let mut addr = self.addr.lock().await;
+        if let Some(addr) = *addr {
+            return Ok(addr);
+        }
+
+        let listener = TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .map_err(|e| format!("Failed to start permission broker: {}", e))?;
+        let local = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to start permission broker: {}", e))?;
+        let broker = Arc::clone(self);
+        let host = host.clone();
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(Arc::clone(&broker).handle_connection(stream, host.clone()));
+                    }
+                    Err(e) => {
+                        log::warn!("[permissions] failed to accept connection: {}", e);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                }
+            }
+        });
+        *addr = Some(local);
+        Ok(local)
+    }
+
+    /// Serve one request from a permission-prompt server: read it, decide,
+    /// and write back the decision in the CLI's format
+    async fn handle_connection(self: Arc<Self>, stream: TcpStream, host: Host) {
+        let (read, mut write) = stream.into_split();
+        let mut reader = BufReader::new(read);
+        let mut line = String::new();
+        if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+            return;
+        }
+        let request = match serde_json::from_str::<BridgeRequest>(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                log::warn!("[permissions] invalid request: {}", e);
+                return;
+            }
+        };
+        let Some(project_path) = self.verify(&request).await else {
+            log::warn!(
+                "[permissions] rejected a request for session {} with the wrong token",
+                request.session_id
+            );
+            return;
+        };
+
+        let input = request.input.clone();
+        let (response, resolution) = self.decide(request, project_path, reader, &host).await;
+        let mut reply = cli_decision(response, input, resolution).to_string();
+        reply.push('\n');
+        let _ = write.write_all(reply.as_bytes()).await;
+    }
+
+    /// The project of the session a request claims to come from, if the
+    /// request carries the token issued to that session
+    async fn verify(&self, request: &BridgeRequest) -> Option<String> {
+        let grants = self.grants.lock().await;
+        let grant = grants.get(&request.session_id)?;
+        let valid: bool = grant
+            .token
+            .as_bytes()
+            .ct_eq(request.token.as_bytes())
+            .into();
+        valid.then(|| grant.project_path.clone())
+    }
+
+    async fn decide(
+        &self,
+        request: BridgeRequest,
+        project_path: String,
+        mut reader: BufReader<tokio::net::tcp::OwnedReadHalf>,
+        host: &Host,
+    ) -> (PermissionResponse, Resolution) {
+        let allowed = {
+            let rules = self.rules.lock().await;
+            rules
+                .iter()
+                .any(|r| r.covers(&project_path, &request.tool_name))
+        };
+        if allowed {
+            log::info!(
+                "[permissions:{}] {} allowed by rule",
+                request.session_id,
+                request.tool_name
+            );
+            let allow = PermissionResponse::Allow {
+                updated_input: None,
+                always: false,
+            };
+            return (allow, Resolution::Rule);
+        }
+
+        let settings = self.settings().await;
+        let request = PermissionRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            session_id: request.session_id,
+            project_path,
+            tool_name: request.tool_name,
+            tool_use_id: request.tool_use_id,
+            input: request.input,
+            requested_at: Utc::now().to_rfc3339(),
+            timeout_secs: settings.timeout_secs,
+        };
+        let (reply, answer) = oneshot::channel();
+        self.pending.lock().await.insert(
+            request.request_id.clone(),
+            Pending {
+                request: request.clone(),
+                reply,
+            },
+        );
+        let _ = host.emit(
+            crate::events::CLAUDE_PERMISSION_REQUEST,
+            serde_json::to_value(&request).unwrap_or_default(),
+        );
+
+        // The server hangs up when its CLI exits, e.g. on kill or interrupt
+        let hung_up = async {
+            let mut rest = String::new();
+            while reader.read_line(&mut rest).await.unwrap_or(0) > 0 {
+                rest.clear();
+            }
+        };
+        let (response, resolution) = tokio::select! {
+            answer = answer => answer.unwrap_or((deny(None), Resolution::Cancelled)),
+            _ = tokio::time::sleep(Duration::from_secs(settings.timeout_secs)) => {
+                let response = match settings.on_timeout {
+                    PermissionBehavior::Allow => PermissionResponse::Allow {
+                        updated_input: None,
+                        always: false,
+                    },
+                    PermissionBehavior::Deny => deny(Some(
+                        "Nobody answered the permission request in time".to_string(),
+                    )),
+                };
+                (response, Resolution::Timeout)
+            }
+            _ = hung_up => (deny(None), Resolution::Cancelled),
+        };
+        self.pending.lock().await.remove(&request.request_id);
+
+        let behavior = match response {
+            PermissionResponse::Allow { .. } => PermissionBehavior::Allow,
+            PermissionResponse::Deny { .. } => PermissionBehavior::Deny,
+        };
+        log::info!(
+            "[permissions:{}] {} {:?} ({:?})",
+            request.session_id,
+            request.tool_name,
+            behavior,
+            resolution
+        );
+        let _ = host.emit(
+            crate::events::CLAUDE_PERMISSION_RESOLVED,
+            serde_json::json!({
+                "sessionId": request.session_id,
+                "requestId": request.request_id,
+                "behavior": behavior,
+                "resolution": resolution,
+            }),
+        );
+        (response, resolution)
+    }
+}
+
+fn deny(message: Option<String>) -> PermissionResponse {
+    PermissionResponse::Deny { message }
+}
+
+/// A decision in the shape the CLI expects from its permission-prompt tool
+fn cli_decision(response: PermissionResponse, input: Value, resolution: Resolution) -> Value {
+    match response {
+        PermissionResponse::Allow { updated_input, .. } => serde_json::json!({
+            "behavior": "allow",
+            "updatedInput": updated_input.unwrap_or(input),
+        }),
+        PermissionResponse::Deny { message } => {
+            let message = message.unwrap_or_else(|| match resolution {
+                Resolution::Cancelled => "The session was stopped".to_string(),
+                _ => "The user denied this tool call".to_string(),
+            });
+            serde_json::json!({ "behavior": "deny", "message": message })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::host::test_host;
+
+    fn env<'a>(server: &'a PermissionServer, key: &str) -> &'a str {
+        server
+            .env
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .unwrap()
+    }
+
+    /// Send a request to the broker as a permission-prompt server would.
+    /// Returns the decision, or `None` if the broker hung up.
+    async fn ask(server: &PermissionServer, token: &str, session_id: &str) -> Option<Value> {
+        let mut stream = TcpStream::connect(env(server, permission_mcp::ADDR_ENV))
+            .await
+            .unwrap();
+        let request = serde_json::json!({
+            "token": token,
+            "sessionId": session_id,
+            "toolName": "Bash",
+            "toolUseId": "toolu_01",
+            "input": { "command": "ls" },
+        });
+        stream
+            .write_all(format!("{}\n", request).as_bytes())
+            .await
+            .unwrap();
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply).await.unwrap();
+        (!reply.is_empty()).then(|| serde_json::from_str(&reply).unwrap())
+    }
+
+    /// A broker with Bash always allowed in `/tmp/trusted`, and unanswered
+    /// requests denied after a second
+    async fn broker() -> (Arc<PermissionBroker>, Host) {
+        let broker = Arc::new(PermissionBroker::new());
+        broker
+            .set_settings(PermissionSettings {
+                enabled: true,
+                timeout_secs: 1,
+                on_timeout: PermissionBehavior::Deny,
+            })
+            .await;
+        broker
+            .set_rules(vec![PermissionRule {
+                id: "rule".to_string(),
+                project_path: "/tmp/trusted".to_string(),
+                tool_name: "Bash".to_string(),
+                created_at: Utc::now().to_rfc3339(),
+            }])
+            .await;
+        (broker, test_host(db::test_pool().await))
+    }
+
+    #[tokio::test]
+    async fn each_session_gets_its_own_token() {
+        let (broker, host) = broker().await;
+        let first = broker.server("s1", "/tmp/trusted", &host).await.unwrap();
+        let again = broker.server("s1", "/tmp/trusted", &host).await.unwrap();
+        let other = broker.server("s2", "/tmp/other", &host).await.unwrap();
+
+        let token = |server: &PermissionServer| env(server, permission_mcp::TOKEN_ENV).to_string();
+        assert_eq!(token(&first), token(&again));
+        assert_ne!(token(&first), token(&other));
+    }
+
+    #[tokio::test]
+    async fn requests_are_only_trusted_for_their_own_session() {
+        let (broker, host) = broker().await;
+        let trusted = broker.server("s1", "/tmp/trusted", &host).await.unwrap();
+        let other = broker.server("s2", "/tmp/other", &host).await.unwrap();
+        let trusted_token = env(&trusted, permission_mcp::TOKEN_ENV);
+        let other_token = env(&other, permission_mcp::TOKEN_ENV);
+
+        let reply = ask(&trusted, trusted_token, "s1").await.unwrap();
+        assert_eq!(reply["behavior"], "allow");
+
+        // Another session's token, a made-up one, or an unknown session
+        assert_eq!(ask(&trusted, other_token, "s1").await, None);
+        assert_eq!(ask(&trusted, "not-a-token", "s1").await, None);
+        assert_eq!(ask(&trusted, trusted_token, "s3").await, None);
+        assert!(broker.pending().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn revoked_sessions_are_no_longer_trusted() {
+        let (broker, host) = broker().await;
+        let old = broker.server("s1", "/tmp/trusted", &host).await.unwrap();
+        let old_token = env(&old, permission_mcp::TOKEN_ENV);
+        broker.revoke("s1").await;
+        assert_eq!(ask(&old, old_token, "s1").await, None);
+
+        // The session's next process gets a new token
+        let new = broker.server("s1", "/tmp/trusted", &host).await.unwrap();
+        let new_token = env(&new, permission_mcp::TOKEN_ENV);
+        assert_ne!(old_token, new_token);
+        assert_eq!(ask(&new, old_token, "s1").await, None);
+        assert_eq!(
+            ask(&new, new_token, "s1").await.unwrap()["behavior"],
+            "allow"
+        );
+    }
+
+    #[tokio::test]
+    async fn rules_apply_to_the_project_the_server_was_started_for() {
+        let (broker, host) = broker().await;
+        let other = broker.server("s2", "/tmp/other", &host).await.unwrap();
+
+        // Bash is only allowed in /tmp/trusted and s2's server runs in
+        // /tmp/other, so it waits for the user and times out instead
+        let reply = ask(&other, env(&other, permission_mcp::TOKEN_ENV), "s2")
+            .await
+            .unwrap();
+        assert_eq!(reply["behavior"], "deny");
+    }
+}
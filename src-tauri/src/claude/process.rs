@@ -1,31 +1,220 @@
-use std::collections::HashMap;
-use std::process::Stdio;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
-use tauri::Emitter;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::{Child, Command};
-use tokio::sync::Mutex;
+use tokio::process::Child;
+use tokio::sync::{broadcast, Mutex};
 
-use super::stream_parser::StreamParser;
+use super::claude_cli::ClaudeCliProvider;
+use super::content::ContentBlock;
+use super::emitter::{EventMetricsSnapshot, EventPipeline, SessionEvents};
+use super::env_profile::EnvProfile;
+use super::host::Host;
+use super::launch::{LaunchSettings, PermissionMode};
+use super::locator::CliLocator;
+use super::permission_mcp::{McpConfigFile, PermissionServer};
+use super::permissions::PermissionBroker;
+use super::provider::{AgentCommand, AgentProvider, ProviderInfo, DEFAULT_PROVIDER};
+use super::queue::{QueuedPrompt, SessionQueue};
+use super::scheduler::{
+    SchedulerStatus, SessionPriority, TurnScheduler, DEFAULT_MAX_CONCURRENT_TURNS,
+};
+use super::session_store::DiscoveredSession;
+use super::turn_summary::TurnSummary;
+use super::types::ResultEvent;
+use super::usage::MessageUsage;
+use super::watchdog::{TimeoutAction, Verdict, WatchdogSettings, MAX_TURN_ATTEMPTS, WATCHDOG_TICK};
+use crate::db;
 
-/// Manages multiple Claude CLI child processes.
+/// How long to wait after each interrupt signal before escalating
+const DEFAULT_INTERRUPT_GRACE: Duration = Duration::from_secs(3);
+/// How many trailing stderr lines to keep for the exit report
+const STDERR_TAIL_LINES: usize = 20;
+/// How long the stdout reader waits for stderr to close before reaping
+const STDERR_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+/// How many turn outcomes a slow subscriber may fall behind by
+const TURN_OUTCOME_CAPACITY: usize = 256;
+/// How long an interrupted CLI gets to be reaped after SIGKILL
+const KILL_REAP_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long process trees get to exit on SIGTERM when the app shuts down
+#[cfg(unix)]
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// Manages multiple agent CLI child processes.
+///
+/// Each session runs one long-lived CLI process: user turns are written to
+/// stdin as NDJSON and the process stays alive between turns, keeping MCP
+/// servers and in-memory state warm. A turn ends when the CLI reports its
+/// result. Agents that can only take one prompt per process run in
+/// [`SessionMode::Respawn`] instead, resuming the conversation each turn.
+///
+/// How an agent is started, how turns are encoded and how its output is
+/// parsed comes from the session's [`AgentProvider`] — the Claude CLI unless
+/// the session's launch settings name another registered provider.
+///
+/// On unix each CLI is spawned as the leader of its own process group, so
+/// killing or interrupting a session also reaches the shells, dev servers and
+/// MCP servers the agent started (unless they moved to a group of their own).
 ///
-/// Claude CLI in `-p` mode is single-turn: it reads one prompt from stdin,
-/// responds, then exits. For multi-turn conversations, we spawn a new process
-/// per message using `--resume <claude-session-id>` to continue the session.
+/// Messages sent while a turn is running wait in a per-session FIFO and are
+/// started one at a time once the previous turn has finished. Across all
+/// sessions, a [`TurnScheduler`] caps how many turns run at once; sessions
+/// over the limit wait for a slot, interactive ones ahead of background ones.
+///
+/// Every process also gets a watchdog task that reports a running turn as
+/// stalled once stdout has been silent for a while, and interrupts (or
+/// retries) turns that exceed the configured time limit.
+///
+/// Tool approvals go through the [`PermissionBroker`]: agents that support
+/// it ask the app before using a tool, and a turn waiting on the user is
+/// never reported as stalled.
 pub struct ProcessManager {
     processes: Arc<Mutex<HashMap<String, ClaudeProcess>>>,
     /// Maps our session IDs to Claude's internal session IDs (discovered from JSONL)
     claude_session_map: Arc<Mutex<HashMap<String, String>>>,
+    /// Pending prompts per session
+    queues: Mutex<HashMap<String, SessionQueue>>,
+    /// Launch settings per session, reused whenever the session is respawned
+    launch_settings: Mutex<HashMap<String, LaunchSettings>>,
+    /// Raw-event mirroring switch and emission counters per session
+    session_events: Mutex<HashMap<String, SessionEvents>>,
+    /// Global turn slots and the priority queue of sessions waiting for one.
+    /// Always locked after `queues` when both are needed.
+    scheduler: Mutex<TurnScheduler>,
+    /// Stall threshold and per-turn time limit applied by the watchdogs
+    watchdog: Mutex<WatchdogSettings>,
+    /// Environment profiles sessions can be spawned with (unmasked)
+    env_profiles: Mutex<Vec<EnvProfile>>,
+    /// Answers tool permission prompts of every session
+    permissions: Arc<PermissionBroker>,
+    /// The built-in Claude CLI provider
+    claude: Arc<ClaudeCliProvider>,
+    /// Every registered provider by ID, including `claude`
+    providers: Mutex<HashMap<String, Arc<dyn AgentProvider>>>,
+    /// Source of per-spawn generation numbers
+    next_generation: AtomicU64,
+    /// How each dispatched prompt ended, for callers waiting on a turn
+    turn_outcomes: broadcast::Sender<TurnOutcome>,
 }
 
 struct ClaudeProcess {
     child: Child,
+    /// Agent the process was started with; encodes its turns
+    provider: Arc<dyn AgentProvider>,
+    /// Process group of the CLI and everything it started; equals the CLI's
+    /// PID and stays known after the CLI itself has been reaped
+    pgid: Option<u32>,
     stdin: Option<tokio::process::ChildStdin>,
     project_path: String,
     status: ProcessStatus,
+    mode: SessionMode,
+    /// Identifies this spawn so reader tasks of a replaced process
+    /// don't update the state of its successor
+    generation: u64,
+    /// A turn has been written to stdin and its `result` event hasn't arrived yet
+    turn_in_flight: bool,
+    turn_started_at: Option<Instant>,
+    /// Prompt of the turn in flight, kept so a timed-out turn can be retried
+    current_prompt: Option<QueuedPrompt>,
+    /// When stdout last produced a line (or the current turn was written)
+    last_output_at: Instant,
+    /// The watchdog has reported the current turn as stalled
+    stalled: bool,
+    /// Number of turns this process has finished
+    turns_completed: u64,
+    /// Set by `interrupt` so the exit is reported as cancelled, not completed
+    cancel_requested: bool,
+    /// `is_error` of the most recent `result` event
+    last_result_is_error: Option<bool>,
+    /// Most recent stderr lines, oldest first
+    stderr_tail: VecDeque<String>,
+    /// Set once the stdout reader has reaped the child
+    exit: Option<ExitInfo>,
+    /// Removed along with the process record
+    _mcp_config: Option<McpConfigFile>,
+}
+
+/// How a CLI process ended
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExitInfo {
+    pub code: Option<i32>,
+    /// Terminating signal, if the process was killed by one (unix only)
+    pub signal: Option<i32>,
+}
+
+impl From<ExitStatus> for ExitInfo {
+    fn from(status: ExitStatus) -> Self {
+        #[cfg(unix)]
+        let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+        #[cfg(not(unix))]
+        let signal = None;
+
+        Self {
+            code: status.code(),
+            signal,
+        }
+    }
+}
+
+/// How a queued prompt's turn ended, broadcast to [`ProcessManager::subscribe_turns`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnOutcome {
+    pub session_id: String,
+    /// ID of the queued prompt the turn was started from
+    pub prompt_id: String,
+    /// Subtype of the turn's result (`success`, `error_max_turns`, ...),
+    /// or `cancelled`, `exited` or `failed` if the turn ended without one
+    pub subtype: String,
+    pub is_error: bool,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: Option<f64>,
+}
+
+impl TurnOutcome {
+    /// Outcome described by a Claude CLI `result` event (prompt ID filled in later)
+    pub(crate) fn from_result(session_id: &str, result: &ResultEvent) -> Self {
+        let usage = result.usage.clone().unwrap_or_default();
+        Self {
+            session_id: session_id.to_string(),
+            prompt_id: String::new(),
+            subtype: result.subtype.clone(),
+            is_error: result.is_error,
+            input_tokens: usage.input_tokens.unwrap_or(0),
+            output_tokens: usage.output_tokens.unwrap_or(0),
+            cost_usd: result.total_cost_usd,
+        }
+    }
+
+    /// Outcome of a prompt whose turn ended (or never started) without a result
+    fn ended(session_id: &str, prompt: &QueuedPrompt, subtype: &str) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            prompt_id: prompt.id.clone(),
+            subtype: subtype.to_string(),
+            is_error: subtype != "cancelled",
+            input_tokens: 0,
+            output_tokens: 0,
+            cost_usd: None,
+        }
+    }
+}
+
+/// How a session's CLI process receives user turns
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionMode {
+    /// One process for the whole session, turns written as NDJSON lines
+    Persistent,
+    /// One resumed process per turn, stdin closed after the prompt
+    Respawn,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -36,6 +225,7 @@ pub enum ProcessStatus {
     Paused,
     Completed,
     Error,
+    Cancelled,
 }
 
 impl std::fmt::Display for ProcessStatus {
@@ -47,6 +237,7 @@ impl std::fmt::Display for ProcessStatus {
             ProcessStatus::Paused => write!(f, "paused"),
             ProcessStatus::Completed => write!(f, "completed"),
             ProcessStatus::Error => write!(f, "error"),
+            ProcessStatus::Cancelled => write!(f, "cancelled"),
         }
     }
 }
@@ -55,117 +246,117 @@ impl std::fmt::Display for ProcessStatus {
 pub struct SpawnOptions {
     pub session_id: String,
     pub project_path: String,
+    /// Binary to run instead of the one the provider would find
     pub claude_cli_path: Option<String>,
     pub resume_session_id: Option<String>,
-    pub model: Option<String>,
+    /// Model, permission and tool settings; remembered for later respawns
+    pub launch: LaunchSettings,
 }
 
 impl ProcessManager {
     pub fn new() -> Self {
+        let claude = Arc::new(ClaudeCliProvider::new());
         Self {
             processes: Arc::new(Mutex::new(HashMap::new())),
             claude_session_map: Arc::new(Mutex::new(HashMap::new())),
+            queues: Mutex::new(HashMap::new()),
+            launch_settings: Mutex::new(HashMap::new()),
+            session_events: Mutex::new(HashMap::new()),
+            scheduler: Mutex::new(TurnScheduler::new(DEFAULT_MAX_CONCURRENT_TURNS)),
+            watchdog: Mutex::new(WatchdogSettings::default()),
+            env_profiles: Mutex::new(Vec::new()),
+            permissions: Arc::new(PermissionBroker::new()),
+            claude: Arc::clone(&claude),
+            providers: Mutex::new(HashMap::from([(
+                DEFAULT_PROVIDER.to_string(),
+                claude as Arc<dyn AgentProvider>,
+            )])),
+            next_generation: AtomicU64::new(1),
+            turn_outcomes: broadcast::channel(TURN_OUTCOME_CAPACITY).0,
         }
     }
 
-    /// Find the claude CLI binary path.
-    /// Checks `which` first, then falls back to common install locations
-    /// (important for GUI apps that don't inherit shell PATH).
-    pub async fn detect_cli_path() -> Option<String> {
-        // Try `which` first (works when launched from terminal)
-        if let Ok(output) = Command::new("which").arg("claude").output().await {
-            if output.status.success() {
-                let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                if !path.is_empty() {
-                    return Some(path);
-                }
-            }
-        }
-
-        // Fall back to common install locations (works for GUI-launched apps)
-        let mut candidates: Vec<String> = vec![
-            "/opt/homebrew/bin/claude".to_string(),
-            "/usr/local/bin/claude".to_string(),
-            "/usr/bin/claude".to_string(),
-        ];
-
-        // Add user-specific paths
-        if let Some(home) = dirs::home_dir() {
-            let home = home.to_string_lossy();
-            candidates.push(format!("{}/.npm/bin/claude", home));
-            candidates.push(format!("{}/.local/bin/claude", home));
-        }
-
-        for path in &candidates {
-            if std::path::Path::new(path).exists() {
-                return Some(path.to_string());
-            }
-        }
-
-        None
+    /// The shared CLI locator (path discovery, version and flag probing)
+    pub fn cli(&self) -> &CliLocator {
+        self.claude.cli()
     }
 
-    fn resolve_cli_path(provided: Option<String>) -> Result<String, String> {
-        if let Some(path) = provided {
-            return Ok(path);
-        }
-
-        let mut candidates: Vec<String> = vec![
-            "/opt/homebrew/bin/claude".to_string(),
-            "/usr/local/bin/claude".to_string(),
-            "/usr/bin/claude".to_string(),
-        ];
+    /// Make another agent available to sessions, replacing any provider
+    /// registered under the same ID
+    pub async fn register_provider(&self, provider: Arc<dyn AgentProvider>) {
+        let mut providers = self.providers.lock().await;
+        providers.insert(provider.id().to_string(), provider);
+    }
 
-        if let Some(home) = dirs::home_dir() {
-            let home = home.to_string_lossy();
-            candidates.push(format!("{}/.npm/bin/claude", home));
-            candidates.push(format!("{}/.local/bin/claude", home));
-        }
+    /// Look up a provider by ID; `None` is the Claude CLI
+    pub async fn provider(&self, id: Option<&str>) -> Result<Arc<dyn AgentProvider>, String> {
+        let id = id.unwrap_or(DEFAULT_PROVIDER);
+        let providers = self.providers.lock().await;
+        providers
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("Unknown agent provider '{}'", id))
+    }
 
-        for path in &candidates {
-            if std::path::Path::new(path).exists() {
-                return Ok(path.to_string());
-            }
-        }
+    /// Registered providers, sorted by ID
+    pub async fn providers(&self) -> Vec<ProviderInfo> {
+        let providers = self.providers.lock().await;
+        let mut list: Vec<ProviderInfo> = providers
+            .values()
+            .map(|p| ProviderInfo {
+                id: p.id().to_string(),
+                name: p.display_name().to_string(),
+            })
+            .collect();
+        list.sort_by(|a, b| a.id.cmp(&b.id));
+        list
+    }
 
-        Err("Claude CLI not found. Install via Homebrew (brew install claude-code) or npm (npm install -g @anthropic-ai/claude-code)"
-            .to_string())
+    /// Saved conversations of every registered provider
+    pub async fn discover_sessions(&self) -> Vec<DiscoveredSession> {
+        let providers: Vec<_> = self.providers.lock().await.values().cloned().collect();
+        providers
+            .iter()
+            .flat_map(|p| p.discover_sessions())
+            .collect()
     }
 
-    /// Spawn a new Claude CLI process for a session
+    /// Spawn a new agent process for a session
     pub async fn spawn(
-        &self,
+        self: &Arc<Self>,
         opts: SpawnOptions,
-        app_handle: tauri::AppHandle,
+        app_handle: Host,
     ) -> Result<(), String> {
-        let cli_path = Self::resolve_cli_path(opts.claude_cli_path.clone())?;
-
-        let mut cmd = Command::new(&cli_path);
-        cmd.arg("-p")
-            .arg("--output-format")
-            .arg("stream-json")
-            .arg("--verbose")
-            .arg("--include-partial-messages")
+        let provider = self.provider(opts.launch.provider.as_deref()).await?;
+        let permissions = self.permission_server_for(&opts, &app_handle).await?;
+        let AgentCommand {
+            mut command,
+            mode,
+            mcp_config,
+        } = provider.command(&opts, permissions.as_ref()).await?;
+        if let Some(profile) = self.env_profile_for(&opts.launch, &opts.project_path).await? {
+            log::info!(
+                "[process:{}] using environment profile '{}'",
+                opts.session_id,
+                profile.name
+            );
+            profile.apply(&mut command)?;
+        }
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+
+        command
             .current_dir(&opts.project_path)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
 
-        // Strip env vars that prevent CLI from running inside other Claude sessions
-        cmd.env_remove("CLAUDECODE");
-        cmd.env_remove("CLAUDE_CODE_ENTRY_POINT");
-
-        if let Some(ref resume_id) = opts.resume_session_id {
-            cmd.arg("--resume").arg(resume_id);
-        }
+        // New process group so the whole tree can be signalled at once
+        #[cfg(unix)]
+        command.process_group(0);
 
-        if let Some(ref model) = opts.model {
-            cmd.arg("--model").arg(model);
-        }
-
-        let mut child = cmd
+        let mut child = command
             .spawn()
-            .map_err(|e| format!("Failed to spawn claude CLI: {}", e))?;
+            .map_err(|e| format!("Failed to spawn {}: {}", provider.display_name(), e))?;
 
         let stdin = child.stdin.take();
         let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
@@ -173,6 +364,8 @@ impl ProcessManager {
 
         let session_id = opts.session_id.clone();
 
+        self.set_launch_settings(&session_id, opts.launch.clone()).await;
+
         // Store the resume session ID mapping if provided
         if let Some(ref claude_sid) = opts.resume_session_id {
             let mut map = self.claude_session_map.lock().await;
@@ -180,10 +373,25 @@ impl ProcessManager {
         }
 
         let process = ClaudeProcess {
+            pgid: child.id(),
             child,
+            provider: Arc::clone(&provider),
             stdin,
             project_path: opts.project_path.clone(),
             status: ProcessStatus::Starting,
+            mode,
+            generation,
+            turn_in_flight: false,
+            turn_started_at: None,
+            current_prompt: None,
+            last_output_at: Instant::now(),
+            stalled: false,
+            turns_completed: 0,
+            cancel_requested: false,
+            last_result_is_error: None,
+            stderr_tail: VecDeque::with_capacity(STDERR_TAIL_LINES),
+            exit: None,
+            _mcp_config: mcp_config,
         };
 
         {
@@ -191,101 +399,181 @@ impl ProcessManager {
             procs.insert(session_id.clone(), process);
         }
 
-        // Spawn stdout reader
-        let processes = self.processes.clone();
+        // Spawn stderr reader — forward to frontend so user sees errors, and
+        // keep the last lines to report alongside the exit status. Stderr on its
+        // own doesn't fail the session; the CLI also logs harmless warnings there.
+        let stderr_processes = self.processes.clone();
+        let stderr_app = app_handle.clone();
+        let sid_err = session_id.clone();
+        let stderr_task = tokio::spawn(async move {
+            let reader = BufReader::new(stderr);
+            let mut lines = reader.lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                log::warn!("[claude-stderr:{}] {}", sid_err, line);
+                {
+                    let mut procs = stderr_processes.lock().await;
+                    if let Some(proc) = procs
+                        .get_mut(&sid_err)
+                        .filter(|p| p.generation == generation)
+                    {
+                        if proc.stderr_tail.len() == STDERR_TAIL_LINES {
+                            proc.stderr_tail.pop_front();
+                        }
+                        proc.stderr_tail.push_back(line.clone());
+                    }
+                }
+                let _ = stderr_app.emit(
+                    crate::events::CLAUDE_STDERR,
+                    serde_json::json!({
+                        "sessionId": sid_err,
+                        "text": line,
+                    }),
+                );
+            }
+        });
+
+        // Spawn stdout reader. Parsed events go through a bounded pipeline
+        // that coalesces deltas; it is flushed before turn and exit status
+        // changes so those never overtake the output that preceded them.
+        let manager = Arc::clone(self);
         let session_map = self.claude_session_map.clone();
         let app = app_handle.clone();
         let sid = session_id.clone();
+        let pipeline = EventPipeline::spawn(app.clone(), self.session_events(&session_id).await);
+        tokio::spawn(Arc::clone(self).watch(session_id.clone(), generation, app_handle.clone()));
         tokio::spawn(async move {
             let reader = BufReader::new(stdout);
             let mut lines = reader.lines();
-            let parser = StreamParser::new();
+            let mut parser = provider.parser();
 
             while let Ok(Some(line)) = lines.next_line().await {
-                // Try to extract Claude's session ID from the stream.
-                // The CLI puts session_id at the top level of every NDJSON line.
-                // We capture it from the first event that has it (typically the init event).
-                if let Ok(val) = serde_json::from_str::<serde_json::Value>(&line) {
-                    if let Some(sid_val) = val
-                        .get("session_id")
-                        .and_then(|s| s.as_str())
-                    {
+                manager.record_output(&sid, generation, &app).await;
+                let parsed = parser.parse_line(&sid, &line);
+
+                // Capture the agent's own session ID from the first line
+                // that carries it (for the Claude CLI, the init event)
+                if let Some(agent_sid) = parsed.agent_session_id {
+                    let resolved = {
                         let mut map = session_map.lock().await;
-                        if !map.contains_key(&sid) {
-                            map.insert(sid.clone(), sid_val.to_string());
-                            // Notify frontend of the real Claude session ID
-                            let _ = app.emit(
-                                crate::events::CLAUDE_SESSION_ID_RESOLVED,
-                                serde_json::json!({
-                                    "sessionId": sid,
-                                    "claudeSessionId": sid_val,
-                                }),
-                            );
+                        if map.contains_key(&sid) {
+                            false
+                        } else {
+                            map.insert(sid.clone(), agent_sid.clone());
+                            true
                         }
+                    };
+                    if resolved {
+                        // Notify frontend of the real agent session ID
+                        let _ = app.emit(
+                            crate::events::CLAUDE_SESSION_ID_RESOLVED,
+                            serde_json::json!({
+                                "sessionId": sid,
+                                "claudeSessionId": agent_sid,
+                            }),
+                        );
+                        manager.persist_session(&sid, &app).await;
                     }
                 }
 
-                parser.parse_line(&sid, &line, &app).await;
-            }
+                for event in parsed.events {
+                    pipeline.send(event).await;
+                }
+                for hook in parsed.finished_hooks {
+                    manager.log_activity(&sid, "hook", &hook, &app).await;
+                }
+                for usage in parsed.usage {
+                    manager.record_usage(&sid, &usage, &app).await;
+                }
 
-            // Process exited
-            let mut procs = processes.lock().await;
-            if let Some(proc) = procs.get_mut(&sid) {
-                proc.status = ProcessStatus::Completed;
+                if let Some(outcome) = parsed.turn_end {
+                    pipeline.flush().await;
+                    if let Some(summary) = parsed.turn_summary {
+                        manager
+                            .record_turn_summary(&sid, generation, summary, &app)
+                            .await;
+                    }
+                    manager.finish_turn(&sid, generation, outcome, &app).await;
+                }
             }
+            pipeline.close().await;
 
-            let _ = app.emit(
-                crate::events::CLAUDE_SESSION_STATUS,
-                serde_json::json!({
-                    "sessionId": sid,
-                    "status": "completed"
-                }),
-            );
-        });
+            // Stdout closed — let stderr drain, then reap the child
+            let _ = tokio::time::timeout(STDERR_DRAIN_TIMEOUT, stderr_task).await;
+            let Some(exit) = manager.reap(&sid, generation).await else {
+                // Replaced or removed meanwhile — its owner reports status
+                return;
+            };
 
-        // Spawn stderr reader — forward to frontend so user sees errors
-        let stderr_processes = self.processes.clone();
-        let stderr_app = app_handle.clone();
-        let sid_err = session_id.clone();
-        tokio::spawn(async move {
-            let reader = BufReader::new(stderr);
-            let mut lines = reader.lines();
-            let mut had_stderr = false;
-            while let Ok(Some(line)) = lines.next_line().await {
-                log::warn!("[claude-stderr:{}] {}", sid_err, line);
-                had_stderr = true;
-                let _ = stderr_app.emit(
-                    crate::events::CLAUDE_STDERR,
+            let (mode, turn_was_running) = {
+                let mut procs = manager.processes.lock().await;
+                let Some(proc) = procs
+                    .get_mut(&sid)
+                    .filter(|p| p.generation == generation)
+                else {
+                    return;
+                };
+
+                let turn_was_running = proc.turn_in_flight;
+                proc.turn_in_flight = false;
+                proc.stdin = None;
+                proc.status = Self::exit_status(proc, &exit);
+                if let Some(prompt) = proc.current_prompt.take() {
+                    let subtype = match proc.status {
+                        ProcessStatus::Cancelled => "cancelled",
+                        _ => "exited",
+                    };
+                    manager.report_outcome(TurnOutcome::ended(&sid, &prompt, subtype));
+                }
+                if proc.status == ProcessStatus::Error {
+                    log::warn!(
+                        "[process:{}] exited with code {:?}, signal {:?}",
+                        sid,
+                        exit.code,
+                        exit.signal
+                    );
+                }
+
+                let _ = app.emit(
+                    crate::events::CLAUDE_SESSION_STATUS,
                     serde_json::json!({
-                        "sessionId": sid_err,
-                        "text": line,
+                        "sessionId": sid,
+                        "status": proc.status.to_string(),
+                        "exitCode": exit.code,
+                        "signal": exit.signal,
+                        "stderrTail": proc.stderr_tail,
                     }),
                 );
-            }
+                proc.exit = Some(exit);
+                (proc.mode, turn_was_running)
+            };
+            manager.permissions.revoke(&sid).await;
+            manager.persist_session(&sid, &app).await;
 
-            // If stderr had output and process is still Starting/Running, mark as error
-            if had_stderr {
-                let mut procs = stderr_processes.lock().await;
-                if let Some(proc) = procs.get_mut(&sid_err) {
-                    if matches!(proc.status, ProcessStatus::Starting | ProcessStatus::Running) {
-                        proc.status = ProcessStatus::Error;
-                        let _ = stderr_app.emit(
-                            crate::events::CLAUDE_SESSION_STATUS,
-                            serde_json::json!({
-                                "sessionId": sid_err,
-                                "status": "error"
-                            }),
-                        );
-                    }
+            // A respawn-mode turn ends when its process exits; a persistent
+            // process exiting mid-turn leaves the queue for the next send
+            if mode == SessionMode::Respawn || turn_was_running {
+                if let Some(queue) = manager.queues.lock().await.get_mut(&sid) {
+                    queue.finish_turn();
+                    queue.emit_update(&sid, &app);
+                }
+                manager.release_turn_slot(&sid, &app).await;
+            }
+            if mode == SessionMode::Respawn && !turn_was_running {
+                if let Err(e) = Arc::clone(&manager).dispatch_next(sid.clone(), app.clone()).await {
+                    Self::report_dispatch_error(&sid, &e, &app);
                 }
             }
         });
 
-        // Update status
+        // Update status — a persistent process sits idle until its first turn
+        let status = match mode {
+            SessionMode::Persistent => ProcessStatus::WaitingInput,
+            SessionMode::Respawn => ProcessStatus::Running,
+        };
         {
             let mut procs = self.processes.lock().await;
             if let Some(proc) = procs.get_mut(&session_id) {
-                proc.status = ProcessStatus::Running;
+                proc.status = status.clone();
             }
         }
 
@@ -293,38 +581,680 @@ impl ProcessManager {
             crate::events::CLAUDE_SESSION_STATUS,
             serde_json::json!({
                 "sessionId": session_id,
-                "status": "active"
+                "status": status.to_string()
             }),
         );
+        self.persist_session(&session_id, &app_handle).await;
 
         Ok(())
     }
 
-    /// Send a message to a session. For the first message, writes to stdin.
-    /// For subsequent messages, spawns a new process with --resume.
+    /// Write a session's ID mapping, launch settings, status and PID to the
+    /// `sessions` table so it can be resumed after an app restart.
+    /// Failures are logged rather than failing the session.
+    async fn persist_session(&self, session_id: &str, app: &Host) {
+        let (project_path, status, pid) = {
+            let procs = self.processes.lock().await;
+            let Some(proc) = procs.get(session_id) else {
+                return;
+            };
+            (proc.project_path.clone(), proc.status.to_string(), proc.child.id())
+        };
+        let launch = self.launch_settings(session_id).await;
+        let session = db::sessions::SessionRuntime {
+            id: session_id.to_string(),
+            claude_session_id: self.get_claude_session_id(session_id).await,
+            project_path,
+            status,
+            model: launch.model.clone(),
+            launch_settings: serde_json::to_string(&launch).unwrap_or_default(),
+            pid,
+        };
+
+        let result = match app.database().await {
+            Ok(pool) => db::sessions::save(&pool, &session).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            log::warn!("[process:{}] failed to persist session: {}", session_id, e);
+        }
+    }
+
+    /// Append an entry to a session's activity log in the database.
+    /// Failures are logged rather than failing the session.
+    async fn log_activity(
+        &self,
+        session_id: &str,
+        kind: &str,
+        entry: &impl Serialize,
+        app: &Host,
+    ) {
+        let payload = match serde_json::to_value(entry) {
+            Ok(payload) => payload,
+            Err(e) => {
+                log::warn!("[process:{}] failed to serialize {}: {}", session_id, kind, e);
+                return;
+            }
+        };
+        let result = match app.database().await {
+            Ok(pool) => db::activity::append(&pool, session_id, kind, &payload).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            log::warn!("[process:{}] failed to log activity: {}", session_id, e);
+        }
+    }
+
+    /// Log a message's token usage and add it to the session's stored totals.
+    /// Failures are logged rather than failing the session.
+    async fn record_usage(&self, session_id: &str, usage: &MessageUsage, app: &Host) {
+        let result = match app.database().await {
+            Ok(pool) => db::usage::record(&pool, session_id, usage).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            log::warn!("[process:{}] failed to record usage: {}", session_id, e);
+        }
+    }
+
+    /// Emit a finished turn's summary and store it, tagged with the prompt
+    /// the turn ran. Failures to store are logged rather than failing the session.
+    async fn record_turn_summary(
+        &self,
+        session_id: &str,
+        generation: u64,
+        mut summary: TurnSummary,
+        app: &Host,
+    ) {
+        summary.prompt_id = self
+            .processes
+            .lock()
+            .await
+            .get(session_id)
+            .filter(|p| p.generation == generation)
+            .and_then(|p| p.current_prompt.as_ref())
+            .map(|prompt| prompt.id.clone());
+        match serde_json::to_value(&summary) {
+            Ok(payload) => {
+                let _ = app.emit(crate::events::CLAUDE_TURN_SUMMARY, payload);
+            }
+            Err(e) => {
+                log::warn!("[process:{}] failed to serialize turn summary: {}", session_id, e)
+            }
+        }
+
+        let result = match app.database().await {
+            Ok(pool) => db::turn_summaries::save(&pool, &summary).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            log::warn!("[process:{}] failed to store turn summary: {}", session_id, e);
+        }
+    }
+
+    /// Record the end of a turn after the CLI emitted its `result` event.
+    /// A persistent process goes back to waiting for the next user turn and
+    /// picks up the next queued prompt, if any.
+    async fn finish_turn(
+        self: &Arc<Self>,
+        session_id: &str,
+        generation: u64,
+        mut outcome: TurnOutcome,
+        app: &Host,
+    ) {
+        let mut procs = self.processes.lock().await;
+        let Some(proc) = procs
+            .get_mut(session_id)
+            .filter(|p| p.generation == generation)
+        else {
+            return;
+        };
+
+        proc.turn_in_flight = false;
+        proc.stalled = false;
+        proc.turns_completed += 1;
+        proc.last_result_is_error = Some(outcome.is_error);
+        if let Some(prompt) = proc.current_prompt.take() {
+            outcome.prompt_id = prompt.id;
+            self.report_outcome(outcome);
+        }
+        if let Some(started) = proc.turn_started_at.take() {
+            log::debug!(
+                "[process:{}] turn {} finished in {:?}",
+                session_id,
+                proc.turns_completed,
+                started.elapsed()
+            );
+        }
+
+        if proc.mode != SessionMode::Persistent {
+            return;
+        }
+
+        proc.status = ProcessStatus::WaitingInput;
+        let _ = app.emit(
+            crate::events::CLAUDE_SESSION_STATUS,
+            serde_json::json!({
+                "sessionId": session_id,
+                "status": proc.status.to_string()
+            }),
+        );
+        drop(procs);
+        self.persist_session(session_id, app).await;
+
+        if let Some(queue) = self.queues.lock().await.get_mut(session_id) {
+            queue.finish_turn();
+            queue.emit_update(session_id, app);
+        }
+        self.release_turn_slot(session_id, app).await;
+        if let Err(e) = Arc::clone(self)
+            .dispatch_next(session_id.to_string(), app.clone())
+            .await
+        {
+            Self::report_dispatch_error(session_id, &e, app);
+        }
+    }
+
+    /// Wait for a session's process to exit and collect its exit code or signal.
+    /// Returns `None` if the process was replaced or removed in the meantime.
+    async fn reap(&self, session_id: &str, generation: u64) -> Option<ExitInfo> {
+        loop {
+            {
+                let mut procs = self.processes.lock().await;
+                let proc = procs
+                    .get_mut(session_id)
+                    .filter(|p| p.generation == generation)?;
+                if let Some(exit) = &proc.exit {
+                    return Some(exit.clone());
+                }
+                match proc.child.try_wait() {
+                    Ok(Some(status)) => return Some(ExitInfo::from(status)),
+                    Ok(None) => {}
+                    Err(e) => {
+                        log::warn!("[process:{}] failed to reap child: {}", session_id, e);
+                        return Some(ExitInfo {
+                            code: None,
+                            signal: None,
+                        });
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    /// Decide the final status of an exited process from its exit code and
+    /// the `is_error` flag of its last `result` event
+    fn exit_status(proc: &ClaudeProcess, exit: &ExitInfo) -> ProcessStatus {
+        if proc.cancel_requested {
+            ProcessStatus::Cancelled
+        } else if exit.code == Some(0) && proc.last_result_is_error != Some(true) {
+            ProcessStatus::Completed
+        } else {
+            ProcessStatus::Error
+        }
+    }
+
+    /// Start the next queued prompt for a session unless a turn is already
+    /// running or no turn slot is free. In the latter case the session waits
+    /// in the scheduler and is dispatched again once a slot is handed to it.
+    ///
+    /// Returns a boxed future because it is reached from the stdout reader
+    /// task that `spawn` itself creates.
+    fn dispatch_next(
+        self: Arc<Self>,
+        session_id: String,
+        app_handle: Host,
+    ) -> Pin<Box<dyn Future<Output = Result<(), String>> + Send>> {
+        Box::pin(async move {
+            let next = {
+                let mut queues = self.queues.lock().await;
+                let queue = queues.get_mut(&session_id);
+                if queue.as_ref().is_some_and(|q| q.is_busy()) {
+                    return Ok(());
+                }
+                match queue {
+                    Some(queue) if queue.has_pending() => {
+                        let mut scheduler = self.scheduler.lock().await;
+                        if !scheduler.acquire(&session_id) {
+                            scheduler.emit_positions(&[], &app_handle);
+                            return Ok(());
+                        }
+                        let next = queue.start_next();
+                        queue.emit_update(&session_id, &app_handle);
+                        next
+                    }
+                    _ => None,
+                }
+            };
+            let Some(prompt) = next else {
+                // Nothing left to run — give back a slot granted while waiting
+                self.release_turn_slot(&session_id, &app_handle).await;
+                return Ok(());
+            };
+
+            let result = self.start_turn(&session_id, &prompt, app_handle.clone()).await;
+
+            if result.is_err() {
+                self.report_outcome(TurnOutcome::ended(&session_id, &prompt, "failed"));
+                // The turn never started — release the queue so it isn't stuck
+                if let Some(queue) = self.queues.lock().await.get_mut(&session_id) {
+                    queue.finish_turn();
+                    queue.emit_update(&session_id, &app_handle);
+                }
+                self.release_turn_slot(&session_id, &app_handle).await;
+            }
+            result
+        })
+    }
+
+    /// Give up a session's turn slot (or its place in line) and start the
+    /// sessions that were waiting for it
+    async fn release_turn_slot(self: &Arc<Self>, session_id: &str, app: &Host) {
+        let woken = {
+            let mut scheduler = self.scheduler.lock().await;
+            let woken = scheduler.release(session_id);
+            scheduler.emit_positions(&woken, app);
+            woken
+        };
+        self.dispatch_woken(woken, app).await;
+    }
+
+    /// Dispatch sessions the scheduler just granted a slot to
+    async fn dispatch_woken(self: &Arc<Self>, woken: Vec<String>, app: &Host) {
+        for session_id in woken {
+            if let Err(e) = Arc::clone(self)
+                .dispatch_next(session_id.clone(), app.clone())
+                .await
+            {
+                Self::report_dispatch_error(&session_id, &e, app);
+            }
+        }
+    }
+
+    /// Change how many turns may run at once across all sessions.
+    /// Raising the limit starts waiting sessions right away.
+    pub async fn set_max_concurrent_turns(
+        self: &Arc<Self>,
+        max_concurrent: usize,
+        app_handle: &Host,
+    ) {
+        let woken = {
+            let mut scheduler = self.scheduler.lock().await;
+            let woken = scheduler.set_max_concurrent(max_concurrent);
+            scheduler.emit_positions(&woken, app_handle);
+            woken
+        };
+        self.dispatch_woken(woken, app_handle).await;
+    }
+
+    /// Set whether a session competes for turn slots as interactive or background
+    pub async fn set_priority(
+        &self,
+        session_id: &str,
+        priority: SessionPriority,
+        app_handle: &Host,
+    ) {
+        let mut scheduler = self.scheduler.lock().await;
+        scheduler.set_priority(session_id, priority);
+        scheduler.emit_positions(&[], app_handle);
+    }
+
+    /// Running and waiting sessions plus the current concurrency limit
+    pub async fn scheduler_status(&self) -> SchedulerStatus {
+        self.scheduler.lock().await.status()
+    }
+
+    /// Surface a queued prompt that failed to start, since there's no caller to return it to
+    fn report_dispatch_error(session_id: &str, error: &str, app: &Host) {
+        log::warn!("[process:{}] failed to start queued prompt: {}", session_id, error);
+        let _ = app.emit(
+            crate::events::CLAUDE_STDERR,
+            serde_json::json!({
+                "sessionId": session_id,
+                "text": format!("Failed to start queued prompt: {}", error),
+            }),
+        );
+    }
+
+    /// Change the stall threshold and per-turn time limit. Running watchdogs
+    /// pick up the new values on their next tick.
+    pub async fn set_watchdog_settings(&self, settings: WatchdogSettings) {
+        *self.watchdog.lock().await = settings;
+    }
+
+    pub async fn watchdog_settings(&self) -> WatchdogSettings {
+        self.watchdog.lock().await.clone()
+    }
+
+    /// Replace the environment profiles available to new spawns. Running
+    /// processes keep the environment they were started with.
+    pub async fn set_env_profiles(&self, profiles: Vec<EnvProfile>) {
+        *self.env_profiles.lock().await = profiles;
+    }
+
+    /// All environment profiles, with secrets unmasked
+    pub async fn env_profiles(&self) -> Vec<EnvProfile> {
+        self.env_profiles.lock().await.clone()
+    }
+
+    /// The profile named in `launch`, else the default of `project_path`.
+    /// Fails if the named profile no longer exists rather than spawning
+    /// without, e.g., the proxy it sets up.
+    async fn env_profile_for(
+        &self,
+        launch: &LaunchSettings,
+        project_path: &str,
+    ) -> Result<Option<EnvProfile>, String> {
+        let profiles = self.env_profiles.lock().await;
+        match &launch.env_profile {
+            Some(id) => profiles
+                .iter()
+                .find(|p| &p.id == id)
+                .cloned()
+                .map(Some)
+                .ok_or_else(|| format!("Environment profile {} not found", id)),
+            None => Ok(profiles
+                .iter()
+                .find(|p| p.project_path.as_deref() == Some(project_path))
+                .cloned()),
+        }
+    }
+
+    /// The broker answering tool permission prompts
+    pub fn permissions(&self) -> &Arc<PermissionBroker> {
+        &self.permissions
+    }
+
+    /// The permission-prompt server a new process should ask before using
+    /// tools, unless approvals are off or the session bypasses permissions
+    async fn permission_server_for(
+        &self,
+        opts: &SpawnOptions,
+        app: &Host,
+    ) -> Result<Option<PermissionServer>, String> {
+        let bypass = opts.launch.permission_mode == Some(PermissionMode::BypassPermissions);
+        if bypass || !self.permissions.settings().await.enabled {
+            return Ok(None);
+        }
+        self.permissions
+            .server(&opts.session_id, &opts.project_path, app)
+            .await
+            .map(Some)
+    }
+
+    /// Note that stdout produced a line, ending a reported stall
+    async fn record_output(&self, session_id: &str, generation: u64, app: &Host) {
+        let mut procs = self.processes.lock().await;
+        let Some(proc) = procs
+            .get_mut(session_id)
+            .filter(|p| p.generation == generation)
+        else {
+            return;
+        };
+        proc.last_output_at = Instant::now();
+        if proc.stalled {
+            proc.stalled = false;
+            log::info!("[process:{}] output resumed after stall", session_id);
+            Self::emit_stalled(session_id, proc, None, app);
+        }
+    }
+
+    /// Watch one spawn of a session's process until it exits or is replaced,
+    /// reporting stalls and enforcing the per-turn time limit
+    async fn watch(self: Arc<Self>, session_id: String, generation: u64, app: Host) {
+        let mut tick = tokio::time::interval(WATCHDOG_TICK);
+        loop {
+            tick.tick().await;
+            let settings = self.watchdog_settings().await;
+            let awaiting_approval = self.permissions.is_waiting(&session_id).await;
+
+            let (action, prompt) = {
+                let mut procs = self.processes.lock().await;
+                let Some(proc) = procs
+                    .get_mut(&session_id)
+                    .filter(|p| p.generation == generation && p.exit.is_none())
+                else {
+                    return;
+                };
+                if !proc.turn_in_flight || proc.cancel_requested {
+                    continue;
+                }
+                // Silence while the user decides on a tool call isn't a stall
+                if awaiting_approval {
+                    proc.last_output_at = Instant::now();
+                    continue;
+                }
+
+                let idle = proc.last_output_at.elapsed();
+                let elapsed = proc.turn_started_at.map(|t| t.elapsed()).unwrap_or_default();
+                match settings.check(idle, elapsed, proc.stalled) {
+                    Verdict::Healthy => continue,
+                    Verdict::Stalled => {
+                        proc.stalled = true;
+                        log::warn!("[process:{}] no output for {:?}", session_id, idle);
+                        Self::emit_stalled(&session_id, proc, None, &app);
+                        continue;
+                    }
+                    Verdict::TimedOut => {
+                        let can_retry = proc
+                            .current_prompt
+                            .as_ref()
+                            .is_some_and(|p| p.attempt < MAX_TURN_ATTEMPTS);
+                        let action = match settings.on_timeout {
+                            TimeoutAction::Retry if can_retry => TimeoutAction::Retry,
+                            _ => TimeoutAction::Interrupt,
+                        };
+                        log::warn!(
+                            "[process:{}] turn timed out after {:?}, {:?}",
+                            session_id,
+                            elapsed,
+                            action
+                        );
+                        proc.stalled = true;
+                        Self::emit_stalled(&session_id, proc, Some(action), &app);
+                        // A retried prompt isn't over yet, so no outcome is reported for it
+                        let prompt = match action {
+                            TimeoutAction::Retry => proc.current_prompt.take(),
+                            TimeoutAction::Interrupt => proc.current_prompt.clone(),
+                        };
+                        (action, prompt)
+                    }
+                }
+            };
+
+            // This process is being stopped; a retry gets a watchdog of its own
+            self.handle_turn_timeout(&session_id, action, prompt, &app).await;
+            return;
+        }
+    }
+
+    /// Stop a turn that hit the time limit. A retried turn goes back to the
+    /// head of the queue, so prompts queued behind it are kept.
+    async fn handle_turn_timeout(
+        self: &Arc<Self>,
+        session_id: &str,
+        action: TimeoutAction,
+        prompt: Option<QueuedPrompt>,
+        app: &Host,
+    ) {
+        let prompt = match (action, prompt) {
+            (TimeoutAction::Retry, Some(prompt)) => prompt,
+            _ => {
+                if let Err(e) = self.interrupt(session_id, None, app).await {
+                    log::warn!(
+                        "[process:{}] failed to interrupt timed-out turn: {}",
+                        session_id,
+                        e
+                    );
+                }
+                return;
+            }
+        };
+
+        if let Err(e) = self.stop_turn(session_id, DEFAULT_INTERRUPT_GRACE, app).await {
+            log::warn!("[process:{}] failed to stop timed-out turn: {}", session_id, e);
+        }
+        if let Some(queue) = self.queues.lock().await.get_mut(session_id) {
+            queue.finish_turn();
+            queue.retry(prompt);
+            queue.emit_update(session_id, app);
+        }
+        self.release_turn_slot(session_id, app).await;
+        if let Err(e) = Arc::clone(self)
+            .dispatch_next(session_id.to_string(), app.clone())
+            .await
+        {
+            Self::report_dispatch_error(session_id, &e, app);
+        }
+    }
+
+    /// Tell the frontend a turn stalled, hit its time limit (`timeout` set),
+    /// or produced output again after a stall
+    fn emit_stalled(
+        session_id: &str,
+        proc: &ClaudeProcess,
+        timeout: Option<TimeoutAction>,
+        app: &Host,
+    ) {
+        let _ = app.emit(
+            crate::events::CLAUDE_SESSION_STALLED,
+            serde_json::json!({
+                "sessionId": session_id,
+                "stalled": proc.stalled,
+                "idleSecs": proc.last_output_at.elapsed().as_secs(),
+                "turnSecs": proc.turn_started_at.map(|t| t.elapsed().as_secs()),
+                "timedOut": timeout.is_some(),
+                "action": timeout,
+                "attempt": proc.current_prompt.as_ref().map(|p| p.attempt),
+            }),
+        );
+    }
+
+    /// Write a user turn, encoded by the session's provider, to the
+    /// process's stdin. Persistent processes keep stdin open; respawned
+    /// processes get EOF after the prompt.
+    async fn write_turn(proc: &mut ClaudeProcess, content: &[ContentBlock]) -> Result<(), String> {
+        let input = proc.provider.encode_turn(proc.mode, content)?;
+        let stdin = proc
+            .stdin
+            .as_mut()
+            .ok_or("Session process is not accepting input")?;
+
+        match proc.mode {
+            SessionMode::Persistent => {
+                stdin
+                    .write_all(input.as_bytes())
+                    .await
+                    .map_err(|e| format!("Failed to write: {}", e))?;
+                stdin
+                    .flush()
+                    .await
+                    .map_err(|e| format!("Failed to flush stdin: {}", e))?;
+            }
+            SessionMode::Respawn => {
+                stdin
+                    .write_all(input.as_bytes())
+                    .await
+                    .map_err(|e| format!("Failed to write: {}", e))?;
+                stdin
+                    .shutdown()
+                    .await
+                    .map_err(|e| format!("Failed to close stdin: {}", e))?;
+                proc.stdin = None;
+            }
+        }
+
+        proc.turn_in_flight = true;
+        proc.turn_started_at = Some(Instant::now());
+        proc.last_output_at = Instant::now();
+        proc.stalled = false;
+        proc.status = ProcessStatus::Running;
+        Ok(())
+    }
+
+    /// Send a message to a session.
+    ///
+    /// The message joins the session's queue and starts right away if no turn
+    /// is running; otherwise it waits for the current turn's `result`.
     ///
     /// `project_path` is required for discovered sessions that have no process
     /// entry yet — the frontend passes it from the session metadata.
     pub async fn send_message(
-        &self,
+        self: &Arc<Self>,
         session_id: &str,
         message: &str,
         project_path: &str,
-        app_handle: tauri::AppHandle,
+        app_handle: Host,
+    ) -> Result<QueuedPrompt, String> {
+        let content = vec![ContentBlock::text(message)];
+        self.send_content(session_id, content, project_path, app_handle)
+            .await
+    }
+
+    /// Send a turn made of content blocks (text, images, documents), queued
+    /// like [`send_message`](Self::send_message). The blocks must already be
+    /// validated, see [`content::resolve`](super::content::resolve).
+    pub async fn send_content(
+        self: &Arc<Self>,
+        session_id: &str,
+        content: Vec<ContentBlock>,
+        project_path: &str,
+        app_handle: Host,
+    ) -> Result<QueuedPrompt, String> {
+        let prompt = {
+            let mut queues = self.queues.lock().await;
+            let queue = queues.entry(session_id.to_string()).or_default();
+            let prompt = queue.push(content, project_path);
+            queue.emit_update(session_id, &app_handle);
+            prompt
+        };
+
+        Arc::clone(self)
+            .dispatch_next(session_id.to_string(), app_handle)
+            .await?;
+        Ok(prompt)
+    }
+
+    /// Receive the outcome of every turn started from a queued prompt,
+    /// including prompts dropped before they ran
+    pub fn subscribe_turns(&self) -> broadcast::Receiver<TurnOutcome> {
+        self.turn_outcomes.subscribe()
+    }
+
+    fn report_outcome(&self, outcome: TurnOutcome) {
+        // No subscribers is fine
+        let _ = self.turn_outcomes.send(outcome);
+    }
+
+    /// Start a turn on the session's process.
+    ///
+    /// Writes the turn to the session's live process when it can take one —
+    /// any persistent process that is still running, or a freshly spawned
+    /// respawn-mode process that hasn't received its prompt yet. Otherwise
+    /// spawns a new process that resumes the conversation first.
+    async fn start_turn(
+        self: &Arc<Self>,
+        session_id: &str,
+        prompt: &QueuedPrompt,
+        app_handle: Host,
     ) -> Result<(), String> {
         let needs_respawn = {
             let procs = self.processes.lock().await;
             match procs.get(session_id) {
                 Some(proc) => {
                     proc.stdin.is_none()
-                        || proc.status == ProcessStatus::Completed
+                        || matches!(
+                            proc.status,
+                            ProcessStatus::Completed | ProcessStatus::Error | ProcessStatus::Cancelled
+                        )
                 }
                 None => true,
             }
         };
 
         if needs_respawn {
-            // Need to spawn a new process with --resume
+            // Need to spawn a new process that resumes the conversation
             let (resolved_path, claude_session_id) = {
                 let procs = self.processes.lock().await;
                 // Use project_path from existing process if available,
@@ -332,93 +1262,402 @@ impl ProcessManager {
                 let path = procs
                     .get(session_id)
                     .map(|p| p.project_path.clone())
-                    .unwrap_or_else(|| project_path.to_string());
+                    .unwrap_or_else(|| prompt.project_path.clone());
 
                 let map = self.claude_session_map.lock().await;
-                // Check the map first; for discovered sessions the session_id
-                // itself IS the Claude session ID
-                let claude_sid = map
-                    .get(session_id)
-                    .cloned()
-                    .or_else(|| Some(session_id.to_string()));
-                (path, claude_sid)
+                (path, map.get(session_id).cloned())
             };
 
-            let claude_sid = claude_session_id
-                .ok_or("No Claude session ID found — cannot resume session")?;
+            let launch = self.launch_settings(session_id).await;
+            let claude_sid = match claude_session_id {
+                Some(claude_sid) => claude_sid,
+                // A session discovered on disk goes by its Claude session ID
+                None if self
+                    .provider(launch.provider.as_deref())
+                    .await?
+                    .has_transcript(session_id) =>
+                {
+                    session_id.to_string()
+                }
+                None => {
+                    return Err(format!(
+                        "Session {} has no recorded Claude session ID, so its \
+                         conversation can't be resumed",
+                        session_id
+                    ))
+                }
+            };
 
-            // Remove old process
+            // Remove the finished process — the queue never respawns mid-turn
             {
                 let mut procs = self.processes.lock().await;
                 if let Some(mut old) = procs.remove(session_id) {
-                    let _ = old.child.kill().await;
+                    let _ = Self::kill_tree(&mut old).await;
                 }
             }
 
-            // Spawn new process with --resume and the message piped to stdin
+            // Spawn the new process, keeping the session's provider and settings
             self.spawn(
                 SpawnOptions {
                     session_id: session_id.to_string(),
                     project_path: resolved_path,
                     claude_cli_path: None,
                     resume_session_id: Some(claude_sid),
-                    model: None,
+                    launch,
                 },
-                app_handle,
+                app_handle.clone(),
             )
             .await?;
+        }
 
-            // Now write the message to the new process's stdin
+        {
             let mut procs = self.processes.lock().await;
-            if let Some(proc) = procs.get_mut(session_id) {
-                if let Some(stdin) = proc.stdin.as_mut() {
-                    stdin
-                        .write_all(message.as_bytes())
-                        .await
-                        .map_err(|e| format!("Failed to write: {}", e))?;
-                    stdin
-                        .shutdown()
-                        .await
-                        .map_err(|e| format!("Failed to close stdin: {}", e))?;
-                    proc.stdin = None;
-                }
-            }
-        } else {
-            // First message — write to existing stdin
+            let proc = procs
+                .get_mut(session_id)
+                .ok_or_else(|| format!("Session {} not found", session_id))?;
+            Self::write_turn(proc, &prompt.content).await?;
+            proc.current_prompt = Some(prompt.clone());
+        }
+
+        let _ = app_handle.emit(
+            crate::events::CLAUDE_SESSION_STATUS,
+            serde_json::json!({
+                "sessionId": session_id,
+                "status": "active"
+            }),
+        );
+        self.persist_session(session_id, &app_handle).await;
+
+        Ok(())
+    }
+
+    /// Remember the launch settings to use whenever this session is spawned
+    pub async fn set_launch_settings(&self, session_id: &str, launch: LaunchSettings) {
+        let mut settings = self.launch_settings.lock().await;
+        settings.insert(session_id.to_string(), launch);
+    }
+
+    /// Get a session's launch settings (defaults if none were set)
+    pub async fn launch_settings(&self, session_id: &str) -> LaunchSettings {
+        let settings = self.launch_settings.lock().await;
+        settings.get(session_id).cloned().unwrap_or_default()
+    }
+
+    /// A session's emission settings and counters, created on first use
+    async fn session_events(&self, session_id: &str) -> SessionEvents {
+        let mut events = self.session_events.lock().await;
+        events.entry(session_id.to_string()).or_default().clone()
+    }
+
+    /// Turn mirroring of raw CLI events (`claude:stream_event`) on or off.
+    /// Takes effect immediately, including for a running process.
+    pub async fn set_raw_event_mirroring(&self, session_id: &str, enabled: bool) {
+        let events = self.session_events(session_id).await;
+        events.mirror_raw.store(enabled, Ordering::Relaxed);
+    }
+
+    /// How many events a session has produced, emitted, merged and dropped
+    pub async fn event_metrics(&self, session_id: &str) -> EventMetricsSnapshot {
+        self.session_events(session_id).await.metrics.snapshot()
+    }
+
+    /// Get the prompts waiting behind a session's current turn
+    pub async fn queued_prompts(&self, session_id: &str) -> Vec<QueuedPrompt> {
+        let queues = self.queues.lock().await;
+        queues
+            .get(session_id)
+            .map(|q| q.prompts())
+            .unwrap_or_default()
+    }
+
+    /// Move a queued prompt to a new position in the session's queue
+    pub async fn move_queued_prompt(
+        &self,
+        session_id: &str,
+        prompt_id: &str,
+        index: usize,
+        app_handle: &Host,
+    ) -> Result<Vec<QueuedPrompt>, String> {
+        let mut queues = self.queues.lock().await;
+        let queue = queues
+            .get_mut(session_id)
+            .ok_or_else(|| format!("Session {} has no queued prompts", session_id))?;
+        queue.move_to(prompt_id, index)?;
+        queue.emit_update(session_id, app_handle);
+        Ok(queue.prompts())
+    }
+
+    /// Remove a prompt from the session's queue before it starts.
+    /// A session left with nothing to run gives up its place in the scheduler.
+    pub async fn cancel_queued_prompt(
+        self: &Arc<Self>,
+        session_id: &str,
+        prompt_id: &str,
+        app_handle: &Host,
+    ) -> Result<QueuedPrompt, String> {
+        let (prompt, idle) = {
+            let mut queues = self.queues.lock().await;
+            let queue = queues
+                .get_mut(session_id)
+                .ok_or_else(|| format!("Session {} has no queued prompts", session_id))?;
+            let prompt = queue.cancel(prompt_id)?;
+            queue.emit_update(session_id, app_handle);
+            (prompt, !queue.is_busy() && !queue.has_pending())
+        };
+        self.report_outcome(TurnOutcome::ended(session_id, &prompt, "cancelled"));
+        if idle {
+            self.release_turn_slot(session_id, app_handle).await;
+        }
+        Ok(prompt)
+    }
+
+    /// Interrupt a session's running turn, escalating until the process exits.
+    ///
+    /// Sends SIGINT so the CLI can abort the turn and flush its JSONL
+    /// transcript, then SIGTERM and finally SIGKILL if it is still alive after
+    /// each grace period. Queued prompts are dropped. The session ends up
+    /// `Cancelled` and is resumed by the next `send_message`.
+    pub async fn interrupt(
+        self: &Arc<Self>,
+        session_id: &str,
+        grace: Option<Duration>,
+        app_handle: &Host,
+    ) -> Result<(), String> {
+        let grace = grace.unwrap_or(DEFAULT_INTERRUPT_GRACE);
+
+        self.drop_queue(session_id, app_handle).await;
+        self.release_turn_slot(session_id, app_handle).await;
+        self.stop_turn(session_id, grace, app_handle).await
+    }
+
+    /// Signal the session's process group until the CLI exits, then kill what
+    /// is left of the tree and mark the session `Cancelled`. Leaves the queue
+    /// and turn slot alone.
+    async fn stop_turn(
+        &self,
+        session_id: &str,
+        grace: Duration,
+        app_handle: &Host,
+    ) -> Result<(), String> {
+        let pgid = {
             let mut procs = self.processes.lock().await;
             let proc = procs
                 .get_mut(session_id)
                 .ok_or_else(|| format!("Session {} not found", session_id))?;
+            if matches!(
+                proc.status,
+                ProcessStatus::Completed | ProcessStatus::Error | ProcessStatus::Cancelled
+            ) {
+                return Ok(());
+            }
+            proc.cancel_requested = true;
+            proc.pgid
+        };
 
-            if let Some(stdin) = proc.stdin.as_mut() {
-                stdin
-                    .write_all(message.as_bytes())
-                    .await
-                    .map_err(|e| format!("Failed to write: {}", e))?;
-                stdin
-                    .shutdown()
-                    .await
-                    .map_err(|e| format!("Failed to close stdin: {}", e))?;
-                proc.stdin = None;
+        #[cfg(unix)]
+        let exited = match pgid {
+            Some(pgid) => self.signal_until_exit(session_id, pgid, grace).await,
+            None => true,
+        };
+        #[cfg(not(unix))]
+        let exited = {
+            let _ = (pgid, grace);
+            false
+        };
+
+        // Also takes down anything the agent started that ignored the signals.
+        // The CLI is reaped without holding the process table, so other
+        // sessions aren't blocked meanwhile.
+        if let Some(proc) = self.processes.lock().await.get_mut(session_id) {
+            if !exited {
+                log::warn!("[process:{}] did not exit after interrupt, killing", session_id);
             }
+            Self::start_kill_tree(proc)?;
+        }
+        if !self.wait_for_exit(session_id, KILL_REAP_TIMEOUT).await {
+            log::warn!("[process:{}] still running after SIGKILL", session_id);
         }
 
+        let mut procs = self.processes.lock().await;
+        if let Some(proc) = procs.get_mut(session_id) {
+            proc.stdin = None;
+            proc.turn_in_flight = false;
+            if let Some(prompt) = proc.current_prompt.take() {
+                self.report_outcome(TurnOutcome::ended(session_id, &prompt, "cancelled"));
+            }
+            Self::mark_cancelled(session_id, proc, app_handle);
+        }
+        drop(procs);
+        self.persist_session(session_id, app_handle).await;
         Ok(())
     }
 
-    /// Kill a running process
-    pub async fn kill(&self, session_id: &str) -> Result<(), String> {
-        let mut procs = self.processes.lock().await;
-        if let Some(mut process) = procs.remove(session_id) {
-            process
-                .child
-                .kill()
-                .await
-                .map_err(|e| format!("Failed to kill process: {}", e))?;
+    /// Send SIGINT, then SIGTERM to the session's process group, waiting
+    /// `grace` after each. Returns whether the CLI process exited.
+    #[cfg(unix)]
+    async fn signal_until_exit(&self, session_id: &str, pgid: u32, grace: Duration) -> bool {
+        for signal in [libc::SIGINT, libc::SIGTERM] {
+            if !signal_group(pgid, signal) {
+                log::warn!(
+                    "[process:{}] failed to send signal {} to process group {}",
+                    session_id,
+                    signal,
+                    pgid
+                );
+            }
+            if self.wait_for_exit(session_id, grace).await {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Poll until the session's process has exited or `timeout` elapses
+    async fn wait_for_exit(&self, session_id: &str, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            {
+                let mut procs = self.processes.lock().await;
+                match procs.get_mut(session_id) {
+                    Some(proc) => {
+                        if matches!(proc.child.try_wait(), Ok(Some(_))) {
+                            return true;
+                        }
+                    }
+                    None => return true,
+                }
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    /// Drop a session's queued prompts, reporting each as cancelled
+    async fn drop_queue(&self, session_id: &str, app_handle: &Host) {
+        let dropped = match self.queues.lock().await.get_mut(session_id) {
+            Some(queue) => {
+                let dropped = queue.clear();
+                queue.emit_update(session_id, app_handle);
+                dropped
+            }
+            None => Vec::new(),
+        };
+        for prompt in dropped {
+            self.report_outcome(TurnOutcome::ended(session_id, &prompt, "cancelled"));
+        }
+    }
+
+    /// Move a session to `Cancelled`, notifying the frontend once
+    fn mark_cancelled(session_id: &str, proc: &mut ClaudeProcess, app: &Host) {
+        if proc.status == ProcessStatus::Cancelled {
+            return;
+        }
+        proc.status = ProcessStatus::Cancelled;
+        let _ = app.emit(
+            crate::events::CLAUDE_SESSION_STATUS,
+            serde_json::json!({
+                "sessionId": session_id,
+                "status": "cancelled"
+            }),
+        );
+    }
+
+    /// Kill a running process. Prompts still queued for the session are dropped.
+    pub async fn kill(
+        self: &Arc<Self>,
+        session_id: &str,
+        app_handle: &Host,
+    ) -> Result<(), String> {
+        self.drop_queue(session_id, app_handle).await;
+        self.release_turn_slot(session_id, app_handle).await;
+
+        // Out of the process table before waiting on it, so other sessions
+        // aren't blocked while the tree dies
+        let removed = self.processes.lock().await.remove(session_id);
+        if let Some(mut process) = removed {
+            if let Some(prompt) = process.current_prompt.take() {
+                self.report_outcome(TurnOutcome::ended(session_id, &prompt, "cancelled"));
+            }
+            Self::kill_tree(&mut process).await?;
+        }
+        self.permissions.revoke(session_id).await;
+
+        // No process left, but the session can still be resumed later
+        let result = match app_handle.database().await {
+            Ok(pool) => {
+                let status = ProcessStatus::Paused.to_string();
+                db::sessions::update_status(&pool, session_id, &status, None).await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            log::warn!("[process:{}] failed to persist session: {}", session_id, e);
         }
         Ok(())
     }
 
+    /// SIGKILL a session's whole process group and reap the CLI process
+    async fn kill_tree(proc: &mut ClaudeProcess) -> Result<(), String> {
+        Self::start_kill_tree(proc)?;
+        proc.child
+            .wait()
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("Failed to kill process: {}", e))
+    }
+
+    /// SIGKILL a session's whole process group without waiting for the CLI
+    /// to be reaped
+    fn start_kill_tree(proc: &mut ClaudeProcess) -> Result<(), String> {
+        #[cfg(unix)]
+        if let Some(pgid) = proc.pgid {
+            // Once the CLI is reaped its PID can be reused; if something is
+            // alive under it again, that group isn't ours anymore
+            let reused = proc.child.id().is_none() && process_alive(pgid);
+            if !reused {
+                signal_group(pgid, libc::SIGKILL);
+            }
+        }
+        proc.child
+            .start_kill()
+            .map_err(|e| format!("Failed to kill process: {}", e))
+    }
+
+    /// Terminate every session's process tree so no agent subprocess
+    /// outlives the app: SIGTERM to each group, a short grace period for
+    /// transcripts to be flushed, then SIGKILL. Called on app exit.
+    pub async fn shutdown(&self) {
+        self.queues.lock().await.clear();
+        let mut procs = self.processes.lock().await;
+
+        #[cfg(unix)]
+        {
+            let mut pending: Vec<&mut ClaudeProcess> = procs
+                .values_mut()
+                .filter(|p| p.child.id().is_some())
+                .collect();
+            for proc in &pending {
+                if let Some(pgid) = proc.pgid {
+                    signal_group(pgid, libc::SIGTERM);
+                }
+            }
+            let deadline = Instant::now() + SHUTDOWN_GRACE;
+            while !pending.is_empty() && Instant::now() < deadline {
+                pending.retain_mut(|p| matches!(p.child.try_wait(), Ok(None)));
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        }
+
+        for (session_id, mut proc) in procs.drain() {
+            if let Err(e) = Self::kill_tree(&mut proc).await {
+                log::warn!("[process:{}] failed to kill on shutdown: {}", session_id, e);
+            }
+            self.permissions.revoke(&session_id).await;
+        }
+    }
+
     /// Get the status of a session
     pub async fn get_status(&self, session_id: &str) -> Option<ProcessStatus> {
         let procs = self.processes.lock().await;
@@ -458,3 +1697,17 @@ impl ProcessManager {
         procs.keys().cloned().collect()
     }
 }
+
+/// Send a signal to every process in a process group
+#[cfg(unix)]
+pub(crate) fn signal_group(pgid: u32, signal: libc::c_int) -> bool {
+    // SAFETY: killpg(2) has no memory-safety preconditions
+    unsafe { libc::killpg(pgid as libc::pid_t, signal) == 0 }
+}
+
+/// Whether a process with this PID exists
+#[cfg(unix)]
+pub(crate) fn process_alive(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
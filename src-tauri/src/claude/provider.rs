@@ -0,0 +1,117 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use serde::Serialize;
+use tokio::process::Command;
+
+use super::content::ContentBlock;
+use super::emitter::OutboundEvent;
+use super::hooks::HookEvent;
+use super::permission_mcp::{McpConfigFile, PermissionServer};
+use super::process::{SessionMode, SpawnOptions, TurnOutcome};
+use super::session_store::{DiscoveredSession, ParsedMessage};
+use super::turn_summary::TurnSummary;
+use super::usage::MessageUsage;
+
+/// Provider used by sessions whose launch settings don't name one
+pub const DEFAULT_PROVIDER: &str = "claude";
+
+/// A coding agent CLI that the `ProcessManager` can run sessions with.
+///
+/// The manager owns everything agent-independent — process groups, queues,
+/// turn slots, the watchdog and event emission. A provider decides how its
+/// binary is started, how a user turn is written to stdin, and how each
+/// NDJSON line on stdout maps to frontend events. Whatever the agent, events
+/// keep their `claude:` names so the frontend renders every session alike.
+pub trait AgentProvider: Send + Sync {
+    /// Stable ID stored in a session's launch settings, e.g. `claude`
+    fn id(&self) -> &'static str;
+
+    /// Human-readable name for the session list
+    fn display_name(&self) -> &'static str;
+
+    /// Build the command that starts the agent for a session, resuming
+    /// `opts.resume_session_id` if set. The manager sets the working
+    /// directory, pipes and process group.
+    ///
+    /// With `permissions` set, the agent should ask that MCP server before
+    /// using tools. Agents that can't leave approvals to their own settings.
+    fn command<'a>(
+        &'a self,
+        opts: &'a SpawnOptions,
+        permissions: Option<&'a PermissionServer>,
+    ) -> Pin<Box<dyn Future<Output = Result<AgentCommand, String>> + Send + 'a>>;
+
+    /// Encode a user turn for the agent's stdin. Persistent processes keep
+    /// stdin open afterwards, respawned ones get EOF. Fails for content the
+    /// agent can't take, e.g. images in a plain-text prompt.
+    fn encode_turn(&self, mode: SessionMode, content: &[ContentBlock]) -> Result<String, String>;
+
+    /// A parser for the stdout of one process
+    fn parser(&self) -> Box<dyn LineParser>;
+
+    /// Saved conversations this agent can resume
+    fn discover_sessions(&self) -> Vec<DiscoveredSession> {
+        Vec::new()
+    }
+
+    /// Whether a saved conversation with this ID exists, e.g. one discovered
+    /// on disk
+    fn has_transcript(&self, _agent_session_id: &str) -> bool {
+        false
+    }
+
+    /// Messages of a saved conversation, for display in the chat UI
+    fn session_messages(&self, _agent_session_id: &str) -> Vec<ParsedMessage> {
+        Vec::new()
+    }
+
+    /// Copy a saved conversation up to and including one of its messages
+    /// into a new one, returning the new conversation's ID
+    fn fork_transcript(
+        &self,
+        _agent_session_id: &str,
+        _message_uuid: &str,
+    ) -> Result<String, String> {
+        Err(format!("{} sessions can't be forked", self.display_name()))
+    }
+}
+
+/// A command ready to spawn, and how the process takes its turns
+pub struct AgentCommand {
+    pub command: Command,
+    pub mode: SessionMode,
+    /// MCP config the command reads at startup, kept until the process is gone
+    pub mcp_config: Option<McpConfigFile>,
+}
+
+/// Turns a process's stdout into frontend events, one line at a time.
+/// Parsers may keep state across lines of the same process.
+pub trait LineParser: Send {
+    fn parse_line(&mut self, session_id: &str, line: &str) -> ParsedLine;
+}
+
+/// What one stdout line means to the `ProcessManager`
+#[derive(Default)]
+pub struct ParsedLine {
+    /// Events to send to the frontend, in order
+    pub events: Vec<OutboundEvent>,
+    /// The agent's own ID for the conversation, when the line carries it
+    pub agent_session_id: Option<String>,
+    /// Set when the line ends the current turn (prompt ID filled in later)
+    pub turn_end: Option<TurnOutcome>,
+    /// Timings, cost and denials of the turn that ended, if the agent reports them
+    pub turn_summary: Option<TurnSummary>,
+    /// API messages the line finished, each reported once, for the usage log
+    pub usage: Vec<MessageUsage>,
+    /// Hooks that finished running, for the session's activity log
+    pub finished_hooks: Vec<HookEvent>,
+}
+
+/// A registered provider, as listed to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderInfo {
+    pub id: String,
+    pub name: String,
+}
@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use super::content::{self, ContentBlock};
+use super::host::Host;
+
+/// A user prompt waiting for the session's current turn to finish
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedPrompt {
+    pub id: String,
+    /// The turn's text, for display
+    pub text: String,
+    /// Every part of the turn in order, as sent to the CLI. Kept out of queue
+    /// updates since attachments can be large.
+    #[serde(skip)]
+    pub content: Vec<ContentBlock>,
+    /// Short descriptions of the images and documents in `content`
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    pub project_path: String,
+    pub queued_at: String,
+    /// 1 for the first try, higher when the watchdog retries a timed-out turn
+    pub attempt: u32,
+}
+
+/// FIFO of pending prompts for one session.
+///
+/// `busy` is set while a turn dispatched from this queue is running, so the
+/// next prompt is only started once the CLI has finished the current one.
+#[derive(Debug, Default)]
+pub struct SessionQueue {
+    pending: VecDeque<QueuedPrompt>,
+    busy: bool,
+}
+
+impl SessionQueue {
+    pub fn push(&mut self, content: Vec<ContentBlock>, project_path: &str) -> QueuedPrompt {
+        let prompt = QueuedPrompt {
+            id: uuid::Uuid::new_v4().to_string(),
+            text: content::text_of(&content),
+            attachments: content.iter().filter_map(ContentBlock::label).collect(),
+            content,
+            project_path: project_path.to_string(),
+            queued_at: chrono::Utc::now().to_rfc3339(),
+            attempt: 1,
+        };
+        self.pending.push_back(prompt.clone());
+        prompt
+    }
+
+    /// Put a prompt back at the head of the queue as its next attempt
+    pub fn retry(&mut self, prompt: QueuedPrompt) {
+        self.pending.push_front(QueuedPrompt {
+            attempt: prompt.attempt + 1,
+            queued_at: chrono::Utc::now().to_rfc3339(),
+            ..prompt
+        });
+    }
+
+    /// Take the next prompt and mark the session busy, unless a turn is already running
+    pub fn start_next(&mut self) -> Option<QueuedPrompt> {
+        if self.busy {
+            return None;
+        }
+        let next = self.pending.pop_front()?;
+        self.busy = true;
+        Some(next)
+    }
+
+    /// Whether a turn dispatched from this queue is still running
+    pub fn is_busy(&self) -> bool {
+        self.busy
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub fn finish_turn(&mut self) {
+        self.busy = false;
+    }
+
+    pub fn prompts(&self) -> Vec<QueuedPrompt> {
+        self.pending.iter().cloned().collect()
+    }
+
+    /// Move a queued prompt to a new position (clamped to the end of the queue)
+    pub fn move_to(&mut self, prompt_id: &str, index: usize) -> Result<(), String> {
+        let from = self
+            .pending
+            .iter()
+            .position(|p| p.id == prompt_id)
+            .ok_or_else(|| format!("Queued prompt {} not found", prompt_id))?;
+        let prompt = self.pending.remove(from).expect("index from position()");
+        let to = index.min(self.pending.len());
+        self.pending.insert(to, prompt);
+        Ok(())
+    }
+
+    pub fn cancel(&mut self, prompt_id: &str) -> Result<QueuedPrompt, String> {
+        let index = self
+            .pending
+            .iter()
+            .position(|p| p.id == prompt_id)
+            .ok_or_else(|| format!("Queued prompt {} not found", prompt_id))?;
+        Ok(self.pending.remove(index).expect("index from position()"))
+    }
+
+    /// Drop all pending prompts and mark the session idle, returning what was dropped
+    pub fn clear(&mut self) -> Vec<QueuedPrompt> {
+        self.busy = false;
+        self.pending.drain(..).collect()
+    }
+
+    /// Notify the frontend of the current queue contents
+    pub fn emit_update(&self, session_id: &str, app: &Host) {
+        let _ = app.emit(
+            crate::events::CLAUDE_QUEUE_UPDATED,
+            serde_json::json!({
+                "sessionId": session_id,
+                "depth": self.pending.len(),
+                "busy": self.busy,
+                "prompts": self.prompts(),
+            }),
+        );
+    }
+}
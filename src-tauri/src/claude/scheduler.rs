@@ -0,0 +1,162 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::host::Host;
+
+/// Default number of turns that may run at once across all sessions
+pub const DEFAULT_MAX_CONCURRENT_TURNS: usize = 4;
+
+/// Scheduling priority of a session. Interactive sessions are served first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionPriority {
+    Background,
+    #[default]
+    Interactive,
+}
+
+/// A session waiting for a free turn slot
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WaitingSession {
+    pub session_id: String,
+    pub priority: SessionPriority,
+    #[serde(skip)]
+    seq: u64,
+}
+
+/// Snapshot of the scheduler for the frontend
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulerStatus {
+    pub max_concurrent: usize,
+    pub running: Vec<String>,
+    pub waiting: Vec<WaitingSession>,
+}
+
+/// Global limit on concurrently running turns.
+///
+/// A session holds a slot from the moment its turn is dispatched until the
+/// turn finishes. When all slots are taken, sessions wait in a queue ordered
+/// by priority, then arrival; freed slots are handed to the head of that
+/// queue right away so a newcomer can't overtake it.
+#[derive(Debug)]
+pub struct TurnScheduler {
+    max_concurrent: usize,
+    running: HashSet<String>,
+    waiting: Vec<WaitingSession>,
+    priorities: HashMap<String, SessionPriority>,
+    next_seq: u64,
+}
+
+impl TurnScheduler {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            max_concurrent: max_concurrent.max(1),
+            running: HashSet::new(),
+            waiting: Vec::new(),
+            priorities: HashMap::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// Take a turn slot for a session, or join the wait queue.
+    /// Returns `true` if the session holds a slot (possibly already).
+    pub fn acquire(&mut self, session_id: &str) -> bool {
+        if self.running.contains(session_id) {
+            return true;
+        }
+        if self.running.len() < self.max_concurrent {
+            self.waiting.retain(|w| w.session_id != session_id);
+            self.running.insert(session_id.to_string());
+            return true;
+        }
+
+        if !self.waiting.iter().any(|w| w.session_id == session_id) {
+            self.waiting.push(WaitingSession {
+                session_id: session_id.to_string(),
+                priority: self.priority(session_id),
+                seq: self.next_seq,
+            });
+            self.next_seq += 1;
+            self.sort_waiting();
+        }
+        false
+    }
+
+    /// Give up a session's slot (or its place in line) and hand freed slots
+    /// to waiting sessions. Returns the sessions that were granted a slot.
+    pub fn release(&mut self, session_id: &str) -> Vec<String> {
+        self.running.remove(session_id);
+        self.waiting.retain(|w| w.session_id != session_id);
+        self.grant_free_slots()
+    }
+
+    /// Change the concurrency limit. Returns sessions granted a slot by a raise.
+    pub fn set_max_concurrent(&mut self, max_concurrent: usize) -> Vec<String> {
+        self.max_concurrent = max_concurrent.max(1);
+        self.grant_free_slots()
+    }
+
+    pub fn set_priority(&mut self, session_id: &str, priority: SessionPriority) {
+        self.priorities.insert(session_id.to_string(), priority);
+        if let Some(waiter) = self.waiting.iter_mut().find(|w| w.session_id == session_id) {
+            waiter.priority = priority;
+            self.sort_waiting();
+        }
+    }
+
+    pub fn priority(&self, session_id: &str) -> SessionPriority {
+        self.priorities.get(session_id).copied().unwrap_or_default()
+    }
+
+    pub fn status(&self) -> SchedulerStatus {
+        let mut running: Vec<String> = self.running.iter().cloned().collect();
+        running.sort();
+        SchedulerStatus {
+            max_concurrent: self.max_concurrent,
+            running,
+            waiting: self.waiting.clone(),
+        }
+    }
+
+    /// Tell each waiting session its position in line (0 = next).
+    /// Sessions in `settled` are told they are no longer waiting.
+    pub fn emit_positions(&self, settled: &[String], app: &Host) {
+        for session_id in settled {
+            self.emit_position(session_id, None, app);
+        }
+        for (position, waiter) in self.waiting.iter().enumerate() {
+            self.emit_position(&waiter.session_id, Some(position), app);
+        }
+    }
+
+    fn emit_position(&self, session_id: &str, position: Option<usize>, app: &Host) {
+        let _ = app.emit(
+            crate::events::CLAUDE_SCHEDULE_UPDATED,
+            serde_json::json!({
+                "sessionId": session_id,
+                "position": position,
+                "waiting": self.waiting.len(),
+                "running": self.running.len(),
+                "maxConcurrent": self.max_concurrent,
+            }),
+        );
+    }
+
+    fn grant_free_slots(&mut self) -> Vec<String> {
+        let mut granted = Vec::new();
+        while self.running.len() < self.max_concurrent && !self.waiting.is_empty() {
+            let next = self.waiting.remove(0);
+            self.running.insert(next.session_id.clone());
+            granted.push(next.session_id);
+        }
+        granted
+    }
+
+    fn sort_waiting(&mut self) {
+        self.waiting
+            .sort_by(|a, b| b.priority.cmp(&a.priority).then(a.seq.cmp(&b.seq)));
+    }
+}
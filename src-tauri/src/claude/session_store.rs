@@ -1,8 +1,10 @@
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
-use super::types::ClaudeSessionEntry;
+use super::types::{ApiContentBlock, ClaudeSessionEntry};
 
 /// Discovered session metadata from ~/.claude/projects/
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -15,6 +17,8 @@ pub struct DiscoveredSession {
     pub message_count: usize,
     pub model: Option<String>,
     pub git_branch: Option<String>,
+    /// Provider that can resume the session
+    pub provider: String,
 }
 
 /// Scan ~/.claude/projects/ for existing Claude Code sessions
@@ -147,28 +151,162 @@ fn parse_session_file(
         message_count,
         model,
         git_branch,
+        provider: super::provider::DEFAULT_PROVIDER.to_string(),
     })
 }
 
 /// Parse messages from a JSONL session file for display in the chat UI
 pub fn parse_session_messages(claude_session_id: &str) -> Vec<ParsedMessage> {
+    match find_session_file(claude_session_id) {
+        Some(jsonl_path) => parse_messages_from_file(&jsonl_path),
+        None => Vec::new(),
+    }
+}
+
+/// Whether a session's JSONL file exists in any project directory
+pub fn session_file_exists(claude_session_id: &str) -> bool {
+    find_session_file(claude_session_id).is_some()
+}
+
+/// Locate a session's JSONL file by searching all project directories
+fn find_session_file(claude_session_id: &str) -> Option<PathBuf> {
     let claude_dir = dirs::home_dir()
         .map(|h| h.join(".claude").join("projects"))
         .unwrap_or_default();
 
-    // Search all project directories for this session ID
-    if let Ok(projects) = std::fs::read_dir(&claude_dir) {
-        for project in projects.flatten() {
-            let jsonl_path = project
-                .path()
-                .join(format!("{}.jsonl", claude_session_id));
-            if jsonl_path.exists() {
-                return parse_messages_from_file(&jsonl_path);
-            }
+    let projects = std::fs::read_dir(&claude_dir).ok()?;
+    projects
+        .flatten()
+        .map(|project| project.path().join(format!("{}.jsonl", claude_session_id)))
+        .find(|jsonl_path| jsonl_path.exists())
+}
+
+/// Create a new Claude session whose history is the given session's
+/// conversation up to and including the message `message_uuid`.
+///
+/// The CLI's `--fork-session` can only branch from the end of a conversation,
+/// so this writes a truncated copy of the JSONL file next to the original,
+/// with every entry moved to a new session ID. Resuming that ID continues
+/// from the chosen message. Returns the new Claude session ID.
+///
+/// A rewound or branched transcript also holds the messages of abandoned
+/// branches, so only the chosen message and its ancestors (followed through
+/// `parentUuid`) are copied, along with entries that aren't messages, such as
+/// summaries, from before it.
+pub fn fork_session_file(claude_session_id: &str, message_uuid: &str) -> Result<String, String> {
+    let source = find_session_file(claude_session_id)
+        .ok_or_else(|| format!("Transcript for session {} not found", claude_session_id))?;
+    let content = std::fs::read_to_string(&source)
+        .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+
+    let lines: Vec<(serde_json::Value, Option<ClaudeSessionEntry>)> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .map(|value| {
+            let entry = serde_json::from_value::<ClaudeSessionEntry>(value.clone()).ok();
+            (value, entry)
+        })
+        .collect();
+    let uuid_of = |entry: &Option<ClaudeSessionEntry>| -> Option<String> {
+        entry.as_ref().and_then(|e| e.uuid.clone())
+    };
+
+    let Some(target) = lines
+        .iter()
+        .position(|(_, entry)| uuid_of(entry).as_deref() == Some(message_uuid))
+    else {
+        return Err(format!(
+            "Message {} not found in session {}",
+            message_uuid, claude_session_id
+        ));
+    };
+    if lines[target]
+        .1
+        .as_ref()
+        .and_then(|e| e.is_sidechain)
+        .unwrap_or(false)
+    {
+        return Err("Cannot fork from a subagent message".to_string());
+    }
+
+    // The chosen message and its ancestors
+    let parents: HashMap<String, Option<String>> = lines
+        .iter()
+        .filter_map(|(_, entry)| {
+            let entry = entry.as_ref()?;
+            Some((entry.uuid.clone()?, entry.parent_uuid.clone()))
+        })
+        .collect();
+    let mut chain: HashSet<String> = HashSet::new();
+    let mut next = Some(message_uuid.to_string());
+    while let Some(uuid) = next {
+        if !chain.insert(uuid.clone()) {
+            break;
         }
+        next = parents.get(&uuid).cloned().flatten();
+    }
+
+    let fork_id = uuid::Uuid::new_v4().to_string();
+    let mut kept = Vec::new();
+    // Tool calls on the chain still waiting for their result
+    let mut open_tool_calls: HashSet<String> = HashSet::new();
+
+    for (value, entry) in &lines[..=target] {
+        match (uuid_of(entry), entry) {
+            (Some(uuid), _) if !chain.contains(&uuid) => continue,
+            (Some(_), Some(entry)) => track_tool_calls(entry, &mut open_tool_calls),
+            _ => {}
+        }
+        let mut value = value.clone();
+        if value.get("sessionId").is_some() {
+            value["sessionId"] = serde_json::Value::String(fork_id.clone());
+        }
+        kept.push(value.to_string());
+    }
+
+    if !open_tool_calls.is_empty() {
+        return Err("Cannot fork in the middle of a tool call — pick its result".to_string());
+    }
+
+    let target = source.with_file_name(format!("{}.jsonl", fork_id));
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&target)
+        .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+    for line in &kept {
+        writeln!(file, "{}", line)
+            .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
     }
 
-    Vec::new()
+    Ok(fork_id)
+}
+
+/// Add the tool calls an assistant entry makes and remove those a user
+/// entry answers with a `tool_result`
+fn track_tool_calls(entry: &ClaudeSessionEntry, open: &mut HashSet<String>) {
+    let Some(blocks) = entry
+        .message
+        .as_ref()
+        .and_then(|m| m.content.as_array())
+    else {
+        return;
+    };
+    for block in blocks {
+        match block.get("type").and_then(|t| t.as_str()) {
+            Some("tool_use") => {
+                if let Some(id) = block.get("id").and_then(|i| i.as_str()) {
+                    open.insert(id.to_string());
+                }
+            }
+            Some("tool_result") => {
+                if let Some(id) = block.get("tool_use_id").and_then(|i| i.as_str()) {
+                    open.remove(id);
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
 /// A parsed chat message suitable for the frontend
@@ -182,6 +320,17 @@ pub struct ParsedMessage {
     pub timestamp: String,
     pub is_sidechain: bool,
     pub model: Option<String>,
+    /// The message's thinking blocks, taken out of `content`
+    pub thinking: Vec<ThinkingBlock>,
+}
+
+/// Reasoning recorded in an assistant message
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ThinkingBlock {
+    /// Empty when the API redacted the reasoning
+    pub text: String,
+    pub redacted: bool,
 }
 
 fn parse_messages_from_file(file_path: &Path) -> Vec<ParsedMessage> {
@@ -213,20 +362,46 @@ fn parse_messages_from_file(file_path: &Path) -> Vec<ParsedMessage> {
             None => continue,
         };
 
+        let (content, thinking) = split_thinking(msg.content);
         messages.push(ParsedMessage {
             uuid: entry.uuid.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
             parent_uuid: entry.parent_uuid,
             role: msg.role,
-            content: msg.content,
+            content,
             timestamp: entry.timestamp.unwrap_or_default(),
             is_sidechain: false,
             model: msg.model,
+            thinking,
         });
     }
 
     messages
 }
 
+/// Separate the thinking blocks of a message's content from the rest
+fn split_thinking(content: serde_json::Value) -> (serde_json::Value, Vec<ThinkingBlock>) {
+    let serde_json::Value::Array(blocks) = content else {
+        return (content, Vec::new());
+    };
+    let mut thinking = Vec::new();
+    let rest = blocks
+        .into_iter()
+        .filter(|block| {
+            let text = match ApiContentBlock::deserialize(block) {
+                Ok(ApiContentBlock::Thinking { thinking, .. }) => Some(thinking),
+                Ok(ApiContentBlock::RedactedThinking { .. }) => None,
+                _ => return true,
+            };
+            thinking.push(ThinkingBlock {
+                redacted: text.is_none(),
+                text: text.unwrap_or_default(),
+            });
+            false
+        })
+        .collect();
+    (serde_json::Value::Array(rest), thinking)
+}
+
 /// Extract text content from a Claude message content field
 fn extract_text_content(content: &serde_json::Value) -> Option<String> {
     // Content can be a string or an array of content blocks
@@ -1,335 +1,743 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tauri::Emitter;
 
+use super::emitter::OutboundEvent;
+use super::hooks::{HookEvent, HookPhase};
+use super::process::TurnOutcome;
+use super::provider::{LineParser, ParsedLine};
+use super::turn_summary::TurnSummary;
+use super::types::{
+    ApiContentBlock, ApiStreamEvent, ClaudeStreamEvent, ContentDelta, MessageContent, MessageLine,
+    ResultEvent, StreamEventLine, SystemEvent,
+};
+use super::usage::{MessageUsage, UsageLedger};
 use crate::events;
 
 /// Parses NDJSON lines from Claude CLI's `--output-format stream-json` output
-/// and emits corresponding Tauri events for the frontend.
+/// into the Tauri events to send to the frontend.
 ///
 /// The CLI wraps API streaming events inside `{"type":"stream_event","event":{...}}`.
 /// It also emits `{"type":"system",...}`, `{"type":"assistant",...}`, and
-/// `{"type":"result",...}` at the top level.
+/// `{"type":"result",...}` at the top level. Lines are read into the
+/// `ClaudeStreamEvent` model.
+///
+/// One parser reads one CLI process. It assembles each content block from
+/// its deltas and emits the finished block at `content_block_stop`.
 pub struct StreamParser {
-    // Stateless parser — each line is self-contained NDJSON
+    /// Blocks still streaming, by message (main or subagent) and index
+    blocks: HashMap<BlockKey, PartialBlock>,
+    /// Tool calls waiting for their result, by tool use ID
+    running_tools: HashMap<String, RunningTool>,
+    /// Usage of the messages seen, each counted once
+    usage: UsageLedger,
 }
 
-impl StreamParser {
-    pub fn new() -> Self {
-        Self {}
-    }
+/// Characters of a tool result sent as its preview
+const RESULT_PREVIEW_CHARS: usize = 2000;
 
-    /// Parse a single NDJSON line and emit appropriate Tauri events
-    pub async fn parse_line(&self, session_id: &str, line: &str, app: &tauri::AppHandle) {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            return;
-        }
+/// A tool call the CLI has made but not yet answered with a `tool_result`
+struct RunningTool {
+    name: String,
+    /// When the call's input was complete
+    since: Instant,
+}
 
-        // Parse the JSON line
-        let event: Value = match serde_json::from_str(trimmed) {
-            Ok(v) => v,
-            Err(e) => {
-                log::warn!(
-                    "[stream-parser:{}] Failed to parse NDJSON line: {} — line: {}",
-                    session_id,
-                    e,
-                    &trimmed[..trimmed.len().min(200)]
-                );
-                return;
-            }
-        };
+/// Subagent messages stream alongside the main one and number their blocks
+/// from 0 too, so blocks are told apart by the tool call that started them
+type BlockKey = (Option<String>, usize);
+
+/// A content block whose deltas are still arriving
+enum PartialBlock {
+    Text(String),
+    Thinking {
+        thinking: String,
+        signature: String,
+        started: Instant,
+    },
+    RedactedThinking {
+        data: String,
+        started: Instant,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        /// Input from `content_block_start`, used if no deltas follow
+        input: Value,
+        partial_json: String,
+        server: bool,
+    },
+    /// A block type the parser doesn't assemble
+    Other,
+}
 
-        // Always emit the raw event for the terminal drawer / debugging
-        let _ = app.emit(
-            events::CLAUDE_STREAM_EVENT,
-            serde_json::json!({
-                "sessionId": session_id,
-                "event": &event,
-            }),
-        );
+/// A content block once its stream has ended, sent with
+/// `claude:content_block_complete`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CompletedBlock {
+    Text {
+        text: String,
+    },
+    Thinking {
+        thinking: String,
+        signature: Option<String>,
+    },
+    RedactedThinking {
+        data: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    /// A tool the API ran itself, e.g. web search
+    ServerToolUse {
+        id: String,
+        name: String,
+        input: Value,
+    },
+}
 
-        // Dispatch based on the top-level CLI event type
-        let event_type = event.get("type").and_then(|t| t.as_str()).unwrap_or("");
+impl PartialBlock {
+    fn start(block: &ApiContentBlock) -> Self {
+        match block {
+            ApiContentBlock::Text { text } => Self::Text(text.clone()),
+            ApiContentBlock::Thinking {
+                thinking,
+                signature,
+            } => Self::Thinking {
+                thinking: thinking.clone(),
+                signature: signature.clone().unwrap_or_default(),
+                started: Instant::now(),
+            },
+            ApiContentBlock::RedactedThinking { data } => Self::RedactedThinking {
+                data: data.clone(),
+                started: Instant::now(),
+            },
+            ApiContentBlock::ToolUse { id, name, input } => Self::ToolUse {
+                id: id.clone(),
+                name: name.clone(),
+                input: input.clone(),
+                partial_json: String::new(),
+                server: false,
+            },
+            ApiContentBlock::ServerToolUse { id, name, input } => Self::ToolUse {
+                id: id.clone(),
+                name: name.clone(),
+                input: input.clone(),
+                partial_json: String::new(),
+                server: true,
+            },
+            _ => Self::Other,
+        }
+    }
 
-        match event_type {
-            // Wrapped API streaming events — unwrap and dispatch inner event
-            "stream_event" => {
-                if let Some(inner) = event.get("event") {
-                    self.handle_stream_event(session_id, inner, app);
-                }
+    /// Add a delta, if it's one for this kind of block
+    fn apply(&mut self, delta: &ContentDelta) {
+        match (self, delta) {
+            (Self::Text(text), ContentDelta::TextDelta { text: more }) => text.push_str(more),
+            (Self::Thinking { thinking, .. }, ContentDelta::ThinkingDelta { thinking: more }) => {
+                thinking.push_str(more)
             }
+            (
+                Self::Thinking { signature, .. },
+                ContentDelta::SignatureDelta { signature: more },
+            ) => signature.push_str(more),
+            (
+                Self::ToolUse { partial_json, .. },
+                ContentDelta::InputJsonDelta { partial_json: more },
+            ) => partial_json.push_str(more),
+            _ => {}
+        }
+    }
 
-            // System events (init, hooks, etc.)
-            "system" => {
-                self.handle_system_event(session_id, &event, app);
+    /// When a thinking block started streaming
+    fn thinking_since(&self) -> Option<Instant> {
+        match self {
+            Self::Thinking { started, .. } | Self::RedactedThinking { started, .. } => {
+                Some(*started)
             }
+            _ => None,
+        }
+    }
 
-            // Complete assistant message (emitted after streaming finishes)
-            "assistant" => {
-                self.handle_assistant_event(session_id, &event, app);
+    fn finish(self, session_id: &str) -> Option<CompletedBlock> {
+        Some(match self {
+            Self::Text(text) => CompletedBlock::Text { text },
+            Self::Thinking {
+                thinking,
+                signature,
+                ..
+            } => CompletedBlock::Thinking {
+                thinking,
+                signature: Some(signature).filter(|s| !s.is_empty()),
+            },
+            Self::RedactedThinking { data, .. } => CompletedBlock::RedactedThinking { data },
+            Self::ToolUse {
+                id,
+                name,
+                input,
+                partial_json,
+                server,
+            } => {
+                let input = tool_input(session_id, &id, input, &partial_json);
+                if server {
+                    CompletedBlock::ServerToolUse { id, name, input }
+                } else {
+                    CompletedBlock::ToolUse { id, name, input }
+                }
             }
+            Self::Other => return None,
+        })
+    }
+}
 
-            // Final result — session turn complete
-            "result" => {
-                self.handle_result_event(session_id, &event, app);
-            }
+/// A tool call's input from its streamed JSON. Input that doesn't parse is
+/// kept as `{"raw": ...}`, as the chat view shows it.
+fn tool_input(session_id: &str, tool_id: &str, started_with: Value, partial_json: &str) -> Value {
+    if partial_json.trim().is_empty() {
+        return started_with;
+    }
+    serde_json::from_str(partial_json).unwrap_or_else(|e| {
+        log::warn!(
+            "[stream-parser:{}] input of tool call {} isn't valid JSON: {}",
+            session_id,
+            tool_id,
+            e
+        );
+        serde_json::json!({ "raw": partial_json })
+    })
+}
 
-            _ => {
-                log::debug!(
-                    "[stream-parser:{}] Unknown top-level event type: {}",
-                    session_id,
-                    event_type
-                );
-            }
+impl StreamParser {
+    pub fn new() -> Self {
+        Self {
+            blocks: HashMap::new(),
+            running_tools: HashMap::new(),
+            usage: UsageLedger::default(),
         }
     }
 
-    /// Handle system events (init with session_id, hooks, etc.)
-    fn handle_system_event(&self, session_id: &str, event: &Value, app: &tauri::AppHandle) {
-        let subtype = event.get("subtype").and_then(|s| s.as_str()).unwrap_or("");
-
-        match subtype {
-            "init" => {
-                // The init event contains session_id, model, tools, etc.
-                let claude_session_id = event.get("session_id").and_then(|s| s.as_str());
-                let model = event.get("model").and_then(|m| m.as_str());
-
+    /// Handle system events (init with session_id, hooks, etc.). Returns
+    /// the hook event, for hook subtypes.
+    fn handle_system_event(
+        &self,
+        session_id: &str,
+        event: &SystemEvent,
+        out: &mut Vec<OutboundEvent>,
+    ) -> Option<HookEvent> {
+        match event {
+            SystemEvent::Init(init) => {
                 log::info!(
-                    "[stream-parser:{}] init: claude_session_id={:?}, model={:?}",
+                    "[stream-parser:{}] init: claude_session_id={}, model={:?}",
                     session_id,
-                    claude_session_id,
-                    model
+                    init.session_id,
+                    init.model
                 );
             }
-            "compaction" => {
+            SystemEvent::CompactBoundary(_) => {
                 log::info!("[stream-parser:{}] context compaction occurred", session_id);
-                let _ = app.emit(
+                out.push(OutboundEvent::new(
                     events::CLAUDE_COMPACTION,
                     serde_json::json!({
                         "sessionId": session_id,
                     }),
-                );
+                ));
             }
-            // hook_started, hook_response, etc. are ignored (internal to CLI)
-            _ => {}
+            SystemEvent::HookStarted(_) | SystemEvent::HookResponse(_) => {
+                let hook = HookEvent::from_system_event(event)?;
+                if hook.blocked() {
+                    log::info!(
+                        "[stream-parser:{}] hook {} blocked: {:?}",
+                        session_id,
+                        hook.hook_name,
+                        hook.decision
+                    );
+                }
+                let mut payload = serde_json::to_value(&hook).unwrap_or_default();
+                payload["sessionId"] = session_id.into();
+                out.push(OutboundEvent::new(events::CLAUDE_HOOK_EVENT, payload));
+                return Some(hook);
+            }
+            SystemEvent::Status(update) => {
+                log::debug!("[stream-parser:{}] status: {:?}", session_id, update.status);
+            }
+            SystemEvent::Unknown => {}
         }
+        None
     }
 
     /// Handle unwrapped API streaming events (message_start, content_block_delta, etc.)
-    fn handle_stream_event(&self, session_id: &str, inner: &Value, app: &tauri::AppHandle) {
-        let inner_type = inner.get("type").and_then(|t| t.as_str()).unwrap_or("");
-
-        match inner_type {
-            "message_start" => {
-                if let Some(message) = inner.get("message") {
-                    let model = message.get("model").and_then(|m| m.as_str());
-                    let role = message.get("role").and_then(|r| r.as_str());
-                    log::debug!(
-                        "[stream-parser:{}] message_start: role={:?}, model={:?}",
-                        session_id,
-                        role,
-                        model
+    fn handle_stream_event(
+        &mut self,
+        session_id: &str,
+        line: &StreamEventLine,
+        out: &mut Vec<OutboundEvent>,
+    ) {
+        let parent = &line.parent_tool_use_id;
+        match &line.event {
+            ApiStreamEvent::MessageStart { message } => {
+                log::debug!(
+                    "[stream-parser:{}] message_start: role={}, model={:?}",
+                    session_id,
+                    message.role,
+                    message.model
+                );
+                self.drop_unfinished(session_id, parent);
+                if let Some(usage) = &message.usage {
+                    self.usage.report(
+                        parent,
+                        message.id.as_deref(),
+                        message.model.as_deref(),
+                        usage,
                     );
                 }
             }
 
-            "content_block_start" => {
-                let content_block = inner.get("content_block");
-                let block_type = content_block
-                    .and_then(|cb| cb.get("type"))
-                    .and_then(|t| t.as_str())
-                    .unwrap_or("");
-
-                if block_type == "tool_use" {
-                    let tool_name = content_block
-                        .and_then(|cb| cb.get("name"))
-                        .and_then(|n| n.as_str())
-                        .unwrap_or("unknown");
-                    let tool_id = content_block
-                        .and_then(|cb| cb.get("id"))
-                        .and_then(|id| id.as_str())
-                        .unwrap_or("");
-
-                    let _ = app.emit(
+            ApiStreamEvent::ContentBlockStart {
+                index,
+                content_block,
+            } => {
+                if let ApiContentBlock::ToolUse { id, name, .. } = content_block {
+                    out.push(OutboundEvent::new(
                         events::CLAUDE_TOOL_START,
                         serde_json::json!({
                             "sessionId": session_id,
-                            "toolName": tool_name,
-                            "toolId": tool_id,
+                            "toolName": name,
+                            "toolId": id,
                         }),
-                    );
+                    ));
+                }
+                let block = PartialBlock::start(content_block);
+                if block.thinking_since().is_some() {
+                    out.push(OutboundEvent::new(
+                        events::CLAUDE_THINKING_START,
+                        serde_json::json!({
+                            "sessionId": session_id,
+                            "index": index,
+                            "parentToolUseId": parent,
+                            "redacted": matches!(block, PartialBlock::RedactedThinking { .. }),
+                        }),
+                    ));
                 }
+                self.blocks.insert((parent.clone(), *index), block);
             }
 
-            "content_block_delta" => {
-                if let Some(delta) = inner.get("delta") {
-                    let delta_type = delta.get("type").and_then(|t| t.as_str()).unwrap_or("");
-
-                    match delta_type {
-                        "text_delta" => {
-                            if let Some(text) = delta.get("text").and_then(|t| t.as_str()) {
-                                let _ = app.emit(
-                                    "claude:text_delta",
-                                    serde_json::json!({
-                                        "sessionId": session_id,
-                                        "text": text,
-                                    }),
-                                );
-                            }
-                        }
-                        "input_json_delta" => {
-                            if let Some(partial_json) =
-                                delta.get("partial_json").and_then(|j| j.as_str())
-                            {
-                                let _ = app.emit(
-                                    "claude:tool_input_delta",
-                                    serde_json::json!({
-                                        "sessionId": session_id,
-                                        "partialJson": partial_json,
-                                    }),
-                                );
-                            }
-                        }
-                        _ => {}
+            ApiStreamEvent::ContentBlockDelta { index, delta } => {
+                let block = self.blocks.get_mut(&(parent.clone(), *index));
+                match delta {
+                    ContentDelta::TextDelta { text } => {
+                        out.push(OutboundEvent::new(
+                            events::CLAUDE_TEXT_DELTA,
+                            serde_json::json!({
+                                "sessionId": session_id,
+                                "index": index,
+                                "text": text,
+                            }),
+                        ));
                     }
+                    ContentDelta::InputJsonDelta { partial_json } => {
+                        let tool_id = match &block {
+                            Some(PartialBlock::ToolUse { id, .. }) => Some(id.as_str()),
+                            _ => None,
+                        };
+                        out.push(OutboundEvent::new(
+                            events::CLAUDE_TOOL_INPUT_DELTA,
+                            serde_json::json!({
+                                "sessionId": session_id,
+                                "toolId": tool_id,
+                                "index": index,
+                                "partialJson": partial_json,
+                            }),
+                        ));
+                    }
+                    ContentDelta::ThinkingDelta { thinking } => {
+                        out.push(OutboundEvent::new(
+                            events::CLAUDE_THINKING_DELTA,
+                            serde_json::json!({
+                                "sessionId": session_id,
+                                "index": index,
+                                "thinking": thinking,
+                            }),
+                        ));
+                    }
+                    _ => {}
+                }
+                match block {
+                    Some(block) => block.apply(delta),
+                    None => log::debug!(
+                        "[stream-parser:{}] delta for unknown content block {}",
+                        session_id,
+                        index
+                    ),
                 }
             }
 
-            "content_block_stop" => {
-                // Content block finished — no specific action needed
-            }
-
-            "message_delta" => {
-                // Extract usage statistics
-                if let Some(usage) = inner.get("usage") {
-                    let input_tokens = usage
-                        .get("input_tokens")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0);
-                    let output_tokens = usage
-                        .get("output_tokens")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0);
-                    let cache_creation = usage
-                        .get("cache_creation_input_tokens")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0);
-                    let cache_read = usage
-                        .get("cache_read_input_tokens")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0);
-
-                    let _ = app.emit(
-                        events::CLAUDE_USAGE_UPDATE,
+            ApiStreamEvent::ContentBlockStop { index } => {
+                let Some(block) = self.blocks.remove(&(parent.clone(), *index)) else {
+                    return;
+                };
+                let thinking_since = block.thinking_since();
+                if let Some(block) = block.finish(session_id) {
+                    if let CompletedBlock::ToolUse { id, name, .. } = &block {
+                        self.tool_called(id, name);
+                    }
+                    if let Some(started) = thinking_since {
+                        let thinking = match &block {
+                            CompletedBlock::Thinking { thinking, .. } => Some(thinking.as_str()),
+                            _ => None,
+                        };
+                        out.push(OutboundEvent::new(
+                            events::CLAUDE_THINKING_STOP,
+                            serde_json::json!({
+                                "sessionId": session_id,
+                                "index": index,
+                                "parentToolUseId": parent,
+                                "durationMs": started.elapsed().as_millis() as u64,
+                                "thinking": thinking,
+                                "redacted": thinking.is_none(),
+                            }),
+                        ));
+                    }
+                    out.push(OutboundEvent::new(
+                        events::CLAUDE_CONTENT_BLOCK_COMPLETE,
                         serde_json::json!({
                             "sessionId": session_id,
-                            "usage": {
-                                "inputTokens": input_tokens,
-                                "outputTokens": output_tokens,
-                                "cacheCreationInputTokens": cache_creation,
-                                "cacheReadInputTokens": cache_read,
-                            }
+                            "index": index,
+                            "parentToolUseId": parent,
+                            "block": block,
                         }),
-                    );
+                    ));
                 }
+            }
 
-                // Extract stop reason
-                if let Some(delta) = inner.get("delta") {
-                    if let Some(stop_reason) =
-                        delta.get("stop_reason").and_then(|s| s.as_str())
-                    {
-                        log::debug!(
-                            "[stream-parser:{}] message_delta stop_reason={}",
-                            session_id,
-                            stop_reason
-                        );
-                    }
+            ApiStreamEvent::MessageDelta { delta, usage } => {
+                if let Some(usage) = usage {
+                    self.usage.report_delta(parent, usage);
+                }
+                if let Some(stop_reason) = &delta.stop_reason {
+                    log::debug!(
+                        "[stream-parser:{}] message_delta stop_reason={}",
+                        session_id,
+                        stop_reason
+                    );
                 }
             }
 
-            "message_stop" => {
-                let _ = app.emit(
+            ApiStreamEvent::MessageStop => {
+                self.drop_unfinished(session_id, parent);
+                self.usage.close(parent);
+                out.push(OutboundEvent::new(
                     events::CLAUDE_MESSAGE_COMPLETE,
                     serde_json::json!({
                         "sessionId": session_id,
                     }),
-                );
+                ));
             }
 
-            _ => {
-                log::debug!(
-                    "[stream-parser:{}] Unknown stream event type: {}",
+            ApiStreamEvent::Error { error } => {
+                log::warn!(
+                    "[stream-parser:{}] API error: {}: {}",
                     session_id,
-                    inner_type
+                    error.error_type,
+                    error.message
                 );
             }
+
+            ApiStreamEvent::Ping | ApiStreamEvent::Unknown => {}
         }
     }
 
-    /// Handle complete assistant message (emitted after stream finishes)
-    fn handle_assistant_event(&self, session_id: &str, event: &Value, _app: &tauri::AppHandle) {
-        if let Some(message) = event.get("message") {
-            let model = message.get("model").and_then(|m| m.as_str());
+    /// Forget the blocks of a message that ended or was cut off without
+    /// stopping them
+    fn drop_unfinished(&mut self, session_id: &str, parent: &Option<String>) {
+        let before = self.blocks.len();
+        self.blocks.retain(|(of, _), _| of != parent);
+        if self.blocks.len() < before {
             log::debug!(
-                "[stream-parser:{}] assistant message complete, model={:?}",
+                "[stream-parser:{}] dropped {} unfinished content blocks",
                 session_id,
-                model
+                before - self.blocks.len()
             );
         }
     }
 
-    /// Handle result event — the session turn is complete
-    fn handle_result_event(&self, session_id: &str, event: &Value, app: &tauri::AppHandle) {
-        let is_error = event.get("is_error").and_then(|v| v.as_bool()).unwrap_or(false);
-        let subtype = event.get("subtype").and_then(|s| s.as_str()).unwrap_or("");
+    /// Handle complete assistant message (emitted after stream finishes)
+    fn handle_assistant_event(&mut self, session_id: &str, line: &MessageLine) {
+        log::debug!(
+            "[stream-parser:{}] assistant message complete, model={:?}",
+            session_id,
+            line.message.model
+        );
+        if let Some(usage) = &line.message.usage {
+            self.usage.report(
+                &line.parent_tool_use_id,
+                line.message.id.as_deref(),
+                line.message.model.as_deref(),
+                usage,
+            );
+        }
+        // Without partial messages this is the first sight of its tool calls
+        if let MessageContent::Blocks(blocks) = &line.message.content {
+            for block in blocks {
+                if let ApiContentBlock::ToolUse { id, name, .. } = block {
+                    self.tool_called(id, name);
+                }
+            }
+        }
+    }
+
+    /// Start timing a tool call, unless it already is
+    fn tool_called(&mut self, id: &str, name: &str) {
+        self.running_tools
+            .entry(id.to_string())
+            .or_insert_with(|| RunningTool {
+                name: name.to_string(),
+                since: Instant::now(),
+            });
+    }
+
+    /// Handle a user message from the CLI: the results of the tool calls
+    /// the assistant made, which finish those calls
+    fn handle_user_event(
+        &mut self,
+        session_id: &str,
+        line: &MessageLine,
+        out: &mut Vec<OutboundEvent>,
+    ) {
+        // The model's messages before this one are over
+        self.usage.close(&line.parent_tool_use_id);
+        let MessageContent::Blocks(blocks) = &line.message.content else {
+            return;
+        };
+        for block in blocks {
+            let ApiContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } = block
+            else {
+                continue;
+            };
+            let tool = self.running_tools.remove(tool_use_id);
+            if tool.is_none() {
+                log::debug!(
+                    "[stream-parser:{}] result for unknown tool call {}",
+                    session_id,
+                    tool_use_id
+                );
+            }
+            let (preview, truncated) = result_preview(content);
+            out.push(OutboundEvent::new(
+                events::CLAUDE_TOOL_COMPLETE,
+                serde_json::json!({
+                    "sessionId": session_id,
+                    "toolId": tool_use_id,
+                    "toolName": tool.as_ref().map(|t| t.name.as_str()),
+                    "parentToolUseId": line.parent_tool_use_id,
+                    "isError": is_error.unwrap_or(false),
+                    "durationMs": tool.map(|t| t.since.elapsed().as_millis() as u64),
+                    "preview": preview,
+                    "truncated": truncated,
+                }),
+            ));
+        }
+    }
 
-        if is_error {
+    /// Handle result event — the session turn is complete
+    fn handle_result_event(
+        &mut self,
+        session_id: &str,
+        result: &ResultEvent,
+        out: &mut Vec<OutboundEvent>,
+    ) {
+        if result.is_error {
             log::warn!(
                 "[stream-parser:{}] result error: subtype={}",
                 session_id,
-                subtype
+                result.subtype
             );
         }
 
-        // Extract total usage from result
-        if let Some(usage) = event.get("usage") {
-            let input_tokens = usage
-                .get("input_tokens")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-            let output_tokens = usage
-                .get("output_tokens")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-            let cache_creation = usage
-                .get("cache_creation_input_tokens")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-            let cache_read = usage
-                .get("cache_read_input_tokens")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-
-            let _ = app.emit(
-                events::CLAUDE_USAGE_UPDATE,
-                serde_json::json!({
-                    "sessionId": session_id,
-                    "usage": {
-                        "inputTokens": input_tokens,
-                        "outputTokens": output_tokens,
-                        "cacheCreationInputTokens": cache_creation,
-                        "cacheReadInputTokens": cache_read,
-                    }
-                }),
+        // Calls still unanswered won't be any more
+        if !self.running_tools.is_empty() {
+            log::debug!(
+                "[stream-parser:{}] {} tool calls ended without a result",
+                session_id,
+                self.running_tools.len()
             );
+            self.running_tools.clear();
         }
 
+        // The result's usage repeats the turn's messages, which are counted
+        // on their own
+        self.usage.close_turn();
+
         // Emit message complete for the result (in case message_stop was missed)
-        let _ = app.emit(
+        out.push(OutboundEvent::new(
             events::CLAUDE_MESSAGE_COMPLETE,
             serde_json::json!({
                 "sessionId": session_id,
             }),
-        );
+        ));
+    }
+}
+
+/// Text of a tool result, cut to `RESULT_PREVIEW_CHARS`, and whether it was cut
+fn result_preview(content: &Value) -> (String, bool) {
+    let text = match content {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        _ => match Vec::<ApiContentBlock>::deserialize(content) {
+            Ok(blocks) => blocks
+                .iter()
+                .map(|block| match block {
+                    ApiContentBlock::Text { text } => text.as_str(),
+                    ApiContentBlock::Image { .. } => "[image]",
+                    ApiContentBlock::Document { .. } => "[document]",
+                    _ => "",
+                })
+                .filter(|text| !text.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Err(_) => content.to_string(),
+        },
+    };
+    match text.char_indices().nth(RESULT_PREVIEW_CHARS) {
+        Some((cut, _)) => (text[..cut].to_string(), true),
+        None => (text, false),
+    }
+}
+
+/// `claude:usage_update` for one message, counted once
+fn usage_update(session_id: &str, usage: &MessageUsage) -> OutboundEvent {
+    OutboundEvent::new(
+        events::CLAUDE_USAGE_UPDATE,
+        serde_json::json!({
+            "sessionId": session_id,
+            "messageId": usage.message_id,
+            "model": usage.model,
+            "usage": {
+                "inputTokens": usage.input_tokens,
+                "outputTokens": usage.output_tokens,
+                "cacheCreationInputTokens": usage.cache_creation_tokens,
+                "cacheReadInputTokens": usage.cache_read_tokens,
+            }
+        }),
+    )
+}
+
+impl LineParser for StreamParser {
+    /// Parse a single NDJSON line into the events to send to the frontend, in order
+    fn parse_line(&mut self, session_id: &str, line: &str) -> ParsedLine {
+        let mut parsed = ParsedLine::default();
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            return parsed;
+        }
+
+        // Parse the JSON line
+        let raw: Value = match serde_json::from_str(trimmed) {
+            Ok(v) => v,
+            Err(e) => {
+                log::warn!(
+                    "[stream-parser:{}] Failed to parse NDJSON line: {} — line: {}",
+                    session_id,
+                    e,
+                    &trimmed[..trimmed.len().min(200)]
+                );
+                return parsed;
+            }
+        };
+
+        // Raw event for the terminal drawer / debugging (dropped unless mirrored)
+        parsed.events.push(OutboundEvent::new(
+            events::CLAUDE_STREAM_EVENT,
+            serde_json::json!({
+                "sessionId": session_id,
+                "event": &raw,
+            }),
+        ));
+
+        let event = match ClaudeStreamEvent::deserialize(&raw) {
+            Ok(event) => event,
+            Err(e) => {
+                log::warn!(
+                    "[stream-parser:{}] Malformed {} event: {}",
+                    session_id,
+                    raw["type"],
+                    e
+                );
+                return parsed;
+            }
+        };
+
+        // The CLI puts session_id at the top level of every NDJSON line
+        parsed.agent_session_id = event.session_id().map(String::from);
+        let out = &mut parsed.events;
+
+        // Dispatch based on the top-level CLI event type
+        match &event {
+            // Wrapped API streaming events — dispatch the inner event
+            ClaudeStreamEvent::StreamEvent(line) => {
+                self.handle_stream_event(session_id, line, out);
+                if matches!(line.event, ApiStreamEvent::Unknown) {
+                    log::debug!(
+                        "[stream-parser:{}] Unknown stream event type: {}",
+                        session_id,
+                        raw["event"]["type"]
+                    );
+                }
+            }
+
+            // System events (init, hooks, etc.)
+            ClaudeStreamEvent::System(system) => {
+                if matches!(system, SystemEvent::Unknown) {
+                    log::debug!(
+                        "[stream-parser:{}] Unhandled system event subtype: {}",
+                        session_id,
+                        raw["subtype"]
+                    );
+                }
+                let hook = self.handle_system_event(session_id, system, out);
+                parsed
+                    .finished_hooks
+                    .extend(hook.filter(|h| h.phase == HookPhase::Finished));
+            }
+
+            // Complete assistant message (emitted after streaming finishes)
+            ClaudeStreamEvent::Assistant(line) => {
+                self.handle_assistant_event(session_id, line);
+            }
+
+            // Tool results, fed back to the model
+            ClaudeStreamEvent::User(line) => {
+                self.handle_user_event(session_id, line, out);
+            }
+
+            // Final result — session turn complete
+            ClaudeStreamEvent::Result(result) => {
+                self.handle_result_event(session_id, result, out);
+                parsed.turn_end = Some(TurnOutcome::from_result(session_id, result));
+                parsed.turn_summary = Some(TurnSummary::from_result(session_id, result));
+            }
+
+            ClaudeStreamEvent::Unknown => {
+                log::debug!(
+                    "[stream-parser:{}] Unknown top-level event type: {}",
+                    session_id,
+                    raw["type"]
+                );
+            }
+        }
+
+        parsed.usage = self.usage.take_counted();
+        for usage in &parsed.usage {
+            parsed.events.push(usage_update(session_id, usage));
+        }
+        parsed
     }
 }
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::types::ResultEvent;
+
+/// What a finished turn took, cost and was refused, from the CLI's `result`
+/// event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnSummary {
+    pub session_id: String,
+    /// ID of the queued prompt the turn ran, filled in by the process manager
+    pub prompt_id: Option<String>,
+    /// `success`, `error_max_turns`, `error_during_execution`, ...
+    pub subtype: String,
+    pub is_error: bool,
+    /// Wall-clock time of the turn
+    pub duration_ms: Option<u64>,
+    /// Time spent waiting on the API
+    pub duration_api_ms: Option<u64>,
+    /// Model round trips within the turn
+    pub num_turns: Option<u32>,
+    pub cost_usd: Option<f64>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    /// Final text of the turn
+    pub result: Option<String>,
+    pub permission_denials: Vec<PermissionDenial>,
+    pub finished_at: String,
+}
+
+/// A tool call the permission settings refused
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionDenial {
+    pub tool_name: String,
+    pub tool_use_id: Option<String>,
+    pub tool_input: Value,
+}
+
+impl TurnSummary {
+    pub fn from_result(session_id: &str, result: &ResultEvent) -> Self {
+        let usage = result.usage.clone().unwrap_or_default();
+        Self {
+            session_id: session_id.to_string(),
+            prompt_id: None,
+            subtype: result.subtype.clone(),
+            is_error: result.is_error,
+            duration_ms: result.duration_ms,
+            duration_api_ms: result.duration_api_ms,
+            num_turns: result.num_turns,
+            cost_usd: result.total_cost_usd,
+            input_tokens: usage.input_tokens.unwrap_or(0),
+            output_tokens: usage.output_tokens.unwrap_or(0),
+            cache_read_tokens: usage.cache_read_input_tokens.unwrap_or(0),
+            cache_creation_tokens: usage.cache_creation_input_tokens.unwrap_or(0),
+            result: result.result.clone(),
+            permission_denials: result
+                .permission_denials
+                .iter()
+                .map(|denial| PermissionDenial {
+                    tool_name: denial.tool_name.clone(),
+                    tool_use_id: denial.tool_use_id.clone(),
+                    tool_input: denial.tool_input.clone(),
+                })
+                .collect(),
+            finished_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
@@ -1,12 +1,322 @@
 use serde::{Deserialize, Serialize};
 
-/// A single event from Claude Code's stream-json output
+/// One line of the CLI's `--output-format stream-json` output.
+///
+/// The serialized form is the CLI's own, so the same shapes are mirrored as
+/// TypeScript types in `src/lib/streamEvents.ts`. Every enum has an
+/// `Unknown` variant so that events added by newer CLIs still parse; fields
+/// the model doesn't name are ignored.
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ClaudeStreamEvent {
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClaudeStreamEvent {
+    System(SystemEvent),
+    /// An API streaming event, wrapped (`--include-partial-messages`)
+    StreamEvent(StreamEventLine),
+    /// A complete assistant message, after its stream finished
+    Assistant(MessageLine),
+    /// A user message — for the CLI's output, mostly tool results
+    User(MessageLine),
+    /// End of a turn
+    Result(ResultEvent),
+    #[serde(other)]
+    Unknown,
+}
+
+impl ClaudeStreamEvent {
+    /// The CLI's session ID, which it puts on every line that has one
+    pub fn session_id(&self) -> Option<&str> {
+        match self {
+            Self::System(SystemEvent::Init(init)) => Some(&init.session_id),
+            Self::System(SystemEvent::CompactBoundary(compact)) => compact.session_id.as_deref(),
+            Self::System(SystemEvent::Status(status)) => status.session_id.as_deref(),
+            Self::System(SystemEvent::HookStarted(run) | SystemEvent::HookResponse(run)) => {
+                run.session_id.as_deref()
+            }
+            Self::System(SystemEvent::Unknown) | Self::Unknown => None,
+            Self::StreamEvent(line) => line.session_id.as_deref(),
+            Self::Assistant(line) | Self::User(line) => line.session_id.as_deref(),
+            Self::Result(result) => result.session_id.as_deref(),
+        }
+    }
+}
+
+/// `{"type":"system",...}` events, by `subtype`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "subtype", rename_all = "snake_case")]
+pub enum SystemEvent {
+    Init(SystemInit),
+    /// The conversation was compacted
+    #[serde(alias = "compaction")]
+    CompactBoundary(CompactBoundary),
+    HookStarted(HookRun),
+    HookResponse(HookRun),
+    /// A change of the CLI's own status, e.g. `compacting`
+    Status(StatusUpdate),
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SystemInit {
+    pub session_id: String,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub tools: Vec<String>,
+    #[serde(default)]
+    pub mcp_servers: Vec<McpServerStatus>,
+    #[serde(default, rename = "permissionMode")]
+    pub permission_mode: Option<String>,
+    #[serde(default, rename = "apiKeySource")]
+    pub api_key_source: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct McpServerStatus {
+    pub name: String,
+    pub status: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CompactBoundary {
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Trigger and token count before compaction
+    #[serde(default)]
+    pub compact_metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StatusUpdate {
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+}
+
+/// A hook run; the output fields are only set on `hook_response`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HookRun {
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub hook_id: Option<String>,
+    #[serde(default)]
+    pub hook_name: String,
+    #[serde(default)]
+    pub hook_event: Option<String>,
+    #[serde(default)]
+    pub tool_name: Option<String>,
+    #[serde(default)]
+    pub output: Option<String>,
+    #[serde(default)]
+    pub stdout: Option<String>,
+    #[serde(default)]
+    pub stderr: Option<String>,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// `success`, `error` or `cancelled`
+    #[serde(default)]
+    pub outcome: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StreamEventLine {
+    pub event: ApiStreamEvent,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Set for events of a subagent, to the tool call that started it
+    #[serde(default)]
+    pub parent_tool_use_id: Option<String>,
+    #[serde(default)]
+    pub uuid: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MessageLine {
+    pub message: ApiMessage,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub parent_tool_use_id: Option<String>,
+    #[serde(default)]
+    pub uuid: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResultEvent {
+    /// `success`, `error_max_turns`, `error_during_execution`, ...
+    pub subtype: String,
+    #[serde(default)]
+    pub is_error: bool,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    #[serde(default)]
+    pub duration_api_ms: Option<u64>,
+    #[serde(default)]
+    pub num_turns: Option<u32>,
+    /// Final text of the turn
+    #[serde(default)]
+    pub result: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub total_cost_usd: Option<f64>,
+    #[serde(default)]
+    pub usage: Option<TokenUsage>,
+    /// Tool calls the permission settings refused during the turn
+    #[serde(default)]
+    pub permission_denials: Vec<ResultDenial>,
+}
+
+/// Entry of a result's `permission_denials`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResultDenial {
+    pub tool_name: String,
+    #[serde(default)]
+    pub tool_use_id: Option<String>,
+    #[serde(default)]
+    pub tool_input: serde_json::Value,
+}
+
+/// Inner events of `stream_event` lines, as the Messages API streams them
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ApiStreamEvent {
+    MessageStart {
+        message: ApiMessage,
+    },
+    ContentBlockStart {
+        index: usize,
+        content_block: ApiContentBlock,
+    },
+    ContentBlockDelta {
+        index: usize,
+        delta: ContentDelta,
+    },
+    ContentBlockStop {
+        index: usize,
+    },
+    MessageDelta {
+        delta: MessageDeltaBody,
+        #[serde(default)]
+        usage: Option<TokenUsage>,
+    },
+    MessageStop,
+    Ping,
+    Error {
+        error: ApiError,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MessageDeltaBody {
+    #[serde(default)]
+    pub stop_reason: Option<String>,
+    #[serde(default)]
+    pub stop_sequence: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiError {
     #[serde(rename = "type")]
-    pub event_type: String,
-    #[serde(flatten)]
-    pub data: serde_json::Value,
+    pub error_type: String,
+    pub message: String,
+}
+
+/// A message as the API returns it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiMessage {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub role: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub content: MessageContent,
+    #[serde(default)]
+    pub stop_reason: Option<String>,
+    #[serde(default)]
+    pub usage: Option<TokenUsage>,
+}
+
+/// Message content: plain text (user prompts) or content blocks
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Blocks(Vec<ApiContentBlock>),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ApiContentBlock {
+    Text {
+        text: String,
+    },
+    Thinking {
+        thinking: String,
+        #[serde(default)]
+        signature: Option<String>,
+    },
+    RedactedThinking {
+        data: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        #[serde(default)]
+        input: serde_json::Value,
+    },
+    /// A tool run by the API itself, e.g. web search
+    ServerToolUse {
+        id: String,
+        name: String,
+        #[serde(default)]
+        input: serde_json::Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        /// A string or a list of content blocks
+        #[serde(default)]
+        content: serde_json::Value,
+        #[serde(default)]
+        is_error: Option<bool>,
+    },
+    Image {
+        source: serde_json::Value,
+    },
+    Document {
+        source: serde_json::Value,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentDelta {
+    TextDelta {
+        text: String,
+    },
+    /// A fragment of a tool call's JSON input
+    InputJsonDelta {
+        partial_json: String,
+    },
+    ThinkingDelta {
+        thinking: String,
+    },
+    SignatureDelta {
+        signature: String,
+    },
+    CitationsDelta {
+        citation: serde_json::Value,
+    },
+    #[serde(other)]
+    Unknown,
 }
 
 /// An entry from a Claude Code session JSONL file
@@ -39,7 +349,7 @@ pub struct ClaudeMessage {
 }
 
 /// Token usage statistics from the API
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct TokenUsage {
     pub input_tokens: Option<u64>,
     pub output_tokens: Option<u64>,
@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::types::TokenUsage;
+
+/// Tokens one API message used, counted once however many lines report it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageUsage {
+    /// API message ID, when the agent reports one
+    pub message_id: Option<String>,
+    /// Model that produced the message
+    pub model: Option<String>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+}
+
+impl MessageUsage {
+    /// Take in another report of the same message. Counts only grow within
+    /// a message, so the larger one wins.
+    fn merge(&mut self, usage: &TokenUsage) {
+        let grow = |count: &mut u64, reported: Option<u64>| {
+            *count = (*count).max(reported.unwrap_or(0));
+        };
+        grow(&mut self.input_tokens, usage.input_tokens);
+        grow(&mut self.output_tokens, usage.output_tokens);
+        grow(&mut self.cache_read_tokens, usage.cache_read_input_tokens);
+        grow(
+            &mut self.cache_creation_tokens,
+            usage.cache_creation_input_tokens,
+        );
+    }
+}
+
+/// Counts each API message's usage exactly once.
+///
+/// The CLI reports a message's usage at `message_start`, cumulatively at
+/// `message_delta`, and on every `assistant` line it splits the message into;
+/// the `result` then repeats the whole turn. The ledger keeps the latest report
+/// of each message until the message is over — at its `message_stop`, a tool
+/// result in the same conversation, or the end of the turn — then counts it
+/// and ignores any later report of it.
+#[derive(Default)]
+pub struct UsageLedger {
+    /// Messages not yet counted, with the subagent call they belong to
+    open: Vec<(Option<String>, MessageUsage)>,
+    /// IDs of the messages counted this turn
+    counted_ids: HashSet<String>,
+    /// Messages counted since the last `take_counted`
+    counted: Vec<MessageUsage>,
+}
+
+impl UsageLedger {
+    /// A report of a message's usage. `scope` is the subagent call the
+    /// message belongs to, `None` for the main conversation.
+    pub fn report(
+        &mut self,
+        scope: &Option<String>,
+        message_id: Option<&str>,
+        model: Option<&str>,
+        usage: &TokenUsage,
+    ) {
+        if message_id.is_some_and(|id| self.counted_ids.contains(id)) {
+            return;
+        }
+        let known = message_id.and_then(|id| {
+            self.open
+                .iter_mut()
+                .find(|(_, message)| message.message_id.as_deref() == Some(id))
+        });
+        match known {
+            Some((_, message)) => {
+                message.merge(usage);
+                if message.model.is_none() {
+                    message.model = model.map(String::from);
+                }
+            }
+            None => {
+                let mut message = MessageUsage {
+                    message_id: message_id.map(String::from),
+                    model: model.map(String::from),
+                    ..Default::default()
+                };
+                message.merge(usage);
+                self.open.push((scope.clone(), message));
+            }
+        }
+    }
+
+    /// A `message_delta`'s usage, which belongs to the latest message of `scope`
+    pub fn report_delta(&mut self, scope: &Option<String>, usage: &TokenUsage) {
+        if let Some((_, message)) = self.open.iter_mut().rev().find(|(s, _)| s == scope) {
+            message.merge(usage);
+        }
+    }
+
+    /// Count the open messages of one conversation, main or a subagent's
+    pub fn close(&mut self, scope: &Option<String>) {
+        let (closed, open): (Vec<_>, Vec<_>) = std::mem::take(&mut self.open)
+            .into_iter()
+            .partition(|(s, _)| s == scope);
+        self.open = open;
+        for (_, message) in closed {
+            self.count(message);
+        }
+    }
+
+    /// Count every open message at the end of a turn
+    pub fn close_turn(&mut self) {
+        for (_, message) in std::mem::take(&mut self.open) {
+            self.count(message);
+        }
+        self.counted_ids.clear();
+    }
+
+    /// Messages counted since the last call, in the order they were counted
+    pub fn take_counted(&mut self) -> Vec<MessageUsage> {
+        std::mem::take(&mut self.counted)
+    }
+
+    fn count(&mut self, message: MessageUsage) {
+        if let Some(id) = &message.message_id {
+            self.counted_ids.insert(id.clone());
+        }
+        self.counted.push(message);
+    }
+}
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// How often each session's watchdog looks at its process
+pub const WATCHDOG_TICK: Duration = Duration::from_secs(1);
+
+/// How many times a turn is attempted in total when retrying on timeout
+pub const MAX_TURN_ATTEMPTS: u32 = 2;
+
+/// When a running turn counts as stalled and what happens once it runs too long
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchdogSettings {
+    /// Seconds without a line on stdout before the turn is reported stalled
+    pub stall_after_secs: u64,
+    /// Hard limit on a turn's duration; `None` lets turns run indefinitely
+    #[serde(default)]
+    pub turn_timeout_secs: Option<u64>,
+    /// What to do with a turn that hits the hard limit
+    #[serde(default)]
+    pub on_timeout: TimeoutAction,
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            stall_after_secs: 120,
+            turn_timeout_secs: None,
+            on_timeout: TimeoutAction::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutAction {
+    /// Interrupt the turn, dropping queued prompts like a user interrupt
+    #[default]
+    Interrupt,
+    /// Interrupt the turn and send its prompt again, once
+    Retry,
+}
+
+/// What the watchdog should do about a running turn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Healthy,
+    /// No output for longer than the stall threshold (reported once per stall)
+    Stalled,
+    /// Past the hard per-turn limit
+    TimedOut,
+}
+
+impl WatchdogSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.stall_after_secs == 0 {
+            return Err("Stall threshold must be at least 1 second".to_string());
+        }
+        if self.turn_timeout_secs == Some(0) {
+            return Err("Turn timeout must be at least 1 second".to_string());
+        }
+        Ok(())
+    }
+
+    /// Judge a turn that has been running for `elapsed` and silent for `idle`
+    pub fn check(&self, idle: Duration, elapsed: Duration, already_stalled: bool) -> Verdict {
+        let timed_out = self
+            .turn_timeout_secs
+            .is_some_and(|limit| elapsed >= Duration::from_secs(limit));
+        if timed_out {
+            Verdict::TimedOut
+        } else if !already_stalled && idle >= Duration::from_secs(self.stall_after_secs) {
+            Verdict::Stalled
+        } else {
+            Verdict::Healthy
+        }
+    }
+}
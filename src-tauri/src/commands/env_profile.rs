@@ -0,0 +1,64 @@
+use chrono::Utc;
+use tauri::State;
+
+use super::session::{resolve_project_path, AppState};
+use crate::claude::env_profile::{EnvProfile, EnvProfileInput};
+use crate::db;
+
+/// List environment profiles, with secret values masked
+#[tauri::command]
+pub async fn list_env_profiles(state: State<'_, AppState>) -> Result<Vec<EnvProfile>, String> {
+    let profiles = state.process_manager.env_profiles().await;
+    Ok(profiles.iter().map(EnvProfile::masked).collect())
+}
+
+/// Create a profile, or update the one with `id`. Secret values sent back
+/// masked keep their stored value.
+#[tauri::command]
+pub async fn save_env_profile(
+    id: Option<String>,
+    mut input: EnvProfileInput,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<EnvProfile, String> {
+    let manager = &state.process_manager;
+    let profiles = manager.env_profiles().await;
+    let existing = match &id {
+        Some(id) => Some(
+            profiles
+                .iter()
+                .find(|p| &p.id == id)
+                .ok_or_else(|| format!("Environment profile {} not found", id))?,
+        ),
+        None => None,
+    };
+    let id = id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    if let Some(path) = input.project_path.take().filter(|p| !p.is_empty()) {
+        input.project_path = Some(resolve_project_path(&path)?);
+    }
+    let profile = EnvProfile::from_input(id, input, existing, Utc::now().to_rfc3339())?;
+
+    let pool = db::pool(&app).await?;
+    db::env_profiles::save(&pool, &profile).await?;
+    manager
+        .set_env_profiles(db::env_profiles::load_all(&pool).await?)
+        .await;
+    Ok(profile.masked())
+}
+
+/// Delete a profile. Sessions that named it fail to spawn until they pick
+/// another one.
+#[tauri::command]
+pub async fn delete_env_profile(
+    id: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    let pool = db::pool(&app).await?;
+    db::env_profiles::delete(&pool, &id).await?;
+    state
+        .process_manager
+        .set_env_profiles(db::env_profiles::load_all(&pool).await?)
+        .await;
+    Ok(())
+}
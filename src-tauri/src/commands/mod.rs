@@ -1,4 +1,7 @@
 pub mod agent_team;
+pub mod env_profile;
 pub mod git;
+pub mod permission;
+pub mod schedule;
 pub mod session;
 pub mod settings;
@@ -0,0 +1,94 @@
+use tauri::State;
+
+use super::session::AppState;
+use crate::claude::host::tauri_host;
+use crate::claude::permissions::{
+    PermissionRequest, PermissionResponse, PermissionRule, PermissionSettings,
+};
+use crate::db;
+
+/// Settings key holding whether approvals are asked for and their timeout
+pub const PERMISSION_SETTING: &str = "permissions";
+
+/// Get the approval settings
+#[tauri::command]
+pub async fn get_permission_settings(
+    state: State<'_, AppState>,
+) -> Result<PermissionSettings, String> {
+    Ok(state.process_manager.permissions().settings().await)
+}
+
+/// Set the approval settings (persisted). Turning approvals on or off
+/// applies to processes spawned afterwards.
+#[tauri::command]
+pub async fn set_permission_settings(
+    settings: PermissionSettings,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    settings.validate()?;
+    let pool = db::pool(&app).await?;
+    let value = serde_json::to_value(&settings)
+        .map_err(|e| format!("Failed to serialize permission settings: {}", e))?;
+    db::settings::set(&pool, PERMISSION_SETTING, &value).await?;
+    state
+        .process_manager
+        .permissions()
+        .set_settings(settings)
+        .await;
+    Ok(())
+}
+
+/// Tool calls waiting for the user, e.g. to restore dialogs after a reload
+#[tauri::command]
+pub async fn list_pending_permissions(
+    state: State<'_, AppState>,
+) -> Result<Vec<PermissionRequest>, String> {
+    Ok(state.process_manager.permissions().pending().await)
+}
+
+/// Allow (optionally with edited input, or always for the project) or deny
+/// a pending tool call. Returns the rule added by an "always allow".
+#[tauri::command]
+pub async fn respond_to_permission(
+    request_id: String,
+    response: PermissionResponse,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<Option<PermissionRule>, String> {
+    state
+        .process_manager
+        .permissions()
+        .respond(&request_id, response, &tauri_host(app))
+        .await
+}
+
+/// "Always allow" rules, of one project or all of them
+#[tauri::command]
+pub async fn list_permission_rules(
+    project_path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<PermissionRule>, String> {
+    let rules = state.process_manager.permissions().rules().await;
+    Ok(rules
+        .into_iter()
+        .filter(|r| project_path.as_ref().is_none_or(|p| &r.project_path == p))
+        .collect())
+}
+
+/// Remove an "always allow" rule; the tool is asked about again
+#[tauri::command]
+pub async fn delete_permission_rule(
+    id: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    let pool = db::pool(&app).await?;
+    db::permission_rules::delete(&pool, &id).await?;
+    state
+        .process_manager
+        .permissions()
+        .set_rules(db::permission_rules::load_all(&pool).await?)
+        .await;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use chrono::Utc;
+use tauri::State;
+
+use super::session::{resolve_project_path, AppState};
+use crate::claude::host::tauri_host;
+use crate::db;
+use crate::schedules::{self, PromptSchedule, ScheduleInput, ScheduleRun};
+
+/// List all scheduled prompts
+#[tauri::command]
+pub async fn list_schedules(app: tauri::AppHandle) -> Result<Vec<PromptSchedule>, String> {
+    let pool = db::pool(&app).await?;
+    db::schedules::load_all(&pool).await
+}
+
+/// Schedule a prompt for a session, or for a new session in a project
+#[tauri::command]
+pub async fn create_schedule(
+    mut input: ScheduleInput,
+    app: tauri::AppHandle,
+) -> Result<PromptSchedule, String> {
+    input.project_path = resolve_project_path(&input.project_path)?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let schedule = PromptSchedule::from_input(id, input, Utc::now().to_rfc3339())?;
+
+    let pool = db::pool(&app).await?;
+    db::schedules::save(&pool, &schedule).await?;
+    Ok(schedule)
+}
+
+/// Replace a schedule's settings; its next run is worked out again from now
+#[tauri::command]
+pub async fn update_schedule(
+    id: String,
+    mut input: ScheduleInput,
+    app: tauri::AppHandle,
+) -> Result<PromptSchedule, String> {
+    let pool = db::pool(&app).await?;
+    let existing = db::schedules::load(&pool, &id)
+        .await?
+        .ok_or_else(|| format!("Schedule {} not found", id))?;
+
+    input.project_path = resolve_project_path(&input.project_path)?;
+    let mut schedule = PromptSchedule::from_input(id, input, existing.created_at)?;
+    schedule.last_run_at = existing.last_run_at;
+    db::schedules::save(&pool, &schedule).await?;
+    Ok(schedule)
+}
+
+/// Delete a schedule and its run log
+#[tauri::command]
+pub async fn delete_schedule(id: String, app: tauri::AppHandle) -> Result<(), String> {
+    let pool = db::pool(&app).await?;
+    db::schedules::delete(&pool, &id).await
+}
+
+/// Run a schedule right away, without changing when it runs next
+#[tauri::command]
+pub async fn run_schedule_now(
+    id: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<ScheduleRun, String> {
+    let pool = db::pool(&app).await?;
+    let schedule = db::schedules::load(&pool, &id)
+        .await?
+        .ok_or_else(|| format!("Schedule {} not found", id))?;
+    schedules::start_run(&state.process_manager, &tauri_host(app), schedule).await
+}
+
+/// Get the most recent runs of a schedule, newest first
+#[tauri::command]
+pub async fn list_schedule_runs(
+    schedule_id: String,
+    limit: Option<u32>,
+    app: tauri::AppHandle,
+) -> Result<Vec<ScheduleRun>, String> {
+    let pool = db::pool(&app).await?;
+    db::schedules::load_runs(&pool, &schedule_id, limit.unwrap_or(50)).await
+}
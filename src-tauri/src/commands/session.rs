@@ -1,13 +1,28 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
-use crate::claude::process::{ProcessManager, SpawnOptions};
+use super::permission::PERMISSION_SETTING;
+use crate::claude::content::{self, ContentInput};
+use crate::claude::emitter::EventMetricsSnapshot;
+use crate::claude::host::{tauri_host, Host};
+use crate::claude::launch::LaunchSettings;
+use crate::claude::locator::CliInfo;
+use crate::claude::orphans;
+use crate::claude::permissions::PermissionSettings;
+use crate::claude::process::{ProcessManager, ProcessStatus, SpawnOptions};
+use crate::claude::provider::ProviderInfo;
+use crate::claude::queue::QueuedPrompt;
+use crate::claude::scheduler::{SchedulerStatus, SessionPriority};
 use crate::claude::session_store;
+use crate::claude::turn_summary::TurnSummary;
+use crate::claude::watchdog::WatchdogSettings;
+use crate::db;
 
 /// Resolve shell-style paths: expand `~` to home dir, `.` to current dir
-fn resolve_project_path(path: &str) -> Result<String, String> {
+pub(crate) fn resolve_project_path(path: &str) -> Result<String, String> {
     let expanded = if path == "~" {
         dirs::home_dir()
             .ok_or("Could not determine home directory")?
@@ -62,27 +77,42 @@ pub struct AppState {
     pub process_manager: Arc<ProcessManager>,
 }
 
+/// Merge the standalone `model` argument into the launch settings
+fn launch_settings_with_model(
+    launch: Option<LaunchSettings>,
+    model: Option<String>,
+) -> LaunchSettings {
+    let mut launch = launch.unwrap_or_default();
+    if model.is_some() {
+        launch.model = model;
+    }
+    launch
+}
+
 /// Create a new Claude Code session
 #[tauri::command]
 pub async fn create_session(
     project_path: String,
     model: Option<String>,
+    launch: Option<LaunchSettings>,
     state: State<'_, AppState>,
     app: tauri::AppHandle,
 ) -> Result<SessionInfo, String> {
     let project_path = resolve_project_path(&project_path)?;
     let session_id = uuid::Uuid::new_v4().to_string();
     let now = chrono::Utc::now().to_rfc3339();
+    let launch = launch_settings_with_model(launch, model);
+    let model = launch.model.clone();
 
     let opts = SpawnOptions {
         session_id: session_id.clone(),
         project_path: project_path.clone(),
         claude_cli_path: None,
         resume_session_id: None,
-        model: model.clone(),
+        launch,
     };
 
-    state.process_manager.spawn(opts, app).await?;
+    state.process_manager.spawn(opts, tauri_host(app)).await?;
 
     Ok(SessionInfo {
         id: session_id,
@@ -102,7 +132,7 @@ pub async fn create_session(
     })
 }
 
-/// Send a message to an active session (handles multi-turn via --resume)
+/// Send a message to an active session. Queued if a turn is still running.
 #[tauri::command]
 pub async fn send_message(
     session_id: String,
@@ -110,11 +140,67 @@ pub async fn send_message(
     project_path: String,
     state: State<'_, AppState>,
     app: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<QueuedPrompt, String> {
+    let project_path = resolve_project_path(&project_path)?;
+    state
+        .process_manager
+        .send_message(&session_id, &message, &project_path, tauri_host(app))
+        .await
+}
+
+/// Send a turn made of text, images and documents to a session. Attachments
+/// are read and validated before the turn is queued.
+#[tauri::command]
+pub async fn send_content(
+    session_id: String,
+    content: Vec<ContentInput>,
+    project_path: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<QueuedPrompt, String> {
     let project_path = resolve_project_path(&project_path)?;
+    let blocks = content::resolve(content, &project_path).await?;
+    state
+        .process_manager
+        .send_content(&session_id, blocks, &project_path, tauri_host(app))
+        .await
+}
+
+/// List prompts waiting behind a session's running turn
+#[tauri::command]
+pub async fn get_queued_prompts(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<QueuedPrompt>, String> {
+    Ok(state.process_manager.queued_prompts(&session_id).await)
+}
+
+/// Move a queued prompt to a new position in its session's queue
+#[tauri::command]
+pub async fn reorder_queued_prompt(
+    session_id: String,
+    prompt_id: String,
+    index: usize,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<Vec<QueuedPrompt>, String> {
+    state
+        .process_manager
+        .move_queued_prompt(&session_id, &prompt_id, index, &tauri_host(app))
+        .await
+}
+
+/// Cancel a queued prompt before it is sent
+#[tauri::command]
+pub async fn cancel_queued_prompt(
+    session_id: String,
+    prompt_id: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<QueuedPrompt, String> {
     state
         .process_manager
-        .send_message(&session_id, &message, &project_path, app)
+        .cancel_queued_prompt(&session_id, &prompt_id, &tauri_host(app))
         .await
 }
 
@@ -123,28 +209,327 @@ pub async fn send_message(
 pub async fn kill_session(
     session_id: String,
     state: State<'_, AppState>,
+    app: tauri::AppHandle,
 ) -> Result<(), String> {
-    state.process_manager.kill(&session_id).await
+    state.process_manager.kill(&session_id, &tauri_host(app)).await
 }
 
+/// Interrupt a session's running turn (SIGINT, then SIGTERM, then SIGKILL).
+/// The session can be resumed afterwards by sending another message.
+#[tauri::command]
+pub async fn interrupt_session(
+    session_id: String,
+    grace_ms: Option<u64>,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    state
+        .process_manager
+        .interrupt(&session_id, grace_ms.map(Duration::from_millis), &tauri_host(app))
+        .await
+}
+
+/// Settings key holding the global limit on concurrently running turns
+pub const MAX_CONCURRENT_TURNS_SETTING: &str = "maxConcurrentTurns";
+
+/// Get running and waiting sessions and the concurrency limit
+#[tauri::command]
+pub async fn get_scheduler_status(state: State<'_, AppState>) -> Result<SchedulerStatus, String> {
+    Ok(state.process_manager.scheduler_status().await)
+}
+
+/// Set how many turns may run at once across all sessions (persisted)
+#[tauri::command]
+pub async fn set_max_concurrent_turns(
+    max_concurrent: usize,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<SchedulerStatus, String> {
+    if max_concurrent == 0 {
+        return Err("Maximum concurrent turns must be at least 1".to_string());
+    }
+
+    let pool = db::pool(&app).await?;
+    db::settings::set(&pool, MAX_CONCURRENT_TURNS_SETTING, &serde_json::json!(max_concurrent))
+        .await?;
+    state
+        .process_manager
+        .set_max_concurrent_turns(max_concurrent, &tauri_host(app))
+        .await;
+
+    Ok(state.process_manager.scheduler_status().await)
+}
+
+/// Mark a session as interactive or background for turn scheduling
+#[tauri::command]
+pub async fn set_session_priority(
+    session_id: String,
+    priority: SessionPriority,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    state
+        .process_manager
+        .set_priority(&session_id, priority, &tauri_host(app))
+        .await;
+    Ok(())
+}
+
+/// Settings key holding the watchdog's stall threshold and turn time limit
+pub const WATCHDOG_SETTING: &str = "watchdog";
+
+/// Get the stall threshold and per-turn time limit
+#[tauri::command]
+pub async fn get_watchdog_settings(
+    state: State<'_, AppState>,
+) -> Result<WatchdogSettings, String> {
+    Ok(state.process_manager.watchdog_settings().await)
+}
+
+/// Set the stall threshold and per-turn time limit (persisted)
+#[tauri::command]
+pub async fn set_watchdog_settings(
+    settings: WatchdogSettings,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    settings.validate()?;
+    let pool = db::pool(&app).await?;
+    let value = serde_json::to_value(&settings)
+        .map_err(|e| format!("Failed to serialize watchdog settings: {}", e))?;
+    db::settings::set(&pool, WATCHDOG_SETTING, &value).await?;
+    state.process_manager.set_watchdog_settings(settings).await;
+    Ok(())
+}
+
+/// Mirror every raw CLI event for a session as `claude:stream_event` (off by default)
+#[tauri::command]
+pub async fn set_raw_event_mirroring(
+    session_id: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .process_manager
+        .set_raw_event_mirroring(&session_id, enabled)
+        .await;
+    Ok(())
+}
+
+/// Get a session's event emission counters (received, emitted, merged, dropped)
+#[tauri::command]
+pub async fn get_event_metrics(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<EventMetricsSnapshot, String> {
+    Ok(state.process_manager.event_metrics(&session_id).await)
+}
+
+/// Settings key holding the user's Claude CLI path override
+pub const CLI_PATH_SETTING: &str = "claudeCliPath";
+
 /// Detect the Claude CLI binary path
 #[tauri::command]
-pub async fn detect_claude_cli() -> Result<Option<String>, String> {
-    Ok(ProcessManager::detect_cli_path().await)
+pub async fn detect_claude_cli(state: State<'_, AppState>) -> Result<Option<String>, String> {
+    Ok(state.process_manager.cli().find_path().await)
+}
+
+/// Probe the Claude CLI (version and supported flags). Errors if it is
+/// missing or too old to drive.
+#[tauri::command]
+pub async fn get_claude_cli_info(state: State<'_, AppState>) -> Result<CliInfo, String> {
+    state.process_manager.cli().locate(None).await
+}
+
+/// Set (or clear with `None`) the Claude CLI path override.
+/// The path is validated before it is persisted.
+#[tauri::command]
+pub async fn set_claude_cli_path(
+    path: Option<String>,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<Option<CliInfo>, String> {
+    let cli = state.process_manager.cli();
+    cli.clear_cache().await;
+
+    let info = match path.as_deref() {
+        Some(path) => Some(cli.locate(Some(path.to_string())).await?),
+        None => None,
+    };
+
+    let pool = db::pool(&app).await?;
+    match &path {
+        Some(path) => db::settings::set(&pool, CLI_PATH_SETTING, &serde_json::json!(path)).await?,
+        None => db::settings::remove(&pool, CLI_PATH_SETTING).await?,
+    }
+    cli.set_override(path).await;
+
+    Ok(info)
+}
+
+/// Apply persisted backend settings — the CLI path override, the
+/// concurrency limit, the watchdog, environment profiles and tool approval
+/// settings and rules — to the process manager (called at startup)
+pub async fn load_persisted_settings(
+    process_manager: &Arc<ProcessManager>,
+    app: &tauri::AppHandle,
+) -> Result<(), String> {
+    let pool = db::pool(app).await?;
+    let path = db::settings::get(&pool, CLI_PATH_SETTING)
+        .await?
+        .and_then(|v| v.as_str().map(String::from));
+    process_manager.cli().set_override(path).await;
+
+    let max_concurrent = db::settings::get(&pool, MAX_CONCURRENT_TURNS_SETTING)
+        .await?
+        .and_then(|v| v.as_u64());
+    if let Some(max_concurrent) = max_concurrent {
+        process_manager
+            .set_max_concurrent_turns(max_concurrent as usize, &tauri_host(app.clone()))
+            .await;
+    }
+
+    let watchdog = db::settings::get(&pool, WATCHDOG_SETTING)
+        .await?
+        .and_then(|v| serde_json::from_value::<WatchdogSettings>(v).ok());
+    if let Some(watchdog) = watchdog {
+        process_manager.set_watchdog_settings(watchdog).await;
+    }
+
+    let profiles = db::env_profiles::load_all(&pool).await?;
+    process_manager.set_env_profiles(profiles).await;
+
+    let permissions = process_manager.permissions();
+    let settings = db::settings::get(&pool, PERMISSION_SETTING)
+        .await?
+        .and_then(|v| serde_json::from_value::<PermissionSettings>(v).ok());
+    if let Some(settings) = settings {
+        permissions.set_settings(settings).await;
+    }
+    permissions
+        .set_rules(db::permission_rules::load_all(&pool).await?)
+        .await;
+    Ok(())
+}
+
+/// Reload sessions stored in SQLite into the process manager (called at startup).
+///
+/// Restores each session's Claude session ID and launch settings so it can be
+/// resumed. Sessions whose process was still alive when the app went away are
+/// marked paused, and any CLI process they left behind is reaped.
+pub async fn restore_sessions(process_manager: &ProcessManager, host: &Host) -> Result<(), String> {
+    let pool = host.database().await?;
+    let paused = ProcessStatus::Paused.to_string();
+    let live = [
+        ProcessStatus::Starting.to_string(),
+        ProcessStatus::Running.to_string(),
+        ProcessStatus::WaitingInput.to_string(),
+    ];
+
+    for record in db::sessions::load_all(&pool).await? {
+        if let Some(claude_session_id) = record.claude_session_id {
+            process_manager
+                .register_claude_session_id(&record.id, claude_session_id)
+                .await;
+        }
+        match record.launch_settings.as_deref().map(serde_json::from_str) {
+            Some(Ok(launch)) => process_manager.set_launch_settings(&record.id, launch).await,
+            Some(Err(e)) => log::warn!("Invalid launch settings for session {}: {}", record.id, e),
+            None => {}
+        }
+
+        if let Some(pid) = record.pid {
+            if orphans::reap(pid as u32).await {
+                log::info!("Reaped orphaned process {} of session {}", pid, record.id);
+            }
+        }
+        if record.pid.is_some() || live.contains(&record.status) {
+            db::sessions::update_status(&pool, &record.id, &paused, None).await?;
+        }
+    }
+    Ok(())
+}
+
+/// List sessions created in the app, as stored in SQLite
+#[tauri::command]
+pub async fn list_saved_sessions(app: tauri::AppHandle) -> Result<Vec<SessionInfo>, String> {
+    let pool = db::pool(&app).await?;
+    let sessions = db::sessions::load_all(&pool).await?;
+    Ok(sessions
+        .into_iter()
+        .map(|record| SessionInfo {
+            id: record.id,
+            claude_session_id: record.claude_session_id,
+            name: record.name,
+            project_path: record.project_path,
+            worktree_path: record.worktree_path,
+            status: record.status,
+            model: record.model,
+            created_at: record.created_at,
+            updated_at: record.updated_at,
+            total_input_tokens: record.total_input_tokens,
+            total_output_tokens: record.total_output_tokens,
+            is_agent_team: record.is_agent_team,
+            team_role: record.team_role,
+            parent_session_id: record.parent_session_id,
+        })
+        .collect())
+}
+
+/// How many activity entries are returned when the caller doesn't say
+const DEFAULT_ACTIVITY_LIMIT: u32 = 200;
+
+/// Get the latest entries of a session's activity log (hook runs), oldest first
+#[tauri::command]
+pub async fn get_session_activity(
+    session_id: String,
+    limit: Option<u32>,
+    app: tauri::AppHandle,
+) -> Result<Vec<db::activity::ActivityEntry>, String> {
+    let pool = db::pool(&app).await?;
+    db::activity::list(&pool, &session_id, limit.unwrap_or(DEFAULT_ACTIVITY_LIMIT)).await
+}
+
+/// How many turn summaries are returned when the caller doesn't say
+const DEFAULT_TURN_SUMMARY_LIMIT: u32 = 100;
+
+/// Get the latest turn summaries of a session, oldest first
+#[tauri::command]
+pub async fn get_turn_summaries(
+    session_id: String,
+    limit: Option<u32>,
+    app: tauri::AppHandle,
+) -> Result<Vec<TurnSummary>, String> {
+    let pool = db::pool(&app).await?;
+    db::turn_summaries::list(&pool, &session_id, limit.unwrap_or(DEFAULT_TURN_SUMMARY_LIMIT))
+        .await
 }
 
-/// Discover existing Claude Code sessions from ~/.claude/projects/
+/// Discover existing sessions of every agent provider (for Claude Code,
+/// from ~/.claude/projects/)
 #[tauri::command]
-pub async fn discover_sessions() -> Result<Vec<session_store::DiscoveredSession>, String> {
-    Ok(session_store::discover_sessions())
+pub async fn discover_sessions(
+    state: State<'_, AppState>,
+) -> Result<Vec<session_store::DiscoveredSession>, String> {
+    Ok(state.process_manager.discover_sessions().await)
 }
 
-/// Get messages from an existing Claude Code session (parsed from JSONL)
+/// Get messages from an existing session's transcript. `provider` defaults
+/// to the Claude CLI.
 #[tauri::command]
 pub async fn get_session_messages(
     claude_session_id: String,
+    provider: Option<String>,
+    state: State<'_, AppState>,
 ) -> Result<Vec<session_store::ParsedMessage>, String> {
-    Ok(session_store::parse_session_messages(&claude_session_id))
+    let provider = state.process_manager.provider(provider.as_deref()).await?;
+    Ok(provider.session_messages(&claude_session_id))
+}
+
+/// List the agent providers sessions can be started with
+#[tauri::command]
+pub async fn list_providers(state: State<'_, AppState>) -> Result<Vec<ProviderInfo>, String> {
+    Ok(state.process_manager.providers().await)
 }
 
 /// Resume an existing Claude Code session (discovered from ~/.claude/)
@@ -152,12 +537,16 @@ pub async fn get_session_messages(
 pub async fn resume_session(
     claude_session_id: String,
     project_path: String,
+    model: Option<String>,
+    launch: Option<LaunchSettings>,
     state: State<'_, AppState>,
-    _app: tauri::AppHandle,
+    app: tauri::AppHandle,
 ) -> Result<SessionInfo, String> {
     let project_path = resolve_project_path(&project_path)?;
     let session_id = uuid::Uuid::new_v4().to_string();
     let now = chrono::Utc::now().to_rfc3339();
+    let launch = launch_settings_with_model(launch, model);
+    let model = launch.model.clone();
 
     // Register the Claude session ID mapping
     state
@@ -165,6 +554,29 @@ pub async fn resume_session(
         .register_claude_session_id(&session_id, claude_session_id.clone())
         .await;
 
+    // Saved so the mapping survives a restart before the first message
+    let pool = db::pool(&app).await?;
+    db::sessions::save(
+        &pool,
+        &db::sessions::SessionRuntime {
+            id: session_id.clone(),
+            claude_session_id: Some(claude_session_id.clone()),
+            project_path: project_path.clone(),
+            status: ProcessStatus::Paused.to_string(),
+            model: model.clone(),
+            launch_settings: serde_json::to_string(&launch)
+                .map_err(|e| format!("Failed to serialize launch settings: {}", e))?,
+            pid: None,
+        },
+    )
+    .await?;
+
+    // Applied when the first message spawns the process
+    state
+        .process_manager
+        .set_launch_settings(&session_id, launch)
+        .await;
+
     Ok(SessionInfo {
         id: session_id,
         claude_session_id: Some(claude_session_id),
@@ -172,7 +584,7 @@ pub async fn resume_session(
         project_path,
         worktree_path: None,
         status: "paused".to_string(),
-        model: None,
+        model,
         created_at: now.clone(),
         updated_at: now,
         total_input_tokens: 0,
@@ -182,3 +594,177 @@ pub async fn resume_session(
         parent_session_id: None,
     })
 }
+
+/// Fork a session's conversation at one of its messages into a new session.
+///
+/// The new session starts paused with a copy of the history up to and
+/// including `message_uuid`, and is resumed by its first message. It keeps
+/// the original's launch settings unless `launch` is given, and records the
+/// original as its parent.
+#[tauri::command]
+pub async fn fork_session(
+    session_id: String,
+    message_uuid: String,
+    project_path: String,
+    launch: Option<LaunchSettings>,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<SessionInfo, String> {
+    let project_path = resolve_project_path(&project_path)?;
+    let manager = &state.process_manager;
+
+    // Discovered sessions go by their Claude session ID
+    let parent_claude_id = manager
+        .get_claude_session_id(&session_id)
+        .await
+        .unwrap_or_else(|| session_id.clone());
+    let parent_launch = manager.launch_settings(&session_id).await;
+    let claude_session_id = manager
+        .provider(parent_launch.provider.as_deref())
+        .await?
+        .fork_transcript(&parent_claude_id, &message_uuid)?;
+
+    let fork_id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    // The fork continues the same conversation, so it keeps the same agent
+    let launch = match launch {
+        Some(launch) => LaunchSettings {
+            provider: parent_launch.provider,
+            ..launch
+        },
+        None => parent_launch,
+    };
+    let model = launch.model.clone();
+
+    manager
+        .register_claude_session_id(&fork_id, claude_session_id.clone())
+        .await;
+
+    let pool = db::pool(&app).await?;
+    // A session that was only discovered has no row yet; add one so the fork
+    // can point at it
+    if !db::sessions::exists(&pool, &session_id).await? {
+        let parent_launch = manager.launch_settings(&session_id).await;
+        let status = manager
+            .get_status(&session_id)
+            .await
+            .unwrap_or(ProcessStatus::Paused);
+        db::sessions::save(
+            &pool,
+            &db::sessions::SessionRuntime {
+                id: session_id.clone(),
+                claude_session_id: Some(parent_claude_id),
+                project_path: project_path.clone(),
+                status: status.to_string(),
+                model: parent_launch.model.clone(),
+                launch_settings: serde_json::to_string(&parent_launch)
+                    .map_err(|e| format!("Failed to serialize launch settings: {}", e))?,
+                pid: None,
+            },
+        )
+        .await?;
+    }
+    db::sessions::save(
+        &pool,
+        &db::sessions::SessionRuntime {
+            id: fork_id.clone(),
+            claude_session_id: Some(claude_session_id.clone()),
+            project_path: project_path.clone(),
+            status: ProcessStatus::Paused.to_string(),
+            model: model.clone(),
+            launch_settings: serde_json::to_string(&launch)
+                .map_err(|e| format!("Failed to serialize launch settings: {}", e))?,
+            pid: None,
+        },
+    )
+    .await?;
+    db::sessions::set_parent(&pool, &fork_id, &session_id).await?;
+
+    manager.set_launch_settings(&fork_id, launch).await;
+
+    Ok(SessionInfo {
+        id: fork_id,
+        claude_session_id: Some(claude_session_id),
+        name: None,
+        project_path,
+        worktree_path: None,
+        status: "paused".to_string(),
+        model,
+        created_at: now.clone(),
+        updated_at: now,
+        total_input_tokens: 0,
+        total_output_tokens: 0,
+        is_agent_team: false,
+        team_role: None,
+        parent_session_id: Some(session_id),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::host::test_host;
+
+    fn stored(id: &str, status: ProcessStatus, pid: Option<u32>) -> db::sessions::SessionRuntime {
+        db::sessions::SessionRuntime {
+            id: id.to_string(),
+            claude_session_id: Some(format!("claude-{}", id)),
+            project_path: "/tmp/project".to_string(),
+            status: status.to_string(),
+            model: None,
+            launch_settings: "{}".to_string(),
+            pid,
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sessions_left_running_are_restored_as_paused() {
+        use std::os::unix::process::CommandExt;
+
+        let pool = db::test_pool().await;
+        let host = test_host(pool.clone());
+        let manager = ProcessManager::new();
+
+        // A PID that has since been reused by something else, in its own
+        // group as the CLI would be
+        let mut unrelated = std::process::Command::new("sleep")
+            .arg("30")
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        // A PID whose process is gone
+        let mut gone = std::process::Command::new("true").spawn().unwrap();
+        let gone_pid = gone.id();
+        gone.wait().unwrap();
+
+        for session in [
+            stored("reused", ProcessStatus::Running, Some(unrelated.id())),
+            stored("gone", ProcessStatus::WaitingInput, Some(gone_pid)),
+            stored("done", ProcessStatus::Completed, None),
+        ] {
+            db::sessions::save(&pool, &session).await.unwrap();
+        }
+
+        restore_sessions(&manager, &host).await.unwrap();
+
+        let records = db::sessions::load_all(&pool).await.unwrap();
+        let record = |id: &str| records.iter().find(|r| r.id == id).unwrap();
+        for id in ["reused", "gone"] {
+            assert_eq!(record(id).status, "paused", "{}", id);
+            assert_eq!(record(id).pid, None, "{}", id);
+        }
+        assert_eq!(record("done").status, "completed");
+
+        // The unrelated process is left alone
+        assert!(unrelated.try_wait().unwrap().is_none());
+        unrelated.kill().unwrap();
+        unrelated.wait().unwrap();
+
+        // Each session can be resumed
+        assert_eq!(
+            manager.get_claude_session_id("reused").await.as_deref(),
+            Some("claude-reused")
+        );
+    }
+}
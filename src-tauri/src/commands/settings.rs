@@ -1,6 +1,9 @@
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::session::AppState;
 
 /// Read the CLAUDE.md file from a project directory
 #[tauri::command]
@@ -529,9 +532,9 @@ pub async fn toggle_plugin_enabled(plugin_id: String) -> Result<bool, String> {
 
 /// Install a Claude Code plugin
 #[tauri::command]
-pub async fn install_plugin(name: String) -> Result<String, String> {
-    let cli = find_claude_cli()?;
-    let output = std::process::Command::new(&cli)
+pub async fn install_plugin(name: String, state: State<'_, AppState>) -> Result<String, String> {
+    let cli = state.process_manager.cli().locate(None).await?;
+    let output = std::process::Command::new(&cli.path)
         .args(["plugin", "add", &name])
         .output()
         .map_err(|e| format!("Failed to install plugin: {}", e))?;
@@ -544,9 +547,9 @@ pub async fn install_plugin(name: String) -> Result<String, String> {
 
 /// Remove a Claude Code plugin
 #[tauri::command]
-pub async fn remove_plugin(name: String) -> Result<String, String> {
-    let cli = find_claude_cli()?;
-    let output = std::process::Command::new(&cli)
+pub async fn remove_plugin(name: String, state: State<'_, AppState>) -> Result<String, String> {
+    let cli = state.process_manager.cli().locate(None).await?;
+    let output = std::process::Command::new(&cli.path)
         .args(["plugin", "remove", &name])
         .output()
         .map_err(|e| format!("Failed to remove plugin: {}", e))?;
@@ -557,40 +560,6 @@ pub async fn remove_plugin(name: String) -> Result<String, String> {
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
-/// Find the claude CLI binary path (sync version)
-fn find_claude_cli() -> Result<String, String> {
-    // Check common install locations first (works for GUI-launched apps)
-    let mut candidates: Vec<String> = vec![
-        "/opt/homebrew/bin/claude".to_string(),
-        "/usr/local/bin/claude".to_string(),
-        "/usr/bin/claude".to_string(),
-    ];
-
-    if let Some(home) = dirs::home_dir() {
-        let home = home.to_string_lossy();
-        candidates.push(format!("{}/.npm/bin/claude", home));
-        candidates.push(format!("{}/.local/bin/claude", home));
-    }
-
-    for path in &candidates {
-        if std::path::Path::new(path).exists() {
-            return Ok(path.to_string());
-        }
-    }
-
-    // Try PATH via `which`
-    if let Ok(output) = std::process::Command::new("which").arg("claude").output() {
-        if output.status.success() {
-            let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if !path.is_empty() {
-                return Ok(path);
-            }
-        }
-    }
-
-    Err("Claude CLI not found. Install with: npm install -g @anthropic-ai/claude-code".to_string())
-}
-
 // --- MCP Server Management ---
 
 /// Add an MCP server to ~/.claude/settings.json
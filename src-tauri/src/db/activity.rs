@@ -0,0 +1,62 @@
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+
+/// One entry of a session's activity log
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityEntry {
+    pub id: i64,
+    pub session_id: String,
+    pub kind: String,
+    pub payload: Value,
+    pub created_at: String,
+}
+
+pub async fn append(
+    pool: &SqlitePool,
+    session_id: &str,
+    kind: &str,
+    payload: &Value,
+) -> Result<(), String> {
+    sqlx::query("INSERT INTO session_activity (session_id, kind, payload) VALUES (?, ?, ?)")
+        .bind(session_id)
+        .bind(kind)
+        .bind(payload.to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to log {} activity: {}", kind, e))?;
+    Ok(())
+}
+
+/// The most recent entries of a session, oldest first
+pub async fn list(
+    pool: &SqlitePool,
+    session_id: &str,
+    limit: u32,
+) -> Result<Vec<ActivityEntry>, String> {
+    let rows = sqlx::query(
+        "SELECT * FROM (
+            SELECT * FROM session_activity WHERE session_id = ? ORDER BY id DESC LIMIT ?
+         ) ORDER BY id",
+    )
+    .bind(session_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load activity of session {}: {}", session_id, e))?;
+    rows.iter().map(entry_from_row).collect()
+}
+
+fn entry_from_row(row: &SqliteRow) -> Result<ActivityEntry, String> {
+    let read = |e: sqlx::Error| format!("Failed to read activity row: {}", e);
+    let payload: String = row.try_get("payload").map_err(read)?;
+    Ok(ActivityEntry {
+        id: row.try_get("id").map_err(read)?,
+        session_id: row.try_get("session_id").map_err(read)?,
+        kind: row.try_get("kind").map_err(read)?,
+        payload: serde_json::from_str(&payload).unwrap_or(Value::Null),
+        created_at: row.try_get("created_at").map_err(read)?,
+    })
+}
@@ -0,0 +1,150 @@
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+
+use crate::claude::env_profile::EnvProfile;
+
+/// Insert a profile or replace it. A profile that becomes a project's
+/// default takes over from the project's previous default, in the same
+/// transaction, so a project never has two.
+pub async fn save(pool: &SqlitePool, profile: &EnvProfile) -> Result<(), String> {
+    let vars = serde_json::to_string(&profile.vars)
+        .map_err(|e| format!("Failed to serialize environment variables: {}", e))?;
+    let path_prepend = serde_json::to_string(&profile.path_prepend)
+        .map_err(|e| format!("Failed to serialize PATH entries: {}", e))?;
+
+    let fail = |e: sqlx::Error| {
+        format!(
+            "Failed to save environment profile '{}': {}",
+            profile.name, e
+        )
+    };
+    let mut tx = pool.begin().await.map_err(fail)?;
+    if let Some(project_path) = &profile.project_path {
+        sqlx::query(
+            "UPDATE env_profiles SET project_path = NULL
+             WHERE project_path = ? AND id != ?",
+        )
+        .bind(project_path)
+        .bind(&profile.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            format!(
+                "Failed to update default profile of {}: {}",
+                project_path, e
+            )
+        })?;
+    }
+
+    sqlx::query(
+        "INSERT INTO env_profiles
+            (id, name, project_path, vars, path_prepend, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            project_path = excluded.project_path,
+            vars = excluded.vars,
+            path_prepend = excluded.path_prepend,
+            updated_at = excluded.updated_at",
+    )
+    .bind(&profile.id)
+    .bind(&profile.name)
+    .bind(&profile.project_path)
+    .bind(vars)
+    .bind(path_prepend)
+    .bind(&profile.created_at)
+    .bind(&profile.updated_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(fail)?;
+    tx.commit().await.map_err(fail)
+}
+
+/// Load every profile, by name
+pub async fn load_all(pool: &SqlitePool) -> Result<Vec<EnvProfile>, String> {
+    let rows = sqlx::query("SELECT * FROM env_profiles ORDER BY name")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load environment profiles: {}", e))?;
+    rows.iter().map(profile_from_row).collect()
+}
+
+pub async fn delete(pool: &SqlitePool, id: &str) -> Result<(), String> {
+    sqlx::query("DELETE FROM env_profiles WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete environment profile {}: {}", id, e))?;
+    Ok(())
+}
+
+fn profile_from_row(row: &SqliteRow) -> Result<EnvProfile, String> {
+    let read = |e: sqlx::Error| format!("Failed to read environment profile row: {}", e);
+    let vars: String = row.try_get("vars").map_err(read)?;
+    let path_prepend: String = row.try_get("path_prepend").map_err(read)?;
+
+    Ok(EnvProfile {
+        id: row.try_get("id").map_err(read)?,
+        name: row.try_get("name").map_err(read)?,
+        project_path: row.try_get("project_path").map_err(read)?,
+        vars: serde_json::from_str(&vars)
+            .map_err(|e| format!("Invalid environment variables in profile: {}", e))?,
+        path_prepend: serde_json::from_str(&path_prepend).unwrap_or_default(),
+        created_at: row.try_get("created_at").map_err(read)?,
+        updated_at: row.try_get("updated_at").map_err(read)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::env_profile::EnvProfileInput;
+
+    fn profile(id: &str, project_path: Option<&str>) -> EnvProfile {
+        let input = EnvProfileInput {
+            name: id.to_string(),
+            project_path: project_path.map(String::from),
+            vars: Vec::new(),
+            path_prepend: Vec::new(),
+        };
+        EnvProfile::from_input(id.to_string(), input, None, "2026-01-01T00:00:00Z".into()).unwrap()
+    }
+
+    fn defaults(profiles: &[EnvProfile]) -> Vec<(&str, Option<&str>)> {
+        profiles
+            .iter()
+            .map(|p| (p.id.as_str(), p.project_path.as_deref()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn a_project_has_at_most_one_default_profile() {
+        let pool = crate::db::test_pool().await;
+        // The last profile saved as a project's default takes over
+        for (id, project_path) in [
+            ("proxy", "/work/app"),
+            ("staging", "/work/other"),
+            ("direct", "/work/app"),
+        ] {
+            save(&pool, &profile(id, Some(project_path))).await.unwrap();
+        }
+
+        let profiles = load_all(&pool).await.unwrap();
+        assert_eq!(
+            defaults(&profiles),
+            vec![
+                ("direct", Some("/work/app")),
+                ("proxy", None),
+                ("staging", Some("/work/other")),
+            ]
+        );
+
+        // The database refuses a second default however it is written
+        let duplicate = sqlx::query(
+            "INSERT INTO env_profiles (id, name, project_path) VALUES ('x', 'x', '/work/app')",
+        )
+        .execute(&pool)
+        .await;
+        assert!(duplicate.is_err());
+    }
+}
@@ -1,5 +1,19 @@
+pub mod activity;
+pub mod env_profiles;
+pub mod permission_rules;
+pub mod schedules;
+pub mod sessions;
+pub mod settings;
+pub mod turn_summaries;
+pub mod usage;
+
 use serde::{Deserialize, Serialize};
-use tauri_plugin_sql::{Migration, MigrationKind};
+use sqlx::SqlitePool;
+use tauri::Manager;
+use tauri_plugin_sql::{DbInstances, DbPool, Migration, MigrationKind};
+
+/// Connection URL of the app database, preloaded by tauri-plugin-sql
+pub const DB_URL: &str = "sqlite:openclaudgents.db";
 
 /// Session record stored in SQLite
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -20,14 +34,98 @@ pub struct SessionRecord {
     pub is_agent_team: bool,
     pub team_role: Option<String>,
     pub parent_session_id: Option<String>,
+    /// `LaunchSettings` as JSON
+    pub launch_settings: Option<String>,
+    /// CLI process ID while the session's process is running
+    pub pid: Option<i64>,
 }
 
 /// Returns the SQLite migrations for the app database
 pub fn get_migrations() -> Vec<Migration> {
-    vec![Migration {
-        version: 1,
-        description: "Initial schema: sessions, worktrees, usage_log, settings",
-        sql: include_str!("../../migrations/001_initial.sql"),
-        kind: MigrationKind::Up,
-    }]
+    vec![
+        Migration {
+            version: 1,
+            description: "Initial schema: sessions, worktrees, usage_log, settings",
+            sql: include_str!("../../migrations/001_initial.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 2,
+            description: "Session recovery: launch settings and process ID",
+            sql: include_str!("../../migrations/002_session_recovery.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 3,
+            description: "Scheduled prompts and their run log",
+            sql: include_str!("../../migrations/003_prompt_schedules.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 4,
+            description: "Environment profiles",
+            sql: include_str!("../../migrations/004_env_profiles.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 5,
+            description: "Per-project always-allow rules for tool approvals",
+            sql: include_str!("../../migrations/005_permission_rules.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 6,
+            description: "Session activity log",
+            sql: include_str!("../../migrations/006_session_activity.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 7,
+            description: "Per-turn summaries: timings, cost and permission denials",
+            sql: include_str!("../../migrations/007_turn_summaries.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 8,
+            description: "Message IDs on usage rows, to count each message once",
+            sql: include_str!("../../migrations/008_usage_message_ids.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 9,
+            description: "At most one default environment profile per project",
+            sql: include_str!("../../migrations/009_env_profile_defaults.sql"),
+            kind: MigrationKind::Up,
+        },
+    ]
+}
+
+/// Get the app database pool that tauri-plugin-sql opened (and migrated) at startup
+pub async fn pool(app: &tauri::AppHandle) -> Result<SqlitePool, String> {
+    let instances = app
+        .try_state::<DbInstances>()
+        .ok_or("Database plugin is not initialised")?;
+    let instances = instances.0.read().await;
+    match instances.get(DB_URL) {
+        Some(DbPool::Sqlite(pool)) => Ok(pool.clone()),
+        None => Err(format!("Database {} is not loaded", DB_URL)),
+    }
+}
+
+/// A fresh in-memory database with every migration applied, for unit tests
+#[cfg(test)]
+pub(crate) async fn test_pool() -> SqlitePool {
+    // One connection, as each connection to `:memory:` is its own database
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("open in-memory database");
+    for migration in get_migrations() {
+        sqlx::raw_sql(migration.sql)
+            .execute(&pool)
+            .await
+            .unwrap_or_else(|e| panic!("migration {} failed: {}", migration.version, e));
+    }
+    pool
 }
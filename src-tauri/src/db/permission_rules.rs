@@ -0,0 +1,54 @@
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+
+use crate::claude::permissions::PermissionRule;
+
+/// Insert a rule; a rule for the same tool in the same project is kept as is
+pub async fn save(pool: &SqlitePool, rule: &PermissionRule) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO permission_rules (id, project_path, tool_name, created_at)
+         VALUES (?, ?, ?, ?)
+         ON CONFLICT(project_path, tool_name) DO NOTHING",
+    )
+    .bind(&rule.id)
+    .bind(&rule.project_path)
+    .bind(&rule.tool_name)
+    .bind(&rule.created_at)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        format!(
+            "Failed to save permission rule for {}: {}",
+            rule.tool_name, e
+        )
+    })?;
+    Ok(())
+}
+
+/// Load every rule, by project and tool
+pub async fn load_all(pool: &SqlitePool) -> Result<Vec<PermissionRule>, String> {
+    let rows = sqlx::query("SELECT * FROM permission_rules ORDER BY project_path, tool_name")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load permission rules: {}", e))?;
+    rows.iter().map(rule_from_row).collect()
+}
+
+pub async fn delete(pool: &SqlitePool, id: &str) -> Result<(), String> {
+    sqlx::query("DELETE FROM permission_rules WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete permission rule {}: {}", id, e))?;
+    Ok(())
+}
+
+fn rule_from_row(row: &SqliteRow) -> Result<PermissionRule, String> {
+    let read = |e: sqlx::Error| format!("Failed to read permission rule row: {}", e);
+    Ok(PermissionRule {
+        id: row.try_get("id").map_err(read)?,
+        project_path: row.try_get("project_path").map_err(read)?,
+        tool_name: row.try_get("tool_name").map_err(read)?,
+        created_at: row.try_get("created_at").map_err(read)?,
+    })
+}
@@ -0,0 +1,217 @@
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+
+use crate::schedules::{PromptSchedule, ScheduleRun};
+
+/// Insert a schedule or replace it
+pub async fn save(pool: &SqlitePool, schedule: &PromptSchedule) -> Result<(), String> {
+    let spec = serde_json::to_string(&schedule.spec)
+        .map_err(|e| format!("Failed to serialize schedule: {}", e))?;
+    let launch = serde_json::to_string(&schedule.launch)
+        .map_err(|e| format!("Failed to serialize launch settings: {}", e))?;
+
+    sqlx::query(
+        "INSERT INTO prompt_schedules
+            (id, name, session_id, project_path, prompt, spec, launch_settings,
+             enabled, next_run_at, last_run_at, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            session_id = excluded.session_id,
+            project_path = excluded.project_path,
+            prompt = excluded.prompt,
+            spec = excluded.spec,
+            launch_settings = excluded.launch_settings,
+            enabled = excluded.enabled,
+            next_run_at = excluded.next_run_at,
+            last_run_at = excluded.last_run_at",
+    )
+    .bind(&schedule.id)
+    .bind(&schedule.name)
+    .bind(&schedule.session_id)
+    .bind(&schedule.project_path)
+    .bind(&schedule.prompt)
+    .bind(spec)
+    .bind(launch)
+    .bind(schedule.enabled)
+    .bind(&schedule.next_run_at)
+    .bind(&schedule.last_run_at)
+    .bind(&schedule.created_at)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save schedule {}: {}", schedule.id, e))?;
+    Ok(())
+}
+
+/// Load every schedule, oldest first
+pub async fn load_all(pool: &SqlitePool) -> Result<Vec<PromptSchedule>, String> {
+    let rows = sqlx::query("SELECT * FROM prompt_schedules ORDER BY created_at")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load schedules: {}", e))?;
+    rows.iter().map(schedule_from_row).collect()
+}
+
+pub async fn load(pool: &SqlitePool, id: &str) -> Result<Option<PromptSchedule>, String> {
+    let row = sqlx::query("SELECT * FROM prompt_schedules WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to load schedule {}: {}", id, e))?;
+    row.as_ref().map(schedule_from_row).transpose()
+}
+
+/// Delete a schedule and its run log
+pub async fn delete(pool: &SqlitePool, id: &str) -> Result<(), String> {
+    sqlx::query("DELETE FROM schedule_runs WHERE schedule_id = ?")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete runs of schedule {}: {}", id, e))?;
+    sqlx::query("DELETE FROM prompt_schedules WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete schedule {}: {}", id, e))?;
+    Ok(())
+}
+
+/// Record that a schedule ran and when it runs next. A schedule with no
+/// next run (e.g. a one-off) is disabled.
+pub async fn mark_ran(
+    pool: &SqlitePool,
+    id: &str,
+    last_run_at: &str,
+    next_run_at: Option<&str>,
+) -> Result<(), String> {
+    sqlx::query(
+        "UPDATE prompt_schedules
+         SET last_run_at = ?, next_run_at = ?, enabled = enabled AND ? IS NOT NULL
+         WHERE id = ?",
+    )
+    .bind(last_run_at)
+    .bind(next_run_at)
+    .bind(next_run_at)
+    .bind(id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to update schedule {}: {}", id, e))?;
+    Ok(())
+}
+
+/// Insert a run or update its outcome
+pub async fn save_run(pool: &SqlitePool, run: &ScheduleRun) -> Result<(), String> {
+    let diff = run
+        .diff
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| format!("Failed to serialize run diff: {}", e))?;
+
+    sqlx::query(
+        "INSERT INTO schedule_runs
+            (id, schedule_id, session_id, started_at, finished_at, outcome, is_error,
+             input_tokens, output_tokens, cost_usd, diff_summary, error)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET
+            session_id = excluded.session_id,
+            finished_at = excluded.finished_at,
+            outcome = excluded.outcome,
+            is_error = excluded.is_error,
+            input_tokens = excluded.input_tokens,
+            output_tokens = excluded.output_tokens,
+            cost_usd = excluded.cost_usd,
+            diff_summary = excluded.diff_summary,
+            error = excluded.error",
+    )
+    .bind(&run.id)
+    .bind(&run.schedule_id)
+    .bind(&run.session_id)
+    .bind(&run.started_at)
+    .bind(&run.finished_at)
+    .bind(&run.outcome)
+    .bind(run.is_error)
+    .bind(run.input_tokens)
+    .bind(run.output_tokens)
+    .bind(run.cost_usd)
+    .bind(diff)
+    .bind(&run.error)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save schedule run {}: {}", run.id, e))?;
+    Ok(())
+}
+
+/// Most recent runs of a schedule, newest first
+pub async fn load_runs(
+    pool: &SqlitePool,
+    schedule_id: &str,
+    limit: u32,
+) -> Result<Vec<ScheduleRun>, String> {
+    let rows = sqlx::query(
+        "SELECT * FROM schedule_runs WHERE schedule_id = ? ORDER BY started_at DESC LIMIT ?",
+    )
+    .bind(schedule_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load runs of schedule {}: {}", schedule_id, e))?;
+    rows.iter().map(run_from_row).collect()
+}
+
+/// Close runs left `running` by an app instance that went away
+pub async fn interrupt_unfinished_runs(pool: &SqlitePool) -> Result<(), String> {
+    sqlx::query(
+        "UPDATE schedule_runs
+         SET outcome = 'interrupted', is_error = 1, finished_at = ?
+         WHERE outcome = 'running'",
+    )
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to close unfinished schedule runs: {}", e))?;
+    Ok(())
+}
+
+fn schedule_from_row(row: &SqliteRow) -> Result<PromptSchedule, String> {
+    let read = |e: sqlx::Error| format!("Failed to read schedule row: {}", e);
+    let spec: String = row.try_get("spec").map_err(read)?;
+    let launch: Option<String> = row.try_get("launch_settings").map_err(read)?;
+
+    Ok(PromptSchedule {
+        id: row.try_get("id").map_err(read)?,
+        name: row.try_get("name").map_err(read)?,
+        session_id: row.try_get("session_id").map_err(read)?,
+        project_path: row.try_get("project_path").map_err(read)?,
+        prompt: row.try_get("prompt").map_err(read)?,
+        spec: serde_json::from_str(&spec)
+            .map_err(|e| format!("Invalid schedule spec '{}': {}", spec, e))?,
+        launch: launch
+            .and_then(|l| serde_json::from_str(&l).ok())
+            .unwrap_or_default(),
+        enabled: row.try_get("enabled").map_err(read)?,
+        next_run_at: row.try_get("next_run_at").map_err(read)?,
+        last_run_at: row.try_get("last_run_at").map_err(read)?,
+        created_at: row.try_get("created_at").map_err(read)?,
+    })
+}
+
+fn run_from_row(row: &SqliteRow) -> Result<ScheduleRun, String> {
+    let read = |e: sqlx::Error| format!("Failed to read schedule run row: {}", e);
+    let diff: Option<String> = row.try_get("diff_summary").map_err(read)?;
+
+    Ok(ScheduleRun {
+        id: row.try_get("id").map_err(read)?,
+        schedule_id: row.try_get("schedule_id").map_err(read)?,
+        session_id: row.try_get("session_id").map_err(read)?,
+        started_at: row.try_get("started_at").map_err(read)?,
+        finished_at: row.try_get("finished_at").map_err(read)?,
+        outcome: row.try_get("outcome").map_err(read)?,
+        is_error: row.try_get("is_error").map_err(read)?,
+        input_tokens: row.try_get("input_tokens").map_err(read)?,
+        output_tokens: row.try_get("output_tokens").map_err(read)?,
+        cost_usd: row.try_get("cost_usd").map_err(read)?,
+        diff: diff.and_then(|d| serde_json::from_str(&d).ok()),
+        error: row.try_get("error").map_err(read)?,
+    })
+}
@@ -0,0 +1,119 @@
+use sqlx::{Row, SqlitePool};
+
+use super::SessionRecord;
+
+/// What the process manager knows about a session at a point in time
+#[derive(Debug, Clone)]
+pub struct SessionRuntime {
+    pub id: String,
+    pub claude_session_id: Option<String>,
+    pub project_path: String,
+    pub status: String,
+    pub model: Option<String>,
+    /// `LaunchSettings` as JSON
+    pub launch_settings: String,
+    pub pid: Option<u32>,
+}
+
+/// Insert a session or update its runtime fields, keeping name, token totals
+/// and team metadata. A known Claude session ID is never cleared.
+pub async fn save(pool: &SqlitePool, session: &SessionRuntime) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO sessions
+            (id, claude_session_id, project_path, status, model, launch_settings, pid)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET
+            claude_session_id = COALESCE(excluded.claude_session_id, sessions.claude_session_id),
+            project_path = excluded.project_path,
+            status = excluded.status,
+            model = excluded.model,
+            launch_settings = excluded.launch_settings,
+            pid = excluded.pid,
+            updated_at = datetime('now')",
+    )
+    .bind(&session.id)
+    .bind(&session.claude_session_id)
+    .bind(&session.project_path)
+    .bind(&session.status)
+    .bind(&session.model)
+    .bind(&session.launch_settings)
+    .bind(session.pid.map(i64::from))
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save session {}: {}", session.id, e))?;
+    Ok(())
+}
+
+/// Update only the status and process ID of a stored session
+pub async fn update_status(
+    pool: &SqlitePool,
+    id: &str,
+    status: &str,
+    pid: Option<u32>,
+) -> Result<(), String> {
+    sqlx::query(
+        "UPDATE sessions SET status = ?, pid = ?, updated_at = datetime('now') WHERE id = ?",
+    )
+    .bind(status)
+    .bind(pid.map(i64::from))
+    .bind(id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to update session {}: {}", id, e))?;
+    Ok(())
+}
+
+/// Link a session to the session it was forked from
+pub async fn set_parent(pool: &SqlitePool, id: &str, parent_id: &str) -> Result<(), String> {
+    sqlx::query("UPDATE sessions SET parent_session_id = ? WHERE id = ?")
+        .bind(parent_id)
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to set parent of session {}: {}", id, e))?;
+    Ok(())
+}
+
+/// Whether a session is stored
+pub async fn exists(pool: &SqlitePool, id: &str) -> Result<bool, String> {
+    let row = sqlx::query("SELECT 1 FROM sessions WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to look up session {}: {}", id, e))?;
+    Ok(row.is_some())
+}
+
+/// Load every stored session, most recently updated first
+pub async fn load_all(pool: &SqlitePool) -> Result<Vec<SessionRecord>, String> {
+    let rows = sqlx::query("SELECT * FROM sessions ORDER BY updated_at DESC")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load sessions: {}", e))?;
+
+    rows.iter()
+        .map(|row| {
+            Ok(SessionRecord {
+                id: row.try_get("id")?,
+                claude_session_id: row.try_get("claude_session_id")?,
+                name: row.try_get("name")?,
+                project_path: row.try_get("project_path")?,
+                worktree_path: row.try_get("worktree_path")?,
+                status: row.try_get("status")?,
+                model: row.try_get("model")?,
+                created_at: row.try_get("created_at")?,
+                updated_at: row.try_get("updated_at")?,
+                total_input_tokens: row.try_get("total_input_tokens")?,
+                total_output_tokens: row.try_get("total_output_tokens")?,
+                total_cache_read_tokens: row.try_get("total_cache_read_tokens")?,
+                total_cache_creation_tokens: row.try_get("total_cache_creation_tokens")?,
+                is_agent_team: row.try_get("is_agent_team")?,
+                team_role: row.try_get("team_role")?,
+                parent_session_id: row.try_get("parent_session_id")?,
+                launch_settings: row.try_get("launch_settings")?,
+                pid: row.try_get("pid")?,
+            })
+        })
+        .collect::<Result<Vec<_>, sqlx::Error>>()
+        .map_err(|e| format!("Failed to read session row: {}", e))
+}
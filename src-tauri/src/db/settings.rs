@@ -0,0 +1,33 @@
+use sqlx::SqlitePool;
+
+/// Read a value from the key-value `settings` table (values are stored as JSON)
+pub async fn get(pool: &SqlitePool, key: &str) -> Result<Option<serde_json::Value>, String> {
+    let row: Option<(String,)> = sqlx::query_as("SELECT value FROM settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to read setting {}: {}", key, e))?;
+
+    Ok(row.and_then(|(value,)| serde_json::from_str(&value).ok()))
+}
+
+/// Insert or replace a value in the `settings` table
+pub async fn set(pool: &SqlitePool, key: &str, value: &serde_json::Value) -> Result<(), String> {
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+        .bind(key)
+        .bind(value.to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to write setting {}: {}", key, e))?;
+    Ok(())
+}
+
+/// Remove a key from the `settings` table
+pub async fn remove(pool: &SqlitePool, key: &str) -> Result<(), String> {
+    sqlx::query("DELETE FROM settings WHERE key = ?")
+        .bind(key)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to remove setting {}: {}", key, e))?;
+    Ok(())
+}
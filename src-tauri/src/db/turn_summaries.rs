@@ -0,0 +1,172 @@
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+
+use crate::claude::turn_summary::TurnSummary;
+
+pub async fn save(pool: &SqlitePool, summary: &TurnSummary) -> Result<(), String> {
+    let denials = serde_json::to_string(&summary.permission_denials)
+        .map_err(|e| format!("Failed to serialize permission denials: {}", e))?;
+    sqlx::query(
+        "INSERT INTO turn_summaries (
+            session_id, prompt_id, subtype, is_error, duration_ms, duration_api_ms,
+            num_turns, cost_usd, input_tokens, output_tokens, cache_read_tokens,
+            cache_creation_tokens, result, permission_denials, finished_at
+         ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&summary.session_id)
+    .bind(&summary.prompt_id)
+    .bind(&summary.subtype)
+    .bind(summary.is_error)
+    .bind(summary.duration_ms.map(|ms| ms as i64))
+    .bind(summary.duration_api_ms.map(|ms| ms as i64))
+    .bind(summary.num_turns)
+    .bind(summary.cost_usd)
+    .bind(summary.input_tokens as i64)
+    .bind(summary.output_tokens as i64)
+    .bind(summary.cache_read_tokens as i64)
+    .bind(summary.cache_creation_tokens as i64)
+    .bind(&summary.result)
+    .bind(denials)
+    .bind(&summary.finished_at)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        format!(
+            "Failed to save turn summary of session {}: {}",
+            summary.session_id, e
+        )
+    })?;
+    Ok(())
+}
+
+/// The most recent summaries of a session, oldest first
+pub async fn list(
+    pool: &SqlitePool,
+    session_id: &str,
+    limit: u32,
+) -> Result<Vec<TurnSummary>, String> {
+    let rows = sqlx::query(
+        "SELECT * FROM (
+            SELECT * FROM turn_summaries WHERE session_id = ? ORDER BY id DESC LIMIT ?
+         ) ORDER BY id",
+    )
+    .bind(session_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        format!(
+            "Failed to load turn summaries of session {}: {}",
+            session_id, e
+        )
+    })?;
+    rows.iter().map(summary_from_row).collect()
+}
+
+fn summary_from_row(row: &SqliteRow) -> Result<TurnSummary, String> {
+    let read = |e: sqlx::Error| format!("Failed to read turn summary row: {}", e);
+    let count = |column: &str| -> Result<u64, String> {
+        Ok(row.try_get::<i64, _>(column).map_err(read)?.max(0) as u64)
+    };
+    let millis = |column: &str| -> Result<Option<u64>, String> {
+        let ms: Option<i64> = row.try_get(column).map_err(read)?;
+        Ok(ms.map(|ms| ms.max(0) as u64))
+    };
+    let denials: String = row.try_get("permission_denials").map_err(read)?;
+    Ok(TurnSummary {
+        session_id: row.try_get("session_id").map_err(read)?,
+        prompt_id: row.try_get("prompt_id").map_err(read)?,
+        subtype: row.try_get("subtype").map_err(read)?,
+        is_error: row.try_get("is_error").map_err(read)?,
+        duration_ms: millis("duration_ms")?,
+        duration_api_ms: millis("duration_api_ms")?,
+        num_turns: row.try_get("num_turns").map_err(read)?,
+        cost_usd: row.try_get("cost_usd").map_err(read)?,
+        input_tokens: count("input_tokens")?,
+        output_tokens: count("output_tokens")?,
+        cache_read_tokens: count("cache_read_tokens")?,
+        cache_creation_tokens: count("cache_creation_tokens")?,
+        result: row.try_get("result").map_err(read)?,
+        permission_denials: serde_json::from_str(&denials).unwrap_or_default(),
+        finished_at: row.try_get("finished_at").map_err(read)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::turn_summary::PermissionDenial;
+
+    fn summary(session_id: &str, result: &str) -> TurnSummary {
+        TurnSummary {
+            session_id: session_id.to_string(),
+            prompt_id: None,
+            subtype: "success".to_string(),
+            is_error: false,
+            duration_ms: Some(1200),
+            duration_api_ms: Some(900),
+            num_turns: Some(1),
+            cost_usd: Some(0.01),
+            input_tokens: 10,
+            output_tokens: 20,
+            cache_read_tokens: 0,
+            cache_creation_tokens: 0,
+            result: Some(result.to_string()),
+            permission_denials: Vec::new(),
+            finished_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    #[tokio::test]
+    async fn summaries_round_trip_with_their_denials() {
+        let pool = crate::db::test_pool().await;
+        let mut denied = summary("s1", "Couldn't write the file");
+        denied.prompt_id = Some("p1".to_string());
+        denied.is_error = true;
+        denied.permission_denials = vec![PermissionDenial {
+            tool_name: "Write".to_string(),
+            tool_use_id: Some("toolu_1".to_string()),
+            tool_input: serde_json::json!({ "file_path": "/etc/hosts", "content": "" }),
+        }];
+        save(&pool, &denied).await.unwrap();
+
+        let loaded = list(&pool, "s1", 10).await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        let loaded = &loaded[0];
+        assert_eq!(loaded.prompt_id.as_deref(), Some("p1"));
+        assert!(loaded.is_error);
+        assert_eq!(loaded.duration_ms, Some(1200));
+        assert_eq!(loaded.cost_usd, Some(0.01));
+        assert_eq!(loaded.output_tokens, 20);
+        assert_eq!(loaded.permission_denials.len(), 1);
+        let denial = &loaded.permission_denials[0];
+        assert_eq!(denial.tool_name, "Write");
+        assert_eq!(denial.tool_use_id.as_deref(), Some("toolu_1"));
+        assert_eq!(denial.tool_input["file_path"], "/etc/hosts");
+    }
+
+    #[tokio::test]
+    async fn the_newest_summaries_are_listed_in_turn_order() {
+        let pool = crate::db::test_pool().await;
+        for turn in 1..=5 {
+            save(&pool, &summary("s1", &format!("turn {}", turn)))
+                .await
+                .unwrap();
+        }
+        save(&pool, &summary("s2", "other session")).await.unwrap();
+
+        let results = |summaries: Vec<TurnSummary>| -> Vec<String> {
+            summaries.into_iter().filter_map(|s| s.result).collect()
+        };
+        // The limit keeps the newest turns, listed oldest first
+        assert_eq!(
+            results(list(&pool, "s1", 3).await.unwrap()),
+            vec!["turn 3", "turn 4", "turn 5"]
+        );
+        assert_eq!(list(&pool, "s1", 10).await.unwrap().len(), 5);
+        assert_eq!(
+            results(list(&pool, "s2", 10).await.unwrap()),
+            vec!["other session"]
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use sqlx::SqlitePool;
+
+use crate::claude::usage::MessageUsage;
+
+/// Log one message's usage and add it to its session's token totals. A
+/// message whose ID is already logged for the session isn't counted again.
+pub async fn record(
+    pool: &SqlitePool,
+    session_id: &str,
+    usage: &MessageUsage,
+) -> Result<(), String> {
+    let fail = |e: sqlx::Error| format!("Failed to record usage of session {}: {}", session_id, e);
+    let mut tx = pool.begin().await.map_err(fail)?;
+    let logged = sqlx::query(
+        "INSERT OR IGNORE INTO usage_log
+            (session_id, message_id, input_tokens, output_tokens, cache_read_tokens,
+             cache_creation_tokens, model)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(session_id)
+    .bind(&usage.message_id)
+    .bind(usage.input_tokens as i64)
+    .bind(usage.output_tokens as i64)
+    .bind(usage.cache_read_tokens as i64)
+    .bind(usage.cache_creation_tokens as i64)
+    .bind(&usage.model)
+    .execute(&mut *tx)
+    .await
+    .map_err(fail)?;
+    if logged.rows_affected() == 0 {
+        return Ok(());
+    }
+    sqlx::query(
+        "UPDATE sessions SET
+            total_input_tokens = total_input_tokens + ?,
+            total_output_tokens = total_output_tokens + ?,
+            total_cache_read_tokens = total_cache_read_tokens + ?,
+            total_cache_creation_tokens = total_cache_creation_tokens + ?
+         WHERE id = ?",
+    )
+    .bind(usage.input_tokens as i64)
+    .bind(usage.output_tokens as i64)
+    .bind(usage.cache_read_tokens as i64)
+    .bind(usage.cache_creation_tokens as i64)
+    .bind(session_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(fail)?;
+    tx.commit().await.map_err(fail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sessions::{self, SessionRuntime};
+    use sqlx::Row;
+
+    fn message(id: &str, input: u64, output: u64, cache_read: u64) -> MessageUsage {
+        MessageUsage {
+            message_id: Some(id.to_string()),
+            model: Some("claude-sonnet-4-5".to_string()),
+            input_tokens: input,
+            output_tokens: output,
+            cache_read_tokens: cache_read,
+            cache_creation_tokens: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn usage_is_logged_and_added_to_session_totals_once() {
+        let pool = crate::db::test_pool().await;
+        let session = SessionRuntime {
+            id: "s1".to_string(),
+            claude_session_id: None,
+            project_path: "/tmp/project".to_string(),
+            status: "running".to_string(),
+            model: None,
+            launch_settings: "{}".to_string(),
+            pid: None,
+        };
+        sessions::save(&pool, &session).await.unwrap();
+
+        // One message in each of two turns
+        record(&pool, "s1", &message("msg_1", 10, 20, 100))
+            .await
+            .unwrap();
+        record(&pool, "s1", &message("msg_2", 5, 7, 300))
+            .await
+            .unwrap();
+        // The first message reported again, e.g. replayed on resume
+        record(&pool, "s1", &message("msg_1", 10, 20, 100))
+            .await
+            .unwrap();
+
+        let rows = sqlx::query(
+            "SELECT message_id, input_tokens, output_tokens, cache_read_tokens, model
+             FROM usage_log WHERE session_id = 's1' ORDER BY id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        let logged: Vec<(String, i64, i64, i64)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.get("message_id"),
+                    row.get("input_tokens"),
+                    row.get("output_tokens"),
+                    row.get("cache_read_tokens"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            logged,
+            vec![
+                ("msg_1".to_string(), 10, 20, 100),
+                ("msg_2".to_string(), 5, 7, 300),
+            ]
+        );
+        assert_eq!(rows[0].get::<String, _>("model"), "claude-sonnet-4-5");
+
+        let stored = sessions::load_all(&pool).await.unwrap();
+        assert_eq!(stored[0].total_input_tokens, 15);
+        assert_eq!(stored[0].total_output_tokens, 27);
+        assert_eq!(stored[0].total_cache_read_tokens, 400);
+        assert_eq!(stored[0].total_cache_creation_tokens, 0);
+    }
+}
@@ -1,12 +1,26 @@
 /// Event name constants for Tauri event emission
 pub const CLAUDE_STREAM_EVENT: &str = "claude:stream_event";
+pub const CLAUDE_TEXT_DELTA: &str = "claude:text_delta";
+pub const CLAUDE_TOOL_INPUT_DELTA: &str = "claude:tool_input_delta";
 pub const CLAUDE_MESSAGE_COMPLETE: &str = "claude:message_complete";
 pub const CLAUDE_TOOL_START: &str = "claude:tool_start";
+pub const CLAUDE_CONTENT_BLOCK_COMPLETE: &str = "claude:content_block_complete";
+pub const CLAUDE_THINKING_START: &str = "claude:thinking_start";
+pub const CLAUDE_THINKING_DELTA: &str = "claude:thinking_delta";
+pub const CLAUDE_THINKING_STOP: &str = "claude:thinking_stop";
 pub const CLAUDE_TOOL_COMPLETE: &str = "claude:tool_complete";
 pub const CLAUDE_SESSION_STATUS: &str = "claude:session_status";
 pub const CLAUDE_USAGE_UPDATE: &str = "claude:usage_update";
+pub const CLAUDE_TURN_SUMMARY: &str = "claude:turn_summary";
 pub const CLAUDE_STDERR: &str = "claude:stderr";
 pub const CLAUDE_COMPACTION: &str = "claude:compaction";
+pub const CLAUDE_HOOK_EVENT: &str = "claude:hook_event";
 pub const CLAUDE_SESSION_ID_RESOLVED: &str = "claude:session_id_resolved";
+pub const CLAUDE_SESSION_STALLED: &str = "claude:session_stalled";
+pub const CLAUDE_PERMISSION_REQUEST: &str = "claude:permission_request";
+pub const CLAUDE_PERMISSION_RESOLVED: &str = "claude:permission_resolved";
+pub const CLAUDE_QUEUE_UPDATED: &str = "claude:queue_updated";
+pub const CLAUDE_SCHEDULE_UPDATED: &str = "claude:schedule_updated";
+pub const SCHEDULE_RUN_UPDATED: &str = "schedule:run_updated";
 pub const GIT_STATUS_CHANGED: &str = "git:status_changed";
 pub const SESSION_DISCOVERED: &str = "session:discovered";
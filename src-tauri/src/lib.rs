@@ -1,8 +1,9 @@
-mod claude;
+pub mod claude;
 mod commands;
 mod db;
-mod events;
+pub mod events;
 mod git;
+mod schedules;
 
 use std::sync::Arc;
 
@@ -12,6 +13,7 @@ use commands::session::{self, AppState};
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let process_manager = Arc::new(ProcessManager::new());
+    let exit_manager = process_manager.clone();
 
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
@@ -19,18 +21,69 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(
             tauri_plugin_sql::Builder::default()
-                .add_migrations("sqlite:openclaudgents.db", db::get_migrations())
+                .add_migrations(db::DB_URL, db::get_migrations())
                 .build(),
         )
-        .manage(AppState { process_manager })
+        .manage(AppState {
+            process_manager: process_manager.clone(),
+        })
+        .setup(move |app| {
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = session::load_persisted_settings(&process_manager, &handle).await {
+                    log::warn!("Failed to load persisted settings: {}", e);
+                }
+                let host = claude::host::tauri_host(handle);
+                if let Err(e) = session::restore_sessions(&process_manager, &host).await {
+                    log::warn!("Failed to restore sessions: {}", e);
+                }
+                // Only once sessions are restored, so due runs can resume them
+                schedules::start(process_manager, host);
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             session::create_session,
             session::send_message,
+            session::send_content,
             session::kill_session,
+            session::interrupt_session,
+            session::get_queued_prompts,
+            session::reorder_queued_prompt,
+            session::cancel_queued_prompt,
             session::detect_claude_cli,
+            session::get_claude_cli_info,
+            session::set_claude_cli_path,
+            session::get_scheduler_status,
+            session::set_max_concurrent_turns,
+            session::set_session_priority,
+            session::get_watchdog_settings,
+            session::set_watchdog_settings,
+            session::set_raw_event_mirroring,
+            session::get_event_metrics,
+            session::list_saved_sessions,
+            session::get_session_activity,
+            session::get_turn_summaries,
             session::discover_sessions,
             session::get_session_messages,
+            session::list_providers,
             session::resume_session,
+            session::fork_session,
+            commands::schedule::list_schedules,
+            commands::schedule::create_schedule,
+            commands::schedule::update_schedule,
+            commands::schedule::delete_schedule,
+            commands::schedule::run_schedule_now,
+            commands::schedule::list_schedule_runs,
+            commands::env_profile::list_env_profiles,
+            commands::env_profile::save_env_profile,
+            commands::env_profile::delete_env_profile,
+            commands::permission::get_permission_settings,
+            commands::permission::set_permission_settings,
+            commands::permission::list_pending_permissions,
+            commands::permission::respond_to_permission,
+            commands::permission::list_permission_rules,
+            commands::permission::delete_permission_rule,
             commands::git::get_git_status,
             commands::git::get_git_diff,
             commands::git::create_worktree,
@@ -60,6 +113,12 @@ pub fn run() {
             commands::agent_team::get_mcp_servers,
             commands::agent_team::get_team_tasks,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(move |_app, event| {
+            // Make sure no agent process tree outlives the app
+            if let tauri::RunEvent::Exit = event {
+                tauri::async_runtime::block_on(exit_manager.shutdown());
+            }
+        });
 }
@@ -1,6 +1,12 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use openclaudgents_lib::claude::permission_mcp;
+
 fn main() {
+    // The CLI starts this binary as its permission-prompt MCP server
+    if std::env::args().nth(1).as_deref() == Some(permission_mcp::SERVER_ARG) {
+        std::process::exit(permission_mcp::serve());
+    }
     openclaudgents_lib::run()
 }
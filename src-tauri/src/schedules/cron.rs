@@ -0,0 +1,383 @@
+use chrono::{
+    DateTime, Datelike, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Timelike,
+};
+
+/// How far ahead to look for the next match before giving up (e.g. `0 0 30 2 *`)
+const SEARCH_LIMIT_DAYS: i64 = 366 * 5;
+
+/// A five-field cron expression (`minute hour day-of-month month day-of-week`),
+/// evaluated in local time.
+///
+/// Each field takes `*`, single values, ranges (`1-5`), steps (`*/15`,
+/// `8-18/2`) and comma-separated lists of those. Day of week runs from 0
+/// (Sunday) to 7 (Sunday again). As in classic cron, when both day fields are
+/// restricted a day matching either one qualifies; a day field starting with
+/// `*` (including `*/2`) counts as unrestricted.
+#[derive(Debug, Clone)]
+pub struct CronExpr {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days_of_month: Vec<bool>,
+    months: Vec<bool>,
+    days_of_week: Vec<bool>,
+    day_of_month_any: bool,
+    day_of_week_any: bool,
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, dom, month, dow] = fields[..] else {
+            return Err(format!(
+                "Cron expression must have 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            ));
+        };
+
+        let mut days_of_week = parse_field(dow, 0, 7, "day of week")?;
+        // 7 is another name for Sunday
+        if days_of_week[7] {
+            days_of_week[0] = true;
+        }
+        days_of_week.truncate(7);
+
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, "minute")?,
+            hours: parse_field(hour, 0, 23, "hour")?,
+            days_of_month: parse_field(dom, 1, 31, "day of month")?,
+            months: parse_field(month, 1, 12, "month")?,
+            days_of_week,
+            day_of_month_any: dom.starts_with('*'),
+            day_of_week_any: dow.starts_with('*'),
+        })
+    }
+
+    /// The first matching minute strictly after `after`, if any within five years
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.naive_local().with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = start + Duration::days(SEARCH_LIMIT_DAYS);
+
+        let mut date = start.date();
+        let mut first_day = true;
+        while date.and_hms_opt(0, 0, 0)? < limit {
+            if self.months[date.month() as usize] && self.day_matches(date) {
+                let from = if first_day {
+                    start.time()
+                } else {
+                    chrono::NaiveTime::MIN
+                };
+                if let Some(time) = self.first_time_from(date, from, after) {
+                    return Some(time);
+                }
+            }
+            date = date.succ_opt()?;
+            first_day = false;
+        }
+        None
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let dom = self.days_of_month[date.day() as usize];
+        let dow = self.days_of_week[date.weekday().num_days_from_sunday() as usize];
+        match (self.day_of_month_any, self.day_of_week_any) {
+            (true, true) => true,
+            (false, true) => dom,
+            (true, false) => dow,
+            (false, false) => dom || dow,
+        }
+    }
+
+    /// The first matching time on `date` at or after `from` that exists in
+    /// local time and is later than `after` (times skipped by a DST change
+    /// are passed over)
+    fn first_time_from(
+        &self,
+        date: NaiveDate,
+        from: chrono::NaiveTime,
+        after: DateTime<Local>,
+    ) -> Option<DateTime<Local>> {
+        for hour in from.hour()..24 {
+            if !self.hours[hour as usize] {
+                continue;
+            }
+            let first_minute = if hour == from.hour() {
+                from.minute()
+            } else {
+                0
+            };
+            for minute in first_minute..60 {
+                if !self.minutes[minute as usize] {
+                    continue;
+                }
+                let naive =
+                    NaiveDateTime::new(date, chrono::NaiveTime::from_hms_opt(hour, minute, 0)?);
+                // In an hour repeated by a DST change, run at the first of the
+                // two times that is still ahead; chrono doesn't always list
+                // the earlier one first
+                let candidates = match Local.from_local_datetime(&naive) {
+                    LocalResult::Single(time) => [Some(time), None],
+                    LocalResult::Ambiguous(a, b) => [Some(a.min(b)), Some(a.max(b))],
+                    LocalResult::None => [None, None],
+                };
+                if let Some(time) = candidates.into_iter().flatten().find(|t| *t > after) {
+                    return Some(time);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Parse one field into a lookup table indexed by value (`0..=max`)
+fn parse_field(field: &str, min: u32, max: u32, name: &str) -> Result<Vec<bool>, String> {
+    let mut allowed = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("Invalid step '{}' in {} field", step, name))?;
+                if step == 0 {
+                    return Err(format!("Step must be at least 1 in {} field", name));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                parse_value(start, min, max, name)?,
+                parse_value(end, min, max, name)?,
+            )
+        } else {
+            let value = parse_value(range, min, max, name)?;
+            // `5/15` means every 15 starting at 5
+            (value, if part.contains('/') { max } else { value })
+        };
+        if start > end {
+            return Err(format!(
+                "Range {}-{} is backwards in {} field",
+                start, end, name
+            ));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            allowed[value as usize] = true;
+        }
+    }
+    Ok(allowed)
+}
+
+fn parse_value(value: &str, min: u32, max: u32, name: &str) -> Result<u32, String> {
+    let parsed: u32 = value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' in {} field", value, name))?;
+    if parsed < min || parsed > max {
+        return Err(format!(
+            "Value {} is out of range ({}-{}) in {} field",
+            parsed, min, max, name
+        ));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluate in a zone with DST. Every test sets the same value, so tests
+    /// running in parallel can't see a different zone.
+    fn oslo() {
+        std::env::set_var("TZ", "Europe/Oslo");
+    }
+
+    /// A local time; the first one if a DST change repeats it
+    fn at(s: &str) -> DateTime<Local> {
+        let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        match Local.from_local_datetime(&naive) {
+            LocalResult::Single(time) => time,
+            LocalResult::Ambiguous(a, b) => a.min(b),
+            LocalResult::None => panic!("{} doesn't exist in local time", s),
+        }
+    }
+
+    fn next(expr: &str, after: &str) -> Option<String> {
+        CronExpr::parse(expr)
+            .unwrap()
+            .next_after(at(after))
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+    }
+
+    /// The next `n` run times after `after`
+    fn runs(expr: &str, after: &str, n: usize) -> Vec<String> {
+        let cron = CronExpr::parse(expr).unwrap();
+        let mut time = at(after);
+        (0..n)
+            .map(|_| {
+                time = cron.next_after(time).unwrap();
+                time.format("%Y-%m-%d %H:%M").to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn steps_ranges_and_lists() {
+        oslo();
+        assert_eq!(
+            runs("*/20 * * * *", "2026-01-05 10:05", 3),
+            ["2026-01-05 10:20", "2026-01-05 10:40", "2026-01-05 11:00"]
+        );
+        assert_eq!(
+            runs("0 8-18/4 * * *", "2026-01-05 09:00", 3),
+            ["2026-01-05 12:00", "2026-01-05 16:00", "2026-01-06 08:00"]
+        );
+        assert_eq!(
+            runs("15,45 9 * * 1-5", "2026-01-09 09:30", 3),
+            ["2026-01-09 09:45", "2026-01-12 09:15", "2026-01-12 09:45"]
+        );
+        // `5/15` starts at 5 and runs to the end of the field
+        assert_eq!(
+            runs("5/15 0 * * *", "2026-01-05 00:00", 4),
+            [
+                "2026-01-05 00:05",
+                "2026-01-05 00:20",
+                "2026-01-05 00:35",
+                "2026-01-05 00:50"
+            ]
+        );
+    }
+
+    #[test]
+    fn next_run_is_strictly_after() {
+        oslo();
+        assert_eq!(
+            next("30 9 * * *", "2026-01-05 09:30").as_deref(),
+            Some("2026-01-06 09:30")
+        );
+    }
+
+    #[test]
+    fn seven_is_sunday() {
+        oslo();
+        // 2026-01-05 is a Monday
+        assert_eq!(
+            next("0 12 * * 7", "2026-01-05 00:00").as_deref(),
+            Some("2026-01-11 12:00")
+        );
+        assert_eq!(
+            next("0 12 * * 0", "2026-01-05 00:00").as_deref(),
+            Some("2026-01-11 12:00")
+        );
+    }
+
+    #[test]
+    fn restricted_day_fields_match_either() {
+        oslo();
+        // The 10th, or any Friday (2026-01-09)
+        assert_eq!(
+            runs("0 9 10 * 5", "2026-01-05 00:00", 3),
+            ["2026-01-09 09:00", "2026-01-10 09:00", "2026-01-16 09:00"]
+        );
+    }
+
+    #[test]
+    fn stepped_star_day_field_is_unrestricted() {
+        oslo();
+        // Only Mondays: `*/2` doesn't switch to matching either day field
+        assert_eq!(
+            runs("0 9 */1 * 1", "2026-01-05 10:00", 2),
+            ["2026-01-12 09:00", "2026-01-19 09:00"]
+        );
+        assert_eq!(
+            runs("0 9 */2 * 1", "2026-01-05 10:00", 2),
+            ["2026-01-12 09:00", "2026-01-19 09:00"]
+        );
+        // Only the 1st, not every other weekday as well
+        assert_eq!(
+            runs("0 9 1 * */2", "2026-01-01 10:00", 2),
+            ["2026-02-01 09:00", "2026-03-01 09:00"]
+        );
+    }
+
+    #[test]
+    fn impossible_dates_never_run() {
+        oslo();
+        assert_eq!(next("0 0 30 2 *", "2026-01-01 00:00"), None);
+        assert_eq!(next("0 0 31 4,6,9,11 *", "2026-01-01 00:00"), None);
+    }
+
+    #[test]
+    fn leap_days_are_found() {
+        oslo();
+        assert_eq!(
+            next("0 0 29 2 *", "2026-01-01 00:00").as_deref(),
+            Some("2028-02-29 00:00")
+        );
+    }
+
+    #[test]
+    fn times_skipped_by_dst_are_passed_over() {
+        oslo();
+        // Clocks jump from 02:00 to 03:00 on 2026-03-29
+        assert_eq!(
+            runs("30 2 * * *", "2026-03-28 12:00", 2),
+            ["2026-03-30 02:30", "2026-03-31 02:30"]
+        );
+        let hourly = CronExpr::parse("0 * * * *").unwrap();
+        assert_eq!(
+            hourly.next_after(at("2026-03-29 01:30")),
+            Some(at("2026-03-29 03:00"))
+        );
+    }
+
+    #[test]
+    fn repeated_dst_hour_runs_once() {
+        oslo();
+        // Clocks fall back from 03:00 to 02:00 on 2026-10-25
+        let cron = CronExpr::parse("30 2 * * *").unwrap();
+        let first = cron.next_after(at("2026-10-24 12:00")).unwrap();
+        assert_eq!(first.to_rfc3339(), "2026-10-25T02:30:00+02:00");
+        let second = cron.next_after(first).unwrap();
+        assert_eq!(
+            second.format("%Y-%m-%d %H:%M").to_string(),
+            "2026-10-26 02:30"
+        );
+
+        // Evaluated during the second 02:00-03:00, the next run is later in
+        // that same hour, not its already-passed first occurrence
+        let every_five = CronExpr::parse("*/5 * * * *").unwrap();
+        let during_second = DateTime::parse_from_rfc3339("2026-10-25T02:10:00+01:00")
+            .unwrap()
+            .with_timezone(&Local);
+        let next = every_five.next_after(during_second).unwrap();
+        assert_eq!(next.to_rfc3339(), "2026-10-25T02:15:00+01:00");
+        assert_eq!(
+            every_five.next_after(next).unwrap().to_rfc3339(),
+            "2026-10-25T02:20:00+01:00"
+        );
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        for expr in [
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "10-5 * * * *",
+            "a * * * *",
+        ] {
+            assert!(
+                CronExpr::parse(expr).is_err(),
+                "{} should be rejected",
+                expr
+            );
+        }
+    }
+}
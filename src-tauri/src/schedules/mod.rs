@@ -0,0 +1,502 @@
+pub mod cron;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::claude::host::Host;
+use crate::claude::launch::LaunchSettings;
+use crate::claude::process::{ProcessManager, SpawnOptions, TurnOutcome};
+use crate::db;
+use crate::git::diff::DiffFile;
+
+use self::cron::CronExpr;
+
+/// How often the backend checks for schedules that are due
+const SCHEDULE_TICK: Duration = Duration::from_secs(20);
+
+/// When a scheduled prompt runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ScheduleSpec {
+    /// Once, at a set time
+    Once { at: DateTime<Utc> },
+    /// Whenever a five-field cron expression matches, in local time
+    Cron { expr: String },
+}
+
+impl ScheduleSpec {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ScheduleSpec::Once { .. } => Ok(()),
+            ScheduleSpec::Cron { expr } => CronExpr::parse(expr).map(|_| ()),
+        }
+    }
+
+    /// The first run time strictly after `after`, or `None` when there is none
+    pub fn next_run(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            ScheduleSpec::Once { at } => (*at > after).then_some(*at),
+            ScheduleSpec::Cron { expr } => CronExpr::parse(expr)
+                .ok()?
+                .next_after(after.with_timezone(&Local))
+                .map(|t| t.with_timezone(&Utc)),
+        }
+    }
+}
+
+/// A prompt sent to a session or project on a schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptSchedule {
+    pub id: String,
+    pub name: String,
+    /// Session that receives the prompt; `None` starts a new session in
+    /// `project_path` for every run
+    pub session_id: Option<String>,
+    pub project_path: String,
+    pub prompt: String,
+    pub spec: ScheduleSpec,
+    /// Launch settings for sessions started by a run
+    pub launch: LaunchSettings,
+    pub enabled: bool,
+    pub next_run_at: Option<String>,
+    pub last_run_at: Option<String>,
+    pub created_at: String,
+}
+
+/// The user-editable part of a schedule
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleInput {
+    pub name: String,
+    pub session_id: Option<String>,
+    pub project_path: String,
+    pub prompt: String,
+    pub spec: ScheduleSpec,
+    #[serde(default)]
+    pub launch: LaunchSettings,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl PromptSchedule {
+    /// Build a schedule from user input, working out its next run from now
+    pub fn from_input(
+        id: String,
+        input: ScheduleInput,
+        created_at: String,
+    ) -> Result<Self, String> {
+        if input.prompt.trim().is_empty() {
+            return Err("Scheduled prompt must not be empty".to_string());
+        }
+        input.spec.validate()?;
+
+        let next_run_at = input.spec.next_run(Utc::now());
+        if input.enabled && next_run_at.is_none() {
+            return Err("Schedule never runs — pick a time in the future".to_string());
+        }
+
+        Ok(Self {
+            id,
+            name: input.name,
+            session_id: input.session_id,
+            project_path: input.project_path,
+            prompt: input.prompt,
+            spec: input.spec,
+            launch: input.launch,
+            enabled: input.enabled,
+            next_run_at: next_run_at.map(|t| t.to_rfc3339()),
+            last_run_at: None,
+            created_at,
+        })
+    }
+
+    fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.enabled
+            && self
+                .next_run_at
+                .as_deref()
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                .is_some_and(|t| t <= now)
+    }
+}
+
+/// Files changed during a run, relative to the commit it started on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunDiff {
+    pub files: Vec<DiffFile>,
+    pub total_additions: usize,
+    pub total_deletions: usize,
+}
+
+/// One run of a schedule, logged for later review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleRun {
+    pub id: String,
+    pub schedule_id: String,
+    pub session_id: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    /// `running`, the turn's result subtype (`success`, `error_max_turns`, ...),
+    /// `cancelled`, `exited` or `failed`, or `interrupted` if the app quit mid-run
+    pub outcome: String,
+    pub is_error: bool,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost_usd: Option<f64>,
+    pub diff: Option<RunDiff>,
+    pub error: Option<String>,
+}
+
+/// Start the background loop that runs due schedules.
+///
+/// Runs still marked `running` from a previous app instance are closed as
+/// `interrupted`. A schedule that came due while the app was closed runs once
+/// on startup, then continues from its next regular time.
+pub fn start(manager: Arc<ProcessManager>, host: Host) {
+    tokio::spawn(async move {
+        match host.database().await {
+            Ok(pool) => {
+                if let Err(e) = db::schedules::interrupt_unfinished_runs(&pool).await {
+                    log::warn!("[schedules] {}", e);
+                }
+            }
+            Err(e) => log::warn!("[schedules] {}", e),
+        }
+
+        let mut tick = tokio::time::interval(SCHEDULE_TICK);
+        loop {
+            tick.tick().await;
+            if let Err(e) = run_due(&manager, &host).await {
+                log::warn!("[schedules] failed to run due schedules: {}", e);
+            }
+        }
+    });
+}
+
+async fn run_due(manager: &Arc<ProcessManager>, host: &Host) -> Result<(), String> {
+    let pool = host.database().await?;
+    let now = Utc::now();
+
+    for schedule in db::schedules::load_all(&pool).await? {
+        if !schedule.is_due(now) {
+            continue;
+        }
+        // Move the schedule on before running, so a slow run isn't started twice
+        let next = schedule.spec.next_run(now).map(|t| t.to_rfc3339());
+        db::schedules::mark_ran(&pool, &schedule.id, &now.to_rfc3339(), next.as_deref()).await?;
+
+        log::info!("[schedules] running '{}' ({})", schedule.name, schedule.id);
+        if let Err(e) = start_run(manager, host, schedule).await {
+            log::warn!("[schedules] failed to start run: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Log a new run of `schedule` and carry it out in the background.
+/// Returns the run as first logged, with outcome `running`.
+pub async fn start_run(
+    manager: &Arc<ProcessManager>,
+    host: &Host,
+    schedule: PromptSchedule,
+) -> Result<ScheduleRun, String> {
+    let pool = host.database().await?;
+    let run = ScheduleRun {
+        id: uuid::Uuid::new_v4().to_string(),
+        schedule_id: schedule.id.clone(),
+        session_id: schedule.session_id.clone(),
+        started_at: Utc::now().to_rfc3339(),
+        finished_at: None,
+        outcome: "running".to_string(),
+        is_error: false,
+        input_tokens: 0,
+        output_tokens: 0,
+        cost_usd: None,
+        diff: None,
+        error: None,
+    };
+    db::schedules::save_run(&pool, &run).await?;
+    emit_run(host, &run);
+
+    let manager = Arc::clone(manager);
+    let host = host.clone();
+    let started = run.clone();
+    tokio::spawn(async move {
+        let mut run = run;
+        execute(&manager, &host, &schedule, &mut run).await;
+        run.finished_at = Some(Utc::now().to_rfc3339());
+        if let Err(e) = db::schedules::save_run(&pool, &run).await {
+            log::warn!("[schedules] {}", e);
+        }
+        emit_run(&host, &run);
+    });
+    Ok(started)
+}
+
+/// Send the prompt, wait for its turn to end and record the outcome in `run`
+async fn execute(
+    manager: &Arc<ProcessManager>,
+    host: &Host,
+    schedule: &PromptSchedule,
+    run: &mut ScheduleRun,
+) {
+    let base = head_commit(&schedule.project_path).await;
+
+    match send_and_wait(manager, host, schedule, run).await {
+        Ok(outcome) => {
+            run.outcome = outcome.subtype;
+            run.is_error = outcome.is_error;
+            run.input_tokens = outcome.input_tokens as i64;
+            run.output_tokens = outcome.output_tokens as i64;
+            run.cost_usd = outcome.cost_usd;
+        }
+        Err(e) => {
+            log::warn!("[schedules] run of {} failed: {}", schedule.id, e);
+            run.outcome = "failed".to_string();
+            run.is_error = true;
+            run.error = Some(e);
+        }
+    }
+
+    if let Some(base) = base {
+        run.diff = diff_since(&schedule.project_path, &base).await;
+    }
+
+    // A session started just for this run is stopped again; it stays saved
+    // and can be resumed to review what happened
+    if schedule.session_id.is_none() {
+        if let Some(session_id) = &run.session_id {
+            if let Err(e) = manager.kill(session_id, host).await {
+                log::warn!("[schedules] failed to stop session {}: {}", session_id, e);
+            }
+        }
+    }
+}
+
+async fn send_and_wait(
+    manager: &Arc<ProcessManager>,
+    host: &Host,
+    schedule: &PromptSchedule,
+    run: &mut ScheduleRun,
+) -> Result<TurnOutcome, String> {
+    let session_id = match &schedule.session_id {
+        Some(session_id) => session_id.clone(),
+        None => {
+            let session_id = uuid::Uuid::new_v4().to_string();
+            let opts = SpawnOptions {
+                session_id: session_id.clone(),
+                project_path: schedule.project_path.clone(),
+                claude_cli_path: None,
+                resume_session_id: None,
+                launch: schedule.launch.clone(),
+            };
+            manager.spawn(opts, host.clone()).await?;
+            run.session_id = Some(session_id.clone());
+            emit_run(host, run);
+            session_id
+        }
+    };
+
+    // Subscribe before sending so the outcome can't be missed
+    let mut outcomes = manager.subscribe_turns();
+    let prompt = manager
+        .send_message(
+            &session_id,
+            &schedule.prompt,
+            &schedule.project_path,
+            host.clone(),
+        )
+        .await?;
+
+    loop {
+        match outcomes.recv().await {
+            Ok(outcome) if outcome.prompt_id == prompt.id => return Ok(outcome),
+            Ok(_) => {}
+            Err(RecvError::Lagged(missed)) => {
+                log::warn!("[schedules] missed {} turn outcomes", missed);
+            }
+            Err(RecvError::Closed) => return Err("Process manager shut down".to_string()),
+        }
+    }
+}
+
+/// The commit checked out in `path`, if it is a git repository
+async fn head_commit(path: &str) -> Option<String> {
+    let output = tokio::process::Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(path)
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+async fn diff_since(path: &str, base: &str) -> Option<RunDiff> {
+    let (path, base) = (path.to_string(), base.to_string());
+    let summary =
+        tokio::task::spawn_blocking(move || crate::git::diff::get_diff(&path, Some(&base)))
+            .await
+            .ok()?;
+    match summary {
+        Ok(summary) => Some(RunDiff {
+            files: summary.files,
+            total_additions: summary.total_additions,
+            total_deletions: summary.total_deletions,
+        }),
+        Err(e) => {
+            log::warn!("[schedules] failed to diff run: {}", e);
+            None
+        }
+    }
+}
+
+fn emit_run(host: &Host, run: &ScheduleRun) {
+    let payload = serde_json::to_value(run).unwrap_or_default();
+    let _ = host.emit(crate::events::SCHEDULE_RUN_UPDATED, payload);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::host::test_host;
+
+    fn schedule(id: &str, spec: ScheduleSpec, next_run_at: DateTime<Utc>) -> PromptSchedule {
+        PromptSchedule {
+            id: id.to_string(),
+            name: id.to_string(),
+            session_id: None,
+            project_path: std::env::temp_dir().to_string_lossy().into_owned(),
+            prompt: "run the tests".to_string(),
+            spec,
+            // No such provider, so a run fails without starting a CLI
+            launch: LaunchSettings {
+                provider: Some("missing".to_string()),
+                ..Default::default()
+            },
+            enabled: true,
+            next_run_at: Some(next_run_at.to_rfc3339()),
+            last_run_at: None,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Runs of a schedule once they have all finished
+    async fn finished_runs(pool: &sqlx::SqlitePool, schedule_id: &str) -> Vec<ScheduleRun> {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        loop {
+            let runs = db::schedules::load_runs(pool, schedule_id, 10)
+                .await
+                .unwrap();
+            if runs.iter().all(|r| r.finished_at.is_some()) {
+                return runs;
+            }
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "runs never finished"
+            );
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn due_schedules_run_once_and_move_on() {
+        let pool = db::test_pool().await;
+        let host = test_host(pool.clone());
+        let manager = Arc::new(ProcessManager::new());
+
+        let overdue = Utc::now() - chrono::Duration::minutes(5);
+        let later = Utc::now() + chrono::Duration::hours(1);
+        let cron = ScheduleSpec::Cron {
+            expr: "0 3 * * *".to_string(),
+        };
+        let once = ScheduleSpec::Once { at: overdue };
+        db::schedules::save(&pool, &schedule("cron", cron, overdue))
+            .await
+            .unwrap();
+        db::schedules::save(&pool, &schedule("once", once, overdue))
+            .await
+            .unwrap();
+        db::schedules::save(
+            &pool,
+            &schedule("later", ScheduleSpec::Once { at: later }, later),
+        )
+        .await
+        .unwrap();
+
+        run_due(&manager, &host).await.unwrap();
+        // Nothing is due any more
+        run_due(&manager, &host).await.unwrap();
+
+        let cron = db::schedules::load(&pool, "cron").await.unwrap().unwrap();
+        let next = cron.next_run_at.as_deref().unwrap();
+        assert!(DateTime::parse_from_rfc3339(next).unwrap() > Utc::now());
+        assert!(cron.last_run_at.is_some());
+        assert!(cron.enabled);
+
+        // A one-off is done after its run
+        let once = db::schedules::load(&pool, "once").await.unwrap().unwrap();
+        assert_eq!(once.next_run_at, None);
+        assert!(!once.enabled);
+
+        for id in ["cron", "once"] {
+            let runs = finished_runs(&pool, id).await;
+            assert_eq!(runs.len(), 1, "{} should run once", id);
+            assert_eq!(runs[0].schedule_id, id);
+            assert_eq!(runs[0].outcome, "failed");
+            assert!(runs[0].is_error);
+            assert!(runs[0]
+                .error
+                .as_deref()
+                .unwrap()
+                .contains("Unknown agent provider"));
+        }
+        assert!(finished_runs(&pool, "later").await.is_empty());
+    }
+
+    fn git(repo: &std::path::Path, args: &[&str]) {
+        let output = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(repo)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+    }
+
+    #[tokio::test]
+    async fn runs_are_diffed_against_their_starting_commit() {
+        let repo = std::env::temp_dir().join(format!("schedule-diff-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&repo).unwrap();
+        let path = repo.to_string_lossy().into_owned();
+        assert_eq!(head_commit(&path).await, None);
+
+        git(&repo, &["init", "-q"]);
+        std::fs::write(repo.join("notes.txt"), "one\n").unwrap();
+        git(&repo, &["add", "notes.txt"]);
+        git(&repo, &["commit", "-q", "-m", "start"]);
+
+        let base = head_commit(&path).await.expect("head commit");
+        std::fs::write(repo.join("notes.txt"), "one\ntwo\nthree\n").unwrap();
+
+        let diff = diff_since(&path, &base).await.expect("diff");
+        assert_eq!(diff.files.len(), 1);
+        assert_eq!(diff.total_additions, 2);
+        assert_eq!(diff.total_deletions, 0);
+
+        let _ = std::fs::remove_dir_all(&repo);
+    }
+}
@@ -0,0 +1,1221 @@
+//! Drives `ProcessManager` against the fake CLI (`examples/fake_claude.rs`),
+//! which replays the NDJSON fixtures in `tests/fixtures/`. No network or
+//! Claude account is needed.
+
+mod support;
+
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+use sqlx::SqlitePool;
+
+use openclaudgents_lib::claude::content::{self, ContentInput};
+use openclaudgents_lib::claude::env_profile::{EnvProfile, EnvProfileInput, EnvVar, MASKED_VALUE};
+use openclaudgents_lib::claude::host::{AgentHost, Host};
+use openclaudgents_lib::claude::launch::LaunchSettings;
+use openclaudgents_lib::claude::permissions::{
+    PermissionBehavior, PermissionResponse, PermissionSettings,
+};
+use openclaudgents_lib::claude::process::{ProcessManager, SpawnOptions};
+use openclaudgents_lib::claude::watchdog::{TimeoutAction, WatchdogSettings};
+use openclaudgents_lib::events;
+
+use support::fake_cli;
+use support::test_provider::TestProvider;
+
+const WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Host that records every emitted event
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<(String, Value)>>,
+}
+
+impl AgentHost for Recorder {
+    fn emit(&self, event: &str, payload: Value) -> Result<(), String> {
+        self.events
+            .lock()
+            .unwrap()
+            .push((event.to_string(), payload));
+        Ok(())
+    }
+
+    fn database(&self) -> Pin<Box<dyn Future<Output = Result<SqlitePool, String>> + Send + '_>> {
+        Box::pin(async { Err("no database in tests".to_string()) })
+    }
+}
+
+impl Recorder {
+    /// Payloads of one event type for one session, in emission order
+    fn payloads(&self, event: &str, session_id: &str) -> Vec<Value> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(name, payload)| name == event && payload["sessionId"] == session_id)
+            .map(|(_, payload)| payload.clone())
+            .collect()
+    }
+
+    fn statuses(&self, session_id: &str) -> Vec<String> {
+        self.payloads(events::CLAUDE_SESSION_STATUS, session_id)
+            .iter()
+            .filter_map(|p| p["status"].as_str().map(String::from))
+            .collect()
+    }
+}
+
+/// Temporary project directory holding the fake CLI's script and logs
+struct Project {
+    path: PathBuf,
+}
+
+impl Project {
+    /// Create a project whose script is the given fixtures, one after another
+    fn new(fixtures: &[&str]) -> Self {
+        let path = std::env::temp_dir().join(format!("fake-claude-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(path.join(".fake-claude")).unwrap();
+        let project = Self { path };
+        project.write_script("script.ndjson", fixtures);
+        project
+    }
+
+    fn write_script(&self, name: &str, fixtures: &[&str]) {
+        let script: String = fixtures.iter().map(|f| fixture(f)).collect();
+        fs::write(self.path.join(".fake-claude").join(name), script).unwrap();
+    }
+
+    fn path_str(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+
+    /// Entries the fake CLI appended to one of its logs
+    fn log(&self, name: &str) -> Vec<Value> {
+        fs::read_to_string(self.path.join(".fake-claude").join(name))
+            .unwrap_or_default()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    fn turns(&self) -> Vec<String> {
+        self.log("turns.ndjson")
+            .iter()
+            .map(|t| t["text"].as_str().unwrap().to_string())
+            .collect()
+    }
+}
+
+impl Drop for Project {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name);
+    fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+async fn setup(cli_path: &str) -> (Arc<ProcessManager>, Arc<Recorder>, Host) {
+    let manager = Arc::new(ProcessManager::new());
+    manager.cli().set_override(Some(cli_path.to_string())).await;
+    let recorder = Arc::new(Recorder::default());
+    let host: Host = recorder.clone();
+    (manager, recorder, host)
+}
+
+async fn spawn(manager: &Arc<ProcessManager>, host: &Host, session_id: &str, project: &Project) {
+    manager
+        .spawn(
+            SpawnOptions {
+                session_id: session_id.to_string(),
+                project_path: project.path_str(),
+                claude_cli_path: None,
+                resume_session_id: None,
+                launch: Default::default(),
+            },
+            host.clone(),
+        )
+        .await
+        .expect("spawn fake CLI");
+}
+
+async fn send(
+    manager: &Arc<ProcessManager>,
+    host: &Host,
+    session_id: &str,
+    project: &Project,
+    text: &str,
+) {
+    manager
+        .send_message(session_id, text, &project.path_str(), host.clone())
+        .await
+        .expect("send message");
+}
+
+/// Poll until `condition` holds, failing the test after `WAIT_TIMEOUT`
+async fn wait_until(what: &str, mut condition: impl FnMut() -> bool) {
+    let deadline = Instant::now() + WAIT_TIMEOUT;
+    while !condition() {
+        assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+/// Run one turn of session `s1` against a fixture script and wait for it to
+/// finish, returning the manager and everything it emitted
+async fn run_fixture_turn(fixture: &str, prompt: &str) -> (Arc<ProcessManager>, Arc<Recorder>) {
+    let project = Project::new(&[fixture]);
+    let (manager, recorder, host) = setup(fake_cli()).await;
+    spawn(&manager, &host, "s1", &project).await;
+    send(&manager, &host, "s1", &project, prompt).await;
+    wait_until("turn to finish", || {
+        recorder.statuses("s1").last().map(String::as_str) == Some("waiting_input")
+            && recorder.statuses("s1").contains(&"active".to_string())
+    })
+    .await;
+    (manager, recorder)
+}
+
+/// One string field of every `event` of session `s1`, concatenated
+fn joined(recorder: &Recorder, event: &str, field: &str) -> String {
+    recorder
+        .payloads(event, "s1")
+        .iter()
+        .map(|payload| payload[field].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn persistent_session_streams_a_turn() {
+    let project = Project::new(&["simple_turn.ndjson", "simple_turn.ndjson"]);
+    let (manager, recorder, host) = setup(fake_cli()).await;
+
+    spawn(&manager, &host, "s1", &project).await;
+    assert_eq!(recorder.statuses("s1"), vec!["waiting_input"]);
+
+    send(&manager, &host, "s1", &project, "Say hello").await;
+    wait_until("turn to finish", || {
+        recorder
+            .statuses("s1")
+            .ends_with(&["active".to_string(), "waiting_input".to_string()])
+    })
+    .await;
+
+    let text: String = recorder
+        .payloads("claude:text_delta", "s1")
+        .iter()
+        .map(|p| p["text"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(text, "Hello there");
+
+    let resolved = recorder.payloads(events::CLAUDE_SESSION_ID_RESOLVED, "s1");
+    assert_eq!(resolved.len(), 1);
+    assert_eq!(resolved[0]["claudeSessionId"], "fake-session");
+    assert_eq!(
+        manager.get_claude_session_id("s1").await.as_deref(),
+        Some("fake-session")
+    );
+
+    // The process stays alive and takes the next turn on the same stdin
+    send(&manager, &host, "s1", &project, "Again").await;
+    wait_until("second turn", || project.turns().len() == 2).await;
+    assert_eq!(project.log("invocations.ndjson").len(), 1);
+    assert_eq!(project.turns(), vec!["Say hello", "Again"]);
+
+    let args = &project.log("invocations.ndjson")[0]["args"];
+    let args: Vec<&str> = args
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a.as_str().unwrap())
+        .collect();
+    assert!(args
+        .windows(2)
+        .any(|w| w == ["--input-format", "stream-json"]));
+    assert!(args.contains(&"--include-partial-messages"));
+
+    manager.kill("s1", &host).await.unwrap();
+}
+
+#[tokio::test]
+async fn deltas_are_coalesced_in_order_and_raw_mirroring_is_optional() {
+    let project = Project::new(&["simple_turn.ndjson", "simple_turn.ndjson"]);
+    let (manager, recorder, host) = setup(fake_cli()).await;
+
+    spawn(&manager, &host, "s1", &project).await;
+    send(&manager, &host, "s1", &project, "Say hello").await;
+    wait_until("turn to finish", || {
+        recorder
+            .statuses("s1")
+            .ends_with(&["active".to_string(), "waiting_input".to_string()])
+    })
+    .await;
+
+    // Deltas come before the message completes and the turn ends
+    let names: Vec<String> = recorder
+        .events
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect();
+    let last_delta = names.iter().rposition(|n| n == events::CLAUDE_TEXT_DELTA);
+    let first_complete = names
+        .iter()
+        .position(|n| n == events::CLAUDE_MESSAGE_COMPLETE);
+    assert!(last_delta.unwrap() < first_complete.unwrap());
+    assert!(recorder.payloads(events::CLAUDE_STREAM_EVENT, "s1").is_empty());
+
+    let metrics = manager.event_metrics("s1").await;
+    assert_eq!(
+        metrics.received,
+        metrics.emitted + metrics.merged + metrics.dropped
+    );
+    // One raw event per stdout line, none mirrored
+    assert_eq!(metrics.dropped, 10);
+
+    manager.set_raw_event_mirroring("s1", true).await;
+    send(&manager, &host, "s1", &project, "Again").await;
+    wait_until("raw events", || {
+        recorder.payloads(events::CLAUDE_STREAM_EVENT, "s1").len() == 10
+    })
+    .await;
+    assert_eq!(manager.event_metrics("s1").await.dropped, 10);
+
+    let text: String = recorder
+        .payloads(events::CLAUDE_TEXT_DELTA, "s1")
+        .iter()
+        .map(|p| p["text"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(text, "Hello thereHello there");
+
+    manager.kill("s1", &host).await.unwrap();
+}
+
+#[tokio::test]
+async fn prompts_sent_during_a_turn_are_queued_in_order() {
+    let project = Project::new(&["simple_turn.ndjson"; 3]);
+    let (manager, recorder, host) = setup(fake_cli()).await;
+    spawn(&manager, &host, "s1", &project).await;
+
+    for text in ["one", "two", "three"] {
+        send(&manager, &host, "s1", &project, text).await;
+    }
+    wait_until("all queued turns", || project.turns().len() == 3).await;
+    assert_eq!(project.turns(), vec!["one", "two", "three"]);
+
+    let updates = recorder.payloads(events::CLAUDE_QUEUE_UPDATED, "s1");
+    assert!(updates.iter().any(|u| u["depth"].as_u64() >= Some(1)));
+    wait_until("queue to drain", || {
+        let updates = recorder.payloads(events::CLAUDE_QUEUE_UPDATED, "s1");
+        let last = updates.last().unwrap();
+        last["depth"] == 0 && last["busy"] == false
+    })
+    .await;
+
+    manager.kill("s1", &host).await.unwrap();
+}
+
+#[tokio::test]
+async fn crash_reports_exit_code_and_stderr() {
+    let project = Project::new(&["crash.ndjson"]);
+    let (manager, recorder, host) = setup(fake_cli()).await;
+    spawn(&manager, &host, "s1", &project).await;
+
+    send(&manager, &host, "s1", &project, "Do something").await;
+    wait_until("error status", || {
+        recorder.statuses("s1").contains(&"error".to_string())
+    })
+    .await;
+
+    let status = recorder
+        .payloads(events::CLAUDE_SESSION_STATUS, "s1")
+        .into_iter()
+        .find(|p| p["status"] == "error")
+        .unwrap();
+    assert_eq!(status["exitCode"], 3);
+    let tail: Vec<&str> = status["stderrTail"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| l.as_str().unwrap())
+        .collect();
+    assert_eq!(tail[0], "Error: connect ECONNREFUSED 127.0.0.1:443");
+
+    let stderr = recorder.payloads(events::CLAUDE_STDERR, "s1");
+    assert_eq!(stderr.len(), 2);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn legacy_cli_respawns_with_resume() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let project = Project::new(&["simple_turn.ndjson"]);
+    project.write_script("resume.ndjson", &["simple_turn.ndjson"]);
+
+    // An old CLI without stream-json input
+    let wrapper = project.path.join("claude-legacy");
+    fs::write(
+        &wrapper,
+        format!(
+            "#!/bin/sh\nFAKE_CLAUDE_LEGACY=1 exec '{}' \"$@\"\n",
+            fake_cli()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&wrapper, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let (manager, recorder, host) = setup(&wrapper.to_string_lossy()).await;
+    spawn(&manager, &host, "s1", &project).await;
+
+    send(&manager, &host, "s1", &project, "first").await;
+    send(&manager, &host, "s1", &project, "second").await;
+    wait_until("both turns", || project.turns().len() == 2).await;
+    wait_until("second process to exit", || {
+        recorder
+            .statuses("s1")
+            .iter()
+            .filter(|s| *s == "completed")
+            .count()
+            == 2
+    })
+    .await;
+
+    assert_eq!(project.turns(), vec!["first", "second"]);
+    let invocations = project.log("invocations.ndjson");
+    assert_eq!(invocations.len(), 2);
+    let first: Vec<&str> = invocations[0]["args"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a.as_str().unwrap())
+        .collect();
+    let second: Vec<&str> = invocations[1]["args"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a.as_str().unwrap())
+        .collect();
+    assert!(!first.contains(&"--input-format"));
+    assert!(!first.contains(&"--resume"));
+    assert!(second.windows(2).any(|w| w == ["--resume", "fake-session"]));
+}
+
+#[tokio::test]
+async fn interrupt_cancels_the_turn_and_drops_the_queue() {
+    let project = Project::new(&["slow_turn.ndjson", "simple_turn.ndjson"]);
+    let (manager, recorder, host) = setup(fake_cli()).await;
+    spawn(&manager, &host, "s1", &project).await;
+
+    send(&manager, &host, "s1", &project, "long task").await;
+    send(&manager, &host, "s1", &project, "follow-up").await;
+    wait_until("turn to start streaming", || {
+        !recorder.payloads("claude:text_delta", "s1").is_empty()
+    })
+    .await;
+
+    manager
+        .interrupt("s1", Some(Duration::from_millis(500)), &host)
+        .await
+        .unwrap();
+    wait_until("cancelled status", || {
+        recorder.statuses("s1").contains(&"cancelled".to_string())
+    })
+    .await;
+
+    assert!(manager.queued_prompts("s1").await.is_empty());
+    assert_eq!(project.turns(), vec!["long task"]);
+}
+
+#[tokio::test]
+async fn watchdog_reports_stalls_and_retries_timed_out_turns() {
+    let project = Project::new(&["slow_turn.ndjson"]);
+    project.write_script("resume.ndjson", &["simple_turn.ndjson"]);
+    let (manager, recorder, host) = setup(fake_cli()).await;
+    manager
+        .set_watchdog_settings(WatchdogSettings {
+            stall_after_secs: 1,
+            turn_timeout_secs: Some(3),
+            on_timeout: TimeoutAction::Retry,
+        })
+        .await;
+    spawn(&manager, &host, "s1", &project).await;
+
+    send(&manager, &host, "s1", &project, "long task").await;
+    wait_until("retried turn to finish", || {
+        recorder.statuses("s1").last().map(String::as_str) == Some("waiting_input")
+            && project.turns().len() == 2
+    })
+    .await;
+
+    let stalls = recorder.payloads(events::CLAUDE_SESSION_STALLED, "s1");
+    assert_eq!(stalls[0]["stalled"], true);
+    assert_eq!(stalls[0]["timedOut"], false);
+    let timeout = stalls.iter().find(|s| s["timedOut"] == true).unwrap();
+    assert_eq!(timeout["action"], "retry");
+    assert_eq!(timeout["attempt"], 1);
+
+    assert_eq!(project.turns(), vec!["long task", "long task"]);
+    let invocations = project.log("invocations.ndjson");
+    assert_eq!(invocations.len(), 2);
+    assert!(invocations[1]["args"].to_string().contains("fake-session"));
+    assert!(recorder.statuses("s1").contains(&"cancelled".to_string()));
+
+    manager.kill("s1", &host).await.unwrap();
+}
+
+#[tokio::test]
+async fn turn_outcomes_are_reported_per_prompt() {
+    let project = Project::new(&["simple_turn.ndjson", "slow_turn.ndjson"]);
+    let (manager, _recorder, host) = setup(fake_cli()).await;
+    let mut outcomes = manager.subscribe_turns();
+    spawn(&manager, &host, "s1", &project).await;
+
+    let quick = manager
+        .send_message("s1", "quick", &project.path_str(), host.clone())
+        .await
+        .unwrap();
+    let slow = manager
+        .send_message("s1", "slow", &project.path_str(), host.clone())
+        .await
+        .unwrap();
+    let dropped = manager
+        .send_message("s1", "never sent", &project.path_str(), host.clone())
+        .await
+        .unwrap();
+
+    let outcome = outcomes.recv().await.unwrap();
+    assert_eq!(outcome.prompt_id, quick.id);
+    assert_eq!(outcome.subtype, "success");
+    assert!(!outcome.is_error);
+    assert_eq!(outcome.output_tokens, 3);
+
+    wait_until("slow turn to start", || project.turns().len() == 2).await;
+    manager
+        .interrupt("s1", Some(Duration::from_millis(500)), &host)
+        .await
+        .unwrap();
+
+    let mut cancelled = Vec::new();
+    while cancelled.len() < 2 {
+        let outcome = tokio::time::timeout(WAIT_TIMEOUT, outcomes.recv())
+            .await
+            .expect("outcome of cancelled prompts")
+            .unwrap();
+        assert_eq!(outcome.subtype, "cancelled");
+        cancelled.push(outcome.prompt_id);
+    }
+    cancelled.sort();
+    let mut expected = vec![slow.id, dropped.id];
+    expected.sort();
+    assert_eq!(cancelled, expected);
+}
+
+#[tokio::test]
+async fn a_second_provider_runs_alongside_claude() {
+    let claude = Project::new(&["simple_turn.ndjson"]);
+    let agent = Project::new(&["test_agent_turn.ndjson"]);
+    let (manager, recorder, host) = setup(fake_cli()).await;
+    manager
+        .register_provider(Arc::new(TestProvider::new(fake_cli())))
+        .await;
+    let ids: Vec<String> = manager.providers().await.into_iter().map(|p| p.id).collect();
+    assert_eq!(ids, vec!["claude", "test"]);
+
+    let spawn_with = |session_id: &str, project: &Project, provider: &str| SpawnOptions {
+        session_id: session_id.to_string(),
+        project_path: project.path_str(),
+        claude_cli_path: None,
+        resume_session_id: None,
+        launch: LaunchSettings {
+            provider: Some(provider.to_string()),
+            ..Default::default()
+        },
+    };
+    let unknown = manager
+        .spawn(spawn_with("nope", &agent, "missing"), host.clone())
+        .await;
+    assert!(unknown.unwrap_err().contains("Unknown agent provider"));
+
+    let mut outcomes = manager.subscribe_turns();
+    spawn(&manager, &host, "c1", &claude).await;
+    manager
+        .spawn(spawn_with("a1", &agent, "test"), host.clone())
+        .await
+        .expect("spawn test agent");
+
+    send(&manager, &host, "c1", &claude, "Say hello").await;
+    let prompt = manager
+        .send_message("a1", "Say hi", &agent.path_str(), host.clone())
+        .await
+        .unwrap();
+
+    let mut agent_outcome = None;
+    while agent_outcome.is_none() {
+        let outcome = tokio::time::timeout(WAIT_TIMEOUT, outcomes.recv())
+            .await
+            .expect("turn outcome")
+            .unwrap();
+        if outcome.session_id == "a1" {
+            agent_outcome = Some(outcome);
+        }
+    }
+    let outcome = agent_outcome.unwrap();
+    assert_eq!(outcome.prompt_id, prompt.id);
+    assert_eq!(outcome.subtype, "success");
+    assert_eq!((outcome.input_tokens, outcome.output_tokens), (3, 5));
+
+    // Turns are encoded in the test dialect and output mapped to the same events
+    assert_eq!(agent.turns(), vec!["Say hi"]);
+    let args = &agent.log("invocations.ndjson")[0]["args"];
+    assert_eq!(args, &serde_json::json!(["--input-format", "ndjson"]));
+    let text: String = recorder
+        .payloads(events::CLAUDE_TEXT_DELTA, "a1")
+        .iter()
+        .map(|p| p["text"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(text, "Hi from the test agent");
+    let tools = recorder.payloads(events::CLAUDE_TOOL_START, "a1");
+    assert_eq!(tools.len(), 1);
+    assert_eq!(tools[0]["toolName"], "Read");
+    assert_eq!(
+        manager.get_claude_session_id("a1").await.as_deref(),
+        Some("fake-session")
+    );
+    assert_eq!(
+        manager.launch_settings("a1").await.provider.as_deref(),
+        Some("test")
+    );
+
+    wait_until("claude turn to finish", || {
+        recorder.statuses("c1").ends_with(&["waiting_input".to_string()])
+            && recorder.statuses("c1").len() > 2
+    })
+    .await;
+    let text: String = recorder
+        .payloads(events::CLAUDE_TEXT_DELTA, "c1")
+        .iter()
+        .map(|p| p["text"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(text, "Hello there");
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn environment_profiles_are_applied_at_spawn() {
+    let with_default = Project::new(&["simple_turn.ndjson"]);
+    let other = Project::new(&["simple_turn.ndjson"]);
+    let (manager, _recorder, host) = setup(fake_cli()).await;
+
+    let var = |key: &str, value: &str| EnvVar {
+        key: key.to_string(),
+        value: value.to_string(),
+        secret: false,
+    };
+    let input = |api_key: &str| EnvProfileInput {
+        name: "Local proxy".to_string(),
+        project_path: Some(with_default.path_str()),
+        vars: vec![
+            var("ANTHROPIC_BASE_URL", "http://127.0.0.1:8080"),
+            var("ANTHROPIC_API_KEY", api_key),
+            var("CLAUDE_CONFIG_DIR", "/tmp/claude-config"),
+        ],
+        path_prepend: vec!["/opt/proxy/bin".to_string()],
+    };
+    let now = "2026-01-01T00:00:00Z".to_string();
+    let profile = EnvProfile::from_input("p1".to_string(), input("sk-secret"), None, now.clone())
+        .unwrap();
+
+    // Key-like variables are secret and never leave the backend unmasked,
+    // and a masked value sent back keeps the stored one
+    let masked = profile.masked();
+    assert_eq!(masked.vars[1].value, MASKED_VALUE);
+    assert_eq!(masked.vars[0].value, "http://127.0.0.1:8080");
+    let resaved = EnvProfile::from_input("p1".to_string(), input(MASKED_VALUE), Some(&profile), now)
+        .unwrap();
+    assert_eq!(resaved.vars[1].value, "sk-secret");
+
+    manager.set_env_profiles(vec![resaved]).await;
+
+    // The project's default applies without being named
+    spawn(&manager, &host, "s1", &with_default).await;
+    wait_until("invocation", || !with_default.log("invocations.ndjson").is_empty()).await;
+    let env = &with_default.log("invocations.ndjson")[0]["env"];
+    assert_eq!(env["ANTHROPIC_BASE_URL"], "http://127.0.0.1:8080");
+    assert_eq!(env["ANTHROPIC_API_KEY"], "sk-secret");
+    assert_eq!(env["CLAUDE_CONFIG_DIR"], "/tmp/claude-config");
+    assert!(env["PATH"].as_str().unwrap().starts_with("/opt/proxy/bin:"));
+
+    // Other projects inherit the app's environment as is, and naming a
+    // missing profile fails the spawn
+    spawn(&manager, &host, "s2", &other).await;
+    wait_until("invocation", || !other.log("invocations.ndjson").is_empty()).await;
+    let env = &other.log("invocations.ndjson")[0]["env"];
+    assert_eq!(
+        env["ANTHROPIC_BASE_URL"].as_str(),
+        std::env::var("ANTHROPIC_BASE_URL").ok().as_deref()
+    );
+    let missing = manager
+        .spawn(
+            SpawnOptions {
+                session_id: "s3".to_string(),
+                project_path: other.path_str(),
+                claude_cli_path: None,
+                resume_session_id: None,
+                launch: LaunchSettings {
+                    env_profile: Some("gone".to_string()),
+                    ..Default::default()
+                },
+            },
+            host.clone(),
+        )
+        .await;
+    assert!(missing.unwrap_err().contains("not found"));
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn attachments_are_sent_as_content_blocks() {
+    let project = Project::new(&["simple_turn.ndjson"]);
+    let (manager, recorder, host) = setup(fake_cli()).await;
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    fs::write(project.path.join("screenshot.png"), png).unwrap();
+    fs::write(project.path.join("notes.txt"), "Build fails on CI").unwrap();
+    fs::write(project.path.join("fake.png"), "not an image").unwrap();
+
+    let inputs = vec![
+        ContentInput::ImageFile {
+            path: "screenshot.png".to_string(),
+        },
+        ContentInput::ImageData {
+            media_type: "image/gif".to_string(),
+            data: "data:image/gif;base64,R0lGODlhAQABAAAAACw=".to_string(),
+        },
+        ContentInput::DocumentFile {
+            path: "notes.txt".to_string(),
+        },
+        ContentInput::Text {
+            text: "What is wrong here?".to_string(),
+        },
+    ];
+    let blocks = content::resolve(inputs, &project.path_str()).await.unwrap();
+
+    // Formats come from the bytes, not the file name
+    let bad = vec![ContentInput::ImageFile {
+        path: "fake.png".to_string(),
+    }];
+    let err = content::resolve(bad, &project.path_str()).await.unwrap_err();
+    assert!(err.contains("not a supported image"), "{}", err);
+    let empty = vec![ContentInput::Text {
+        text: "  ".to_string(),
+    }];
+    assert!(content::resolve(empty, &project.path_str()).await.is_err());
+
+    spawn(&manager, &host, "s1", &project).await;
+    let prompt = manager
+        .send_content("s1", blocks, &project.path_str(), host.clone())
+        .await
+        .unwrap();
+    assert_eq!(prompt.text, "What is wrong here?");
+    assert_eq!(prompt.attachments.len(), 3);
+    // Queue updates carry labels, not the attachment data
+    let update = &recorder.payloads(events::CLAUDE_QUEUE_UPDATED, "s1")[0];
+    assert!(update["prompts"][0].get("content").is_none());
+
+    wait_until("turn to be received", || project.turns().len() == 1).await;
+    let turn = &project.log("turns.ndjson")[0];
+    let content = turn["content"].as_array().unwrap();
+    let types: Vec<&str> = content.iter().map(|b| b["type"].as_str().unwrap()).collect();
+    assert_eq!(types, vec!["image", "image", "document", "text"]);
+    assert_eq!(content[0]["source"]["media_type"], "image/png");
+    assert_eq!(content[0]["source"]["type"], "base64");
+    assert_eq!(content[1]["source"]["media_type"], "image/gif");
+    assert_eq!(content[1]["source"]["data"], "R0lGODlhAQABAAAAACw=");
+    assert_eq!(content[2]["source"]["type"], "text");
+    assert_eq!(content[2]["source"]["data"], "Build fails on CI");
+    assert_eq!(content[2]["title"], "notes.txt");
+    assert_eq!(turn["text"], "What is wrong here?");
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn scheduler_holds_turns_over_the_limit() {
+    let slow = Project::new(&["slow_turn.ndjson"]);
+    let quick = Project::new(&["simple_turn.ndjson"]);
+    let (manager, recorder, host) = setup(fake_cli()).await;
+    manager.set_max_concurrent_turns(1, &host).await;
+    spawn(&manager, &host, "slow", &slow).await;
+    spawn(&manager, &host, "quick", &quick).await;
+
+    send(&manager, &host, "slow", &slow, "long task").await;
+    send(&manager, &host, "quick", &quick, "short task").await;
+
+    wait_until("quick session to wait for a slot", || {
+        recorder
+            .payloads(events::CLAUDE_SCHEDULE_UPDATED, "quick")
+            .iter()
+            .any(|p| p["position"] == 0)
+    })
+    .await;
+    assert!(quick.turns().is_empty());
+
+    // Cancelling the running turn hands its slot to the waiting session
+    manager
+        .interrupt("slow", Some(Duration::from_millis(500)), &host)
+        .await
+        .unwrap();
+    wait_until("quick turn to run", || quick.turns().len() == 1).await;
+    wait_until("quick turn to finish", || {
+        recorder
+            .statuses("quick")
+            .ends_with(&["waiting_input".to_string()])
+            && recorder.statuses("quick").contains(&"active".to_string())
+    })
+    .await;
+
+    manager.kill("quick", &host).await.unwrap();
+}
+
+#[tokio::test]
+async fn tool_calls_wait_for_the_users_approval() {
+    let project = Project::new(&["permission_prompts.ndjson"]);
+    let (manager, recorder, host) = setup(fake_cli()).await;
+    let permissions = manager.permissions();
+    // The fake CLI doubles as the permission-prompt MCP server
+    permissions.set_server_command(Some(fake_cli().to_string())).await;
+    permissions
+        .set_settings(PermissionSettings {
+            enabled: true,
+            timeout_secs: 5,
+            on_timeout: PermissionBehavior::Deny,
+        })
+        .await;
+    manager
+        .set_watchdog_settings(WatchdogSettings {
+            stall_after_secs: 1,
+            ..Default::default()
+        })
+        .await;
+    spawn(&manager, &host, "s1", &project).await;
+    send(&manager, &host, "s1", &project, "Tidy up").await;
+
+    let request = |n: usize| {
+        let recorder = Arc::clone(&recorder);
+        async move {
+            wait_until("permission request", || {
+                recorder
+                    .payloads(events::CLAUDE_PERMISSION_REQUEST, "s1")
+                    .len()
+                    > n
+            })
+            .await;
+            recorder.payloads(events::CLAUDE_PERMISSION_REQUEST, "s1")[n].clone()
+        }
+    };
+
+    // Edit the command and always allow Bash; waiting isn't reported as a stall
+    let bash = request(0).await;
+    assert_eq!(bash["toolName"], "Bash");
+    assert_eq!(bash["input"]["command"], "ls");
+    assert_eq!(bash["toolUseId"], "toolu_01Ls");
+    assert_eq!(permissions.pending().await.len(), 1);
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let allow = PermissionResponse::Allow {
+        updated_input: Some(serde_json::json!({ "command": "ls -la" })),
+        always: true,
+    };
+    let rule = permissions
+        .respond(bash["requestId"].as_str().unwrap(), allow, &host)
+        .await
+        .unwrap()
+        .expect("always-allow rule");
+    assert_eq!(rule.tool_name, "Bash");
+    assert_eq!(rule.project_path, project.path_str());
+
+    // The second Bash call is covered by the rule; Write is denied
+    let write = request(1).await;
+    assert_eq!(write["toolName"], "Write");
+    permissions
+        .set_settings(PermissionSettings {
+            enabled: true,
+            timeout_secs: 1,
+            on_timeout: PermissionBehavior::Deny,
+        })
+        .await;
+    let deny = PermissionResponse::Deny {
+        message: Some("Not that file".to_string()),
+    };
+    permissions
+        .respond(write["requestId"].as_str().unwrap(), deny, &host)
+        .await
+        .unwrap();
+
+    // Nobody answers the Edit, so the timeout default applies
+    wait_until("turn to finish", || {
+        recorder.statuses("s1").last().map(String::as_str) == Some("waiting_input")
+            && project.log("permissions.ndjson").len() == 4
+    })
+    .await;
+
+    let decisions: Vec<Value> = project
+        .log("permissions.ndjson")
+        .iter()
+        .map(|d| d["decision"].clone())
+        .collect();
+    assert_eq!(
+        decisions[0],
+        serde_json::json!({ "behavior": "allow", "updatedInput": { "command": "ls -la" } })
+    );
+    assert_eq!(
+        decisions[1],
+        serde_json::json!({ "behavior": "allow", "updatedInput": { "command": "pwd" } })
+    );
+    assert_eq!(
+        decisions[2],
+        serde_json::json!({ "behavior": "deny", "message": "Not that file" })
+    );
+    assert_eq!(decisions[3]["behavior"], "deny");
+
+    let resolutions: Vec<(Value, Value)> = recorder
+        .payloads(events::CLAUDE_PERMISSION_RESOLVED, "s1")
+        .iter()
+        .map(|r| (r["behavior"].clone(), r["resolution"].clone()))
+        .collect();
+    assert_eq!(
+        resolutions,
+        vec![
+            ("allow".into(), "user".into()),
+            ("deny".into(), "user".into()),
+            ("deny".into(), "timeout".into()),
+        ]
+    );
+    let requests = recorder.payloads(events::CLAUDE_PERMISSION_REQUEST, "s1");
+    assert_eq!(requests.len(), 3);
+    assert!(recorder
+        .payloads(events::CLAUDE_SESSION_STALLED, "s1")
+        .is_empty());
+    assert!(permissions.pending().await.is_empty());
+
+    // The broker token stays out of argv, in a config file only we can read
+    let args = &project.log("invocations.ndjson")[0]["args"];
+    assert!(args.to_string().contains("mcp__openclaudgents__approve"));
+    assert!(!args.to_string().contains("OPENCLAUDGENTS_PERMISSION_TOKEN"));
+    let flags: Vec<&str> = args
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a.as_str().unwrap())
+        .collect();
+    let at = flags.iter().position(|a| *a == "--mcp-config").unwrap();
+    let config = PathBuf::from(flags[at + 1]);
+    assert!(fs::read_to_string(&config)
+        .unwrap()
+        .contains("OPENCLAUDGENTS_PERMISSION_TOKEN"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&config).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    manager.kill("s1", &host).await.unwrap();
+    assert!(!config.exists(), "the config goes with the process");
+}
+
+#[tokio::test]
+async fn hook_runs_are_reported_with_their_decisions() {
+    let (manager, recorder) = run_fixture_turn("hook_turn.ndjson", "Clean up").await;
+
+    let hooks = recorder.payloads(events::CLAUDE_HOOK_EVENT, "s1");
+    let phases: Vec<(&str, &str)> = hooks
+        .iter()
+        .map(|h| (h["hookName"].as_str().unwrap(), h["phase"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        phases,
+        vec![
+            ("PreToolUse:Bash", "started"),
+            ("PreToolUse:Bash", "finished"),
+            ("PostToolUse:Edit", "started"),
+            ("PostToolUse:Edit", "finished"),
+            ("Stop", "finished"),
+        ]
+    );
+
+    // Exit status 2 blocks, with stderr as the reason
+    let bash = &hooks[1];
+    assert_eq!(bash["hookEvent"], "PreToolUse");
+    assert_eq!(bash["toolName"], "Bash");
+    assert_eq!(bash["exitCode"], 2);
+    assert_eq!(bash["decision"]["decision"], "block");
+    assert_eq!(bash["decision"]["reason"], "rm -rf is not allowed in this repo");
+
+    // A decision in the hook's JSON output
+    let edit = &hooks[3];
+    assert_eq!(edit["toolName"], "Edit");
+    assert_eq!(edit["decision"]["decision"], "block");
+    assert_eq!(edit["decision"]["reason"], "Lint failed");
+
+    let stop = &hooks[4];
+    assert_eq!(stop["toolName"], Value::Null);
+    assert_eq!(stop["stdout"], "formatted 2 files\n");
+    assert_eq!(stop["decision"], Value::Null);
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn content_blocks_are_assembled_from_their_deltas() {
+    let (manager, recorder) = run_fixture_turn("block_turn.ndjson", "Fix the port").await;
+
+    // Deltas of one tool call merge; interleaved calls stay apart
+    let inputs: Vec<(String, String)> = recorder
+        .payloads(events::CLAUDE_TOOL_INPUT_DELTA, "s1")
+        .iter()
+        .map(|d| {
+            (
+                d["toolId"].as_str().unwrap().to_string(),
+                d["partialJson"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    let input_of = |id: &str| -> String {
+        inputs
+            .iter()
+            .filter(|(of, _)| of == id)
+            .map(|(_, json)| json.as_str())
+            .collect()
+    };
+    assert_eq!(
+        input_of("toolu_01Read"),
+        r#"{"file_path": "/tmp/project/config.toml"}"#
+    );
+    assert_eq!(
+        input_of("toolu_02Task"),
+        r#"{"description": "Find port users", "prompt": "grep 8080"}"#
+    );
+    assert_eq!(input_of("toolu_03Grep"), r#"{"pattern": "8080"}"#);
+
+    let blocks = recorder.payloads(events::CLAUDE_CONTENT_BLOCK_COMPLETE, "s1");
+    let kinds: Vec<(u64, &str, &Value)> = blocks
+        .iter()
+        .map(|b| {
+            (
+                b["index"].as_u64().unwrap(),
+                b["block"]["type"].as_str().unwrap(),
+                &b["parentToolUseId"],
+            )
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (0, "thinking", &Value::Null),
+            (1, "text", &Value::Null),
+            (2, "tool_use", &Value::Null),
+            (0, "tool_use", &Value::from("toolu_02Task")),
+            (3, "tool_use", &Value::Null),
+            (4, "tool_use", &Value::Null),
+        ]
+    );
+
+    assert_eq!(
+        blocks[0]["block"]["thinking"],
+        "Read the config, then fix the port."
+    );
+    assert_eq!(blocks[0]["block"]["signature"], "EqQBCkgIBxAB");
+    assert_eq!(blocks[1]["block"]["text"], "Let me look at the config.");
+    assert_eq!(blocks[2]["block"]["id"], "toolu_01Read");
+    assert_eq!(
+        blocks[2]["block"]["input"],
+        serde_json::json!({ "file_path": "/tmp/project/config.toml" })
+    );
+    assert_eq!(
+        blocks[3]["block"]["input"],
+        serde_json::json!({ "pattern": "8080" })
+    );
+    assert_eq!(blocks[4]["block"]["input"]["prompt"], "grep 8080");
+    // Input cut off mid-JSON is passed on as it was streamed
+    assert_eq!(
+        blocks[5]["block"]["input"],
+        serde_json::json!({ "raw": r#"{"file_path": "config.toml", "old_str"# })
+    );
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn thinking_is_streamed_with_its_duration() {
+    let (manager, recorder) =
+        run_fixture_turn("thinking_turn.ndjson", "Which primes sum to 10?").await;
+
+    let names: Vec<String> = recorder
+        .events
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, _)| name.clone())
+        .filter(|name| name.starts_with("claude:thinking") || name == events::CLAUDE_TEXT_DELTA)
+        .collect();
+    // The reasoning is bracketed by its markers, ahead of the answer
+    assert_eq!(names[0], events::CLAUDE_THINKING_START);
+    assert_eq!(names[1], events::CLAUDE_THINKING_DELTA);
+    assert_eq!(names.last().unwrap(), events::CLAUDE_TEXT_DELTA);
+
+    assert_eq!(
+        joined(&recorder, events::CLAUDE_THINKING_DELTA, "thinking"),
+        "Two primes that sum to 10: 3 and 7."
+    );
+    // Thinking isn't mixed into the answer
+    assert_eq!(
+        joined(&recorder, events::CLAUDE_TEXT_DELTA, "text"),
+        "3 and 7."
+    );
+
+    let starts = recorder.payloads(events::CLAUDE_THINKING_START, "s1");
+    let redacted: Vec<&Value> = starts.iter().map(|s| &s["redacted"]).collect();
+    assert_eq!(redacted, vec![false, true]);
+
+    let stops = recorder.payloads(events::CLAUDE_THINKING_STOP, "s1");
+    assert_eq!(stops.len(), 2);
+    assert_eq!(stops[0]["index"], 0);
+    assert_eq!(stops[0]["thinking"], "Two primes that sum to 10: 3 and 7.");
+    assert!(stops[0]["durationMs"].as_u64().unwrap() >= 150);
+    assert_eq!(stops[1]["redacted"], true);
+    assert_eq!(stops[1]["thinking"], Value::Null);
+    assert!(stops[1]["durationMs"].is_u64());
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn tool_results_complete_their_calls() {
+    let (manager, recorder) = run_fixture_turn("tool_results_turn.ndjson", "Build it").await;
+
+    let done = recorder.payloads(events::CLAUDE_TOOL_COMPLETE, "s1");
+    let ids: Vec<&str> = done.iter().map(|d| d["toolId"].as_str().unwrap()).collect();
+    assert_eq!(
+        ids,
+        vec![
+            "toolu_01Read",
+            "toolu_02Bash",
+            "toolu_03Grep",
+            "toolu_99Gone"
+        ]
+    );
+
+    let read = &done[0];
+    assert_eq!(read["toolName"], "Read");
+    assert_eq!(read["isError"], false);
+    assert!(read["durationMs"].as_u64().unwrap() >= 120);
+    assert_eq!(read["truncated"], true);
+    let preview = read["preview"].as_str().unwrap();
+    assert_eq!(preview.chars().count(), 2000);
+    assert!(preview.starts_with("   1\tfn step_1() -> u32 { 1 }\n"));
+
+    let bash = &done[1];
+    assert_eq!(bash["toolName"], "Bash");
+    assert_eq!(bash["isError"], true);
+    assert_eq!(bash["truncated"], false);
+    assert_eq!(
+        bash["preview"],
+        "error[E0425]: cannot find value `x`\n[image]"
+    );
+
+    // A subagent's call, seen only in its complete message
+    let grep = &done[2];
+    assert_eq!(grep["toolName"], "Grep");
+    assert_eq!(grep["parentToolUseId"], "toolu_00Task");
+    assert_eq!(grep["preview"], "src/steps.rs:7");
+    assert!(grep["durationMs"].is_u64());
+
+    // A result for a call the parser never saw still finishes it
+    let gone = &done[3];
+    assert_eq!(gone["toolName"], Value::Null);
+    assert_eq!(gone["durationMs"], Value::Null);
+    assert_eq!(gone["preview"], "late");
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn turns_are_summarised_from_their_result() {
+    let (manager, recorder) = run_fixture_turn("denied_turn.ndjson", "Edit the hosts file").await;
+
+    let summaries = recorder.payloads(events::CLAUDE_TURN_SUMMARY, "s1");
+    assert_eq!(summaries.len(), 1);
+    let summary = &summaries[0];
+    assert!(summary["promptId"].is_string());
+    assert_eq!(summary["subtype"], "success");
+    assert_eq!(summary["isError"], false);
+    assert_eq!(summary["durationMs"], 6120);
+    assert_eq!(summary["durationApiMs"], 4870);
+    assert_eq!(summary["numTurns"], 2);
+    assert_eq!(summary["costUsd"], 0.01234);
+    assert_eq!(summary["inputTokens"], 20);
+    assert_eq!(summary["outputTokens"], 54);
+    assert_eq!(summary["cacheReadTokens"], 1800);
+    assert_eq!(summary["cacheCreationTokens"], 1800);
+    assert_eq!(summary["result"], "I wasn't allowed to edit /etc/hosts.");
+    assert_eq!(
+        summary["permissionDenials"],
+        serde_json::json!([{
+            "toolName": "Write",
+            "toolUseId": "toolu_01Write",
+            "toolInput": {"file_path": "/etc/hosts", "content": ""},
+        }])
+    );
+
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn each_message_is_counted_once_for_its_model() {
+    let (manager, recorder) = run_fixture_turn("usage_turn.ndjson", "Who calls step_7?").await;
+
+    let counted: Vec<(String, String, u64, u64, u64, u64)> = recorder
+        .payloads(events::CLAUDE_USAGE_UPDATE, "s1")
+        .iter()
+        .map(|update| {
+            let usage = &update["usage"];
+            (
+                update["messageId"].as_str().unwrap().to_string(),
+                update["model"].as_str().unwrap().to_string(),
+                usage["inputTokens"].as_u64().unwrap(),
+                usage["outputTokens"].as_u64().unwrap(),
+                usage["cacheReadInputTokens"].as_u64().unwrap(),
+                usage["cacheCreationInputTokens"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    let sonnet = "claude-sonnet-4-5-20250929".to_string();
+    let haiku = "claude-haiku-4-5-20251001".to_string();
+    assert_eq!(
+        counted,
+        vec![
+            // Streamed, with its final output count from message_delta
+            ("msg_01Usage".to_string(), sonnet.clone(), 40, 85, 0, 1200),
+            // A subagent's messages, on the subagent's model
+            ("msg_02Sub".to_string(), haiku.clone(), 9, 20, 0, 0),
+            ("msg_03Sub".to_string(), haiku, 12, 8, 0, 0),
+            // Split over two assistant lines
+            ("msg_04Usage".to_string(), sonnet, 6, 30, 1240, 0),
+        ]
+    );
+
+    manager.shutdown().await;
+}
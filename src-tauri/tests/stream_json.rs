@@ -0,0 +1,210 @@
+//! Checks the `ClaudeStreamEvent` model against every line the CLI fixtures
+//! in `tests/fixtures/` record, including the standalone corpus of shapes the
+//! fake-CLI scripts don't use.
+
+use std::fs;
+use std::path::PathBuf;
+
+use openclaudgents_lib::claude::types::{
+    ApiContentBlock, ApiMessage, ApiStreamEvent, ClaudeStreamEvent, ContentDelta, MessageContent,
+    SystemEvent,
+};
+
+/// Fixtures in another agent's dialect
+const OTHER_DIALECTS: &[&str] = &["test_agent_turn.ndjson"];
+
+/// Every stream-json line of the fixtures, with where it came from
+fn recorded_lines() -> Vec<(String, String)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures");
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+        .expect("read fixtures")
+        .map(|entry| entry.expect("fixture entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ndjson"))
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            !OTHER_DIALECTS.contains(&name.as_ref())
+        })
+        .collect();
+    files.sort();
+
+    let mut lines = Vec::new();
+    for path in files {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let text = fs::read_to_string(&path).expect("read fixture");
+        for (number, line) in text.lines().enumerate() {
+            // Directives for the fake CLI aren't CLI output
+            if line.trim().is_empty() || line.starts_with("{\"fake\"") {
+                continue;
+            }
+            lines.push((format!("{}:{}", name, number + 1), line.to_string()));
+        }
+    }
+    lines
+}
+
+/// Paths to the `Unknown` variants within an event, empty if fully modelled
+fn unknowns(event: &ClaudeStreamEvent) -> Vec<&'static str> {
+    let mut found = Vec::new();
+    match event {
+        ClaudeStreamEvent::System(SystemEvent::Unknown) => found.push("system"),
+        ClaudeStreamEvent::System(_) => {}
+        ClaudeStreamEvent::StreamEvent(line) => match &line.event {
+            ApiStreamEvent::MessageStart { message } => message_unknowns(message, &mut found),
+            ApiStreamEvent::ContentBlockStart { content_block, .. } => {
+                if matches!(content_block, ApiContentBlock::Unknown) {
+                    found.push("content_block_start.content_block");
+                }
+            }
+            ApiStreamEvent::ContentBlockDelta { delta, .. } => {
+                if matches!(delta, ContentDelta::Unknown) {
+                    found.push("content_block_delta.delta");
+                }
+            }
+            ApiStreamEvent::Unknown => found.push("stream_event.event"),
+            _ => {}
+        },
+        ClaudeStreamEvent::Assistant(line) | ClaudeStreamEvent::User(line) => {
+            message_unknowns(&line.message, &mut found)
+        }
+        ClaudeStreamEvent::Result(_) => {}
+        ClaudeStreamEvent::Unknown => found.push("type"),
+    }
+    found
+}
+
+fn message_unknowns(message: &ApiMessage, found: &mut Vec<&'static str>) {
+    if let MessageContent::Blocks(blocks) = &message.content {
+        if blocks.iter().any(|b| matches!(b, ApiContentBlock::Unknown)) {
+            found.push("message.content");
+        }
+    }
+}
+
+fn parse(line: &str) -> ClaudeStreamEvent {
+    serde_json::from_str(line).unwrap_or_else(|e| panic!("{}: {}", e, line))
+}
+
+#[test]
+fn every_recorded_line_is_modelled() {
+    let lines = recorded_lines();
+    assert!(
+        lines
+            .iter()
+            .any(|(at, _)| at.starts_with("stream_json_corpus")),
+        "the corpus fixture is missing"
+    );
+
+    for (at, line) in &lines {
+        let event: ClaudeStreamEvent = serde_json::from_str(line)
+            .unwrap_or_else(|e| panic!("{} doesn't deserialize: {}", at, e));
+        assert!(
+            unknowns(&event).is_empty(),
+            "{} has unmodelled parts: {:?}",
+            at,
+            unknowns(&event)
+        );
+    }
+}
+
+#[test]
+fn corpus_lines_read_into_their_fields() {
+    let lines = recorded_lines();
+    let corpus: Vec<ClaudeStreamEvent> = lines
+        .iter()
+        .filter(|(at, _)| at.starts_with("stream_json_corpus"))
+        .map(|(_, line)| parse(line))
+        .collect();
+
+    let ClaudeStreamEvent::System(SystemEvent::Init(init)) = &corpus[0] else {
+        panic!("corpus should start with init: {:?}", corpus[0]);
+    };
+    assert_eq!(init.permission_mode.as_deref(), Some("acceptEdits"));
+    assert_eq!(init.mcp_servers[1].status, "failed");
+    assert_eq!(corpus[0].session_id(), Some(init.session_id.as_str()));
+
+    let tool_input: String = corpus
+        .iter()
+        .filter_map(|event| match event {
+            ClaudeStreamEvent::StreamEvent(line) => match &line.event {
+                ApiStreamEvent::ContentBlockDelta {
+                    index: 3,
+                    delta: ContentDelta::InputJsonDelta { partial_json },
+                } => Some(partial_json.as_str()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&tool_input).unwrap()["command"],
+        "cargo test"
+    );
+
+    let subagent_call = corpus.iter().find_map(|event| match event {
+        ClaudeStreamEvent::Assistant(line) => line.parent_tool_use_id.as_deref(),
+        _ => None,
+    });
+    assert_eq!(subagent_call, Some("toolu_02Task"));
+
+    let failed_tool = corpus.iter().find_map(|event| match event {
+        ClaudeStreamEvent::User(line) => match &line.message.content {
+            MessageContent::Blocks(blocks) => blocks.iter().find_map(|block| match block {
+                ApiContentBlock::ToolResult {
+                    tool_use_id,
+                    is_error: Some(true),
+                    ..
+                } => Some(tool_use_id.as_str()),
+                _ => None,
+            }),
+            MessageContent::Text(_) => None,
+        },
+        _ => None,
+    });
+    assert_eq!(failed_tool, Some("toolu_01Bash"));
+
+    let compactions = corpus
+        .iter()
+        .filter(|event| {
+            matches!(
+                event,
+                ClaudeStreamEvent::System(SystemEvent::CompactBoundary(_))
+            )
+        })
+        .count();
+    assert_eq!(compactions, 2, "compact_boundary and its older name");
+
+    let ClaudeStreamEvent::Result(result) = &corpus[corpus.len() - 2] else {
+        panic!("expected a result: {:?}", corpus[corpus.len() - 2]);
+    };
+    assert_eq!(result.subtype, "error_max_turns");
+    assert!(result.is_error);
+    assert_eq!(result.permission_denials.len(), 1);
+    assert_eq!(result.usage.as_ref().unwrap().output_tokens, Some(1840));
+}
+
+#[test]
+fn events_from_newer_clis_still_parse() {
+    assert!(matches!(
+        parse(r#"{"type":"rate_limit","resets_at":1760000000}"#),
+        ClaudeStreamEvent::Unknown
+    ));
+    assert!(matches!(
+        parse(r#"{"type":"system","subtype":"plugin_loaded","name":"x"}"#),
+        ClaudeStreamEvent::System(SystemEvent::Unknown)
+    ));
+
+    let delta = parse(
+        r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,
+            "delta":{"type":"audio_delta","audio":"AAAA"}},"session_id":"s"}"#,
+    );
+    assert_eq!(unknowns(&delta), vec!["content_block_delta.delta"]);
+    assert_eq!(delta.session_id(), Some("s"));
+
+    let message = parse(
+        r#"{"type":"assistant","message":{"role":"assistant",
+            "content":[{"type":"container_upload","file_id":"f"}]}}"#,
+    );
+    assert_eq!(unknowns(&message), vec!["message.content"]);
+}
@@ -0,0 +1,45 @@
+//! Code shared by the integration tests
+
+pub mod test_provider;
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Path of the fake CLI (`examples/fake_claude.rs`).
+///
+/// It is built on first use, so it is current even when only some test
+/// targets were built (`cargo test --test orchestration` skips examples).
+pub fn fake_cli() -> &'static str {
+    static PATH: OnceLock<String> = OnceLock::new();
+    PATH.get_or_init(|| {
+        // Test binaries run from `target/<profile>/deps/`, next to `examples/`
+        let profile_dir: PathBuf = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .and_then(|deps| deps.parent())
+            .expect("test binary is in target/<profile>/deps")
+            .to_path_buf();
+        let profile = match profile_dir.file_name().and_then(|n| n.to_str()) {
+            Some("debug") | None => "dev",
+            Some(profile) => profile,
+        };
+
+        let target_dir = profile_dir.parent().expect("target directory");
+
+        let status = Command::new(env!("CARGO"))
+            .args(["build", "--example", "fake-claude", "--profile", profile])
+            .arg("--target-dir")
+            .arg(target_dir)
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .status()
+            .expect("run cargo");
+        assert!(status.success(), "failed to build the fake CLI");
+
+        profile_dir
+            .join("examples")
+            .join(format!("fake-claude{}", std::env::consts::EXE_SUFFIX))
+            .to_string_lossy()
+            .into_owned()
+    })
+}
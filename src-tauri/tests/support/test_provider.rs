@@ -0,0 +1,134 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use serde_json::Value;
+use tokio::process::Command;
+
+use openclaudgents_lib::claude::content::{self, ContentBlock};
+use openclaudgents_lib::claude::emitter::OutboundEvent;
+use openclaudgents_lib::claude::permission_mcp::PermissionServer;
+use openclaudgents_lib::claude::process::{SessionMode, SpawnOptions, TurnOutcome};
+use openclaudgents_lib::claude::provider::{AgentCommand, AgentProvider, LineParser, ParsedLine};
+use openclaudgents_lib::events;
+
+/// A minimal NDJSON agent protocol, for exercising the provider plumbing
+/// without the Claude CLI.
+///
+/// The binary is started as `<path> --input-format ndjson [--resume <id>]`
+/// and reads one `{"type":"prompt","text":"..."}` line per turn. It answers
+/// with lines of these types:
+///
+/// - `{"type":"session","id":"..."}` — the agent's ID for the conversation
+/// - `{"type":"text","text":"..."}` — a chunk of the reply
+/// - `{"type":"tool","id":"...","name":"..."}` — a tool call started
+/// - `{"type":"done","is_error":false,"input_tokens":1,"output_tokens":2}` — end of turn
+///
+/// It keeps no transcripts, so there is nothing to discover or fork, and it
+/// has no permission prompts.
+pub struct TestProvider {
+    path: String,
+}
+
+impl TestProvider {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl AgentProvider for TestProvider {
+    fn id(&self) -> &'static str {
+        "test"
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Test agent"
+    }
+
+    fn command<'a>(
+        &'a self,
+        opts: &'a SpawnOptions,
+        _permissions: Option<&'a PermissionServer>,
+    ) -> Pin<Box<dyn Future<Output = Result<AgentCommand, String>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut cmd = Command::new(opts.claude_cli_path.as_deref().unwrap_or(&self.path));
+            cmd.arg("--input-format").arg("ndjson");
+            if let Some(ref resume_id) = opts.resume_session_id {
+                cmd.arg("--resume").arg(resume_id);
+            }
+            Ok(AgentCommand {
+                command: cmd,
+                mode: SessionMode::Persistent,
+                mcp_config: None,
+            })
+        })
+    }
+
+    fn encode_turn(&self, _mode: SessionMode, blocks: &[ContentBlock]) -> Result<String, String> {
+        if blocks.iter().any(|b| !matches!(b, ContentBlock::Text { .. })) {
+            return Err("The test agent only takes text".to_string());
+        }
+        let text = content::text_of(blocks);
+        let mut line = serde_json::json!({ "type": "prompt", "text": text }).to_string();
+        line.push('\n');
+        Ok(line)
+    }
+
+    fn parser(&self) -> Box<dyn LineParser> {
+        Box::new(TestParser)
+    }
+}
+
+struct TestParser;
+
+impl LineParser for TestParser {
+    fn parse_line(&mut self, session_id: &str, line: &str) -> ParsedLine {
+        let mut parsed = ParsedLine::default();
+        let Ok(event) = serde_json::from_str::<Value>(line.trim()) else {
+            return parsed;
+        };
+
+        parsed.events.push(OutboundEvent::new(
+            events::CLAUDE_STREAM_EVENT,
+            serde_json::json!({ "sessionId": session_id, "event": &event }),
+        ));
+
+        match event["type"].as_str().unwrap_or("") {
+            "session" => parsed.agent_session_id = event["id"].as_str().map(String::from),
+            "text" => {
+                if let Some(text) = event["text"].as_str() {
+                    parsed.events.push(OutboundEvent::new(
+                        events::CLAUDE_TEXT_DELTA,
+                        serde_json::json!({ "sessionId": session_id, "text": text }),
+                    ));
+                }
+            }
+            "tool" => parsed.events.push(OutboundEvent::new(
+                events::CLAUDE_TOOL_START,
+                serde_json::json!({
+                    "sessionId": session_id,
+                    "toolName": event["name"].as_str().unwrap_or("unknown"),
+                    "toolId": event["id"].as_str().unwrap_or(""),
+                }),
+            )),
+            "done" => {
+                let tokens = |key: &str| event[key].as_u64().unwrap_or(0);
+                let is_error = event["is_error"].as_bool().unwrap_or(false);
+                parsed.events.push(OutboundEvent::new(
+                    events::CLAUDE_MESSAGE_COMPLETE,
+                    serde_json::json!({ "sessionId": session_id }),
+                ));
+                parsed.turn_end = Some(TurnOutcome {
+                    session_id: session_id.to_string(),
+                    prompt_id: String::new(),
+                    subtype: if is_error { "error" } else { "success" }.to_string(),
+                    is_error,
+                    input_tokens: tokens("input_tokens"),
+                    output_tokens: tokens("output_tokens"),
+                    cost_usd: None,
+                });
+            }
+            _ => {}
+        }
+        parsed
+    }
+}
@@ -0,0 +1,135 @@
+//! Reads and forks recorded session transcripts in a stand-in `~/.claude/projects/`.
+//! Kept apart from the other tests because it points `HOME` elsewhere.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde_json::Value;
+
+use openclaudgents_lib::claude::session_store;
+
+const SESSION_ID: &str = "5b7e9d1f-3a2c-4e6b-8d0f-1c3e5a7b9d02";
+const BRANCHED_SESSION_ID: &str = "9c4d2e6f-8a1b-4c3d-9e5f-7a8b9c0d1e2f";
+
+/// The stand-in project directory. `HOME` is set once for every test here,
+/// as tests run in parallel; each one uses its own session IDs.
+fn project_dir() -> &'static Path {
+    static PROJECT_DIR: OnceLock<PathBuf> = OnceLock::new();
+    PROJECT_DIR.get_or_init(|| {
+        let home = std::env::temp_dir().join(format!("transcripts-{}", uuid::Uuid::new_v4()));
+        let project_dir = home.join(".claude").join("projects").join("-tmp-project");
+        fs::create_dir_all(&project_dir).unwrap();
+        std::env::set_var("HOME", &home);
+        project_dir
+    })
+}
+
+/// Copy a fixture into the stand-in project as the transcript of `session_id`
+fn install(fixture: &str, session_id: &str) {
+    fs::copy(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join(fixture),
+        project_dir().join(format!("{}.jsonl", session_id)),
+    )
+    .unwrap();
+}
+
+/// The lines of a transcript in the stand-in project
+fn transcript(session_id: &str) -> Vec<Value> {
+    fs::read_to_string(project_dir().join(format!("{}.jsonl", session_id)))
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+/// Message UUID `n` of the branched fixture
+fn branched(n: u32) -> String {
+    format!("b2e4a6c8-0000-4000-8000-{:012}", n)
+}
+
+#[test]
+fn thinking_blocks_are_kept_in_parsed_transcripts() {
+    install("thinking_transcript.jsonl", SESSION_ID);
+
+    let messages = session_store::parse_session_messages(SESSION_ID);
+    assert_eq!(messages.len(), 3);
+
+    assert!(messages[0].thinking.is_empty());
+    assert_eq!(messages[0].content, "Which primes sum to 10?");
+
+    let answer = &messages[1];
+    assert_eq!(answer.thinking.len(), 2);
+    assert_eq!(
+        answer.thinking[0].text,
+        "Two primes that sum to 10: 3 and 7, or 5 and 5."
+    );
+    assert!(!answer.thinking[0].redacted);
+    assert!(answer.thinking[1].redacted);
+    assert_eq!(answer.thinking[1].text, "");
+    // Only the answer itself is left in the content
+    let kinds: Vec<&Value> = answer
+        .content
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|block| &block["type"])
+        .collect();
+    assert_eq!(kinds, vec!["text"]);
+
+    let serialized = serde_json::to_value(answer).unwrap();
+    assert_eq!(serialized["thinking"][1]["redacted"], true);
+
+    assert!(messages[2].thinking.is_empty());
+    assert_eq!(messages[2].content, "No reasoning here.");
+}
+
+#[test]
+fn forks_follow_the_branch_of_the_chosen_message() {
+    install("branched_transcript.jsonl", BRANCHED_SESSION_ID);
+
+    let fork_id = session_store::fork_session_file(BRANCHED_SESSION_ID, &branched(8)).unwrap();
+    let lines = transcript(&fork_id);
+    // The rewound branch (3 and 4) is left out
+    let messages: Vec<&str> = lines
+        .iter()
+        .filter_map(|line| line["uuid"].as_str())
+        .collect();
+    assert_eq!(messages, [1, 2, 5, 6, 7, 8].map(branched));
+    // Entries that aren't messages are kept
+    assert_eq!(lines.len(), 7);
+    assert_eq!(lines[2]["type"], "summary");
+    for line in lines.iter().filter(|line| line.get("uuid").is_some()) {
+        assert_eq!(line["sessionId"], fork_id.as_str());
+    }
+
+    // The original is untouched
+    assert_eq!(transcript(BRANCHED_SESSION_ID).len(), 10);
+}
+
+#[test]
+fn forks_can_start_from_an_abandoned_branch() {
+    let session_id = "0e1f2a3b-4c5d-4e6f-8a9b-0c1d2e3f4a5b";
+    install("branched_transcript.jsonl", session_id);
+
+    let fork_id = session_store::fork_session_file(session_id, &branched(4)).unwrap();
+    let messages: Vec<String> = transcript(&fork_id)
+        .iter()
+        .map(|line| line["uuid"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(messages, [1, 2, 3, 4].map(branched));
+}
+
+#[test]
+fn forks_are_refused_mid_tool_call_or_for_unknown_messages() {
+    let session_id = "6d7e8f90-1a2b-4c3d-8e4f-5a6b7c8d9e0f";
+    install("branched_transcript.jsonl", session_id);
+
+    let err = session_store::fork_session_file(session_id, &branched(6)).unwrap_err();
+    assert!(err.contains("tool call"), "{}", err);
+    let err = session_store::fork_session_file(session_id, &branched(99)).unwrap_err();
+    assert!(err.contains("not found"), "{}", err);
+}
@@ -2,7 +2,7 @@ import { useState, useCallback, useRef, useEffect } from "react";
 import ReactMarkdown from "react-markdown";
 import remarkGfm from "remark-gfm";
 import { ToolCallBlock } from "./ToolCallBlock";
-import type { ChatMessage, ContentBlock } from "../../lib/types";
+import type { ChatMessage, ContentBlock, ThinkingBlock } from "../../lib/types";
 import type { Components } from "react-markdown";
 import type { ReactNode } from "react";
 
@@ -80,6 +80,15 @@ export function MessageBubble({ message, searchQuery }: MessageBubbleProps) {
           </div>
         )}
 
+        {/* Reasoning — collapsed by default */}
+        {message.thinking && message.thinking.length > 0 && (
+          <div className="mb-3 space-y-2">
+            {message.thinking.map((block, i) => (
+              <ThinkingSection key={i} block={block} searchQuery={searchQuery} />
+            ))}
+          </div>
+        )}
+
         {/* Message content */}
         <div className="text-sm leading-relaxed">
           <MessageContent content={message.content} searchQuery={searchQuery} />
@@ -112,6 +121,34 @@ export function MessageBubble({ message, searchQuery }: MessageBubbleProps) {
   );
 }
 
+function ThinkingSection({ block, searchQuery }: { block: ThinkingBlock; searchQuery?: string }) {
+  const [expanded, setExpanded] = useState(false);
+  const label = block.durationMs !== undefined
+    ? `Thought for ${Math.max(1, Math.round(block.durationMs / 1000))}s`
+    : "Thinking";
+
+  if (block.redacted) {
+    return <div className="text-xs italic text-text-muted">{label} (redacted)</div>;
+  }
+
+  return (
+    <div className="rounded-lg border-l-2 border-border/50 pl-3">
+      <button
+        onClick={() => setExpanded((e) => !e)}
+        aria-expanded={expanded}
+        className="text-xs font-medium text-text-muted transition-colors hover:text-text"
+      >
+        {expanded ? "▾" : "▸"} {label}
+      </button>
+      {expanded && (
+        <div className="mt-1 animate-fade-in whitespace-pre-wrap text-xs leading-relaxed text-text-secondary">
+          {searchQuery ? <HighlightedText text={block.text} query={searchQuery} /> : block.text}
+        </div>
+      )}
+    </div>
+  );
+}
+
 function MessageContent({ content, searchQuery }: { content: string | ContentBlock[]; searchQuery?: string }) {
   if (typeof content === "string") {
     return <MarkdownRenderer text={content} highlight={searchQuery} />;
@@ -38,6 +38,11 @@ export function ToolCallBlock({ toolCall }: ToolCallBlockProps) {
           </span>
           {/* Status icon */}
           <StatusIcon status={toolCall.status} />
+          {toolCall.durationMs !== undefined && (
+            <span className="text-[10px] text-text-muted">
+              {formatDuration(toolCall.durationMs)}
+            </span>
+          )}
         </div>
       </button>
 
@@ -63,6 +68,9 @@ export function ToolCallBlock({ toolCall }: ToolCallBlockProps) {
               <pre className="max-h-48 overflow-auto rounded-lg bg-code-bg p-2.5 font-mono text-xs text-text-secondary">
                 {toolCall.result}
               </pre>
+              {toolCall.truncated && (
+                <div className="mt-1 text-[10px] text-text-muted">Output truncated</div>
+              )}
             </div>
           )}
         </div>
@@ -71,6 +79,10 @@ export function ToolCallBlock({ toolCall }: ToolCallBlockProps) {
   );
 }
 
+function formatDuration(ms: number): string {
+  return ms < 1000 ? `${ms}ms` : `${(ms / 1000).toFixed(1)}s`;
+}
+
 function StatusIcon({ status }: { status: string }) {
   if (status === "completed") {
     return (
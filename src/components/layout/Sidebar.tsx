@@ -87,6 +87,7 @@ export function Sidebar({ onNewSession }: SidebarProps) {
             : (p.content as ContentBlock[]),
         timestamp: p.timestamp,
         isSidechain: p.isSidechain,
+        thinking: p.thinking.length > 0 ? p.thinking : undefined,
       }));
       setMessages(messages);
     } catch (err) {
@@ -2,9 +2,12 @@ import { useEffect } from "react";
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import { useChatStore } from "../stores/chatStore";
 import { useSessionStore } from "../stores/sessionStore";
+import type { CompletedBlock } from "../lib/tauri";
 
 interface TextDeltaEvent {
   sessionId: string;
+  /** Content block the text belongs to */
+  index: number;
   text: string;
 }
 
@@ -18,14 +21,50 @@ interface ToolStartEvent {
   toolId: string;
 }
 
-interface ToolInputDeltaEvent {
+interface ToolCompleteEvent {
   sessionId: string;
-  partialJson: string;
+  toolId: string;
+  /** Null when the call was never seen starting */
+  toolName: string | null;
+  parentToolUseId: string | null;
+  isError: boolean;
+  durationMs: number | null;
+  preview: string;
+  truncated: boolean;
+}
+
+interface ContentBlockCompleteEvent {
+  sessionId: string;
+  index: number;
+  /** Set for blocks of a subagent, to the tool call that started it */
+  parentToolUseId: string | null;
+  block: CompletedBlock;
+}
+
+interface ThinkingStartEvent {
+  sessionId: string;
+  index: number;
+  parentToolUseId: string | null;
+  redacted: boolean;
+}
+
+interface ThinkingStopEvent {
+  sessionId: string;
+  index: number;
+  parentToolUseId: string | null;
+  durationMs: number;
+  /** Null when the API redacted the reasoning */
+  thinking: string | null;
+  redacted: boolean;
 }
 
 interface SessionStatusEvent {
   sessionId: string;
   status: string;
+  /** Only present once the process has exited */
+  exitCode?: number | null;
+  signal?: number | null;
+  stderrTail?: string[];
 }
 
 interface StderrEvent {
@@ -33,8 +72,11 @@ interface StderrEvent {
   text: string;
 }
 
+/** Usage of one API message, sent once when the message is over */
 interface UsageUpdateEvent {
   sessionId: string;
+  messageId: string | null;
+  model: string | null;
   usage: {
     inputTokens: number;
     outputTokens: number;
@@ -63,8 +105,12 @@ export function useStreamingChat() {
   const addMessage = useChatStore((s) => s.addMessage);
   const incrementCompaction = useChatStore((s) => s.incrementCompaction);
   const startToolCall = useChatStore((s) => s.startToolCall);
-  const appendToolInput = useChatStore((s) => s.appendToolInput);
+  const setToolInput = useChatStore((s) => s.setToolInput);
   const flushPendingToolCalls = useChatStore((s) => s.flushPendingToolCalls);
+  const finishToolCall = useChatStore((s) => s.finishToolCall);
+  const settleToolCalls = useChatStore((s) => s.settleToolCalls);
+  const addThinking = useChatStore((s) => s.addThinking);
+  const flushPendingThinking = useChatStore((s) => s.flushPendingThinking);
   const updateSession = useSessionStore((s) => s.updateSession);
   const setActivityState = useSessionStore((s) => s.setActivityState);
   const activeSessionId = useSessionStore((s) => s.activeSessionId);
@@ -98,12 +144,47 @@ export function useStreamingChat() {
       }),
     );
 
-    // Tool input JSON deltas — accumulate partial JSON for the current tool
+    // Tool result — the call finished, successfully or not
     unlisteners.push(
-      listen<ToolInputDeltaEvent>("claude:tool_input_delta", (event) => {
-        const { sessionId, partialJson } = event.payload;
+      listen<ToolCompleteEvent>("claude:tool_complete", (event) => {
+        const { sessionId, toolId, isError, durationMs, preview, truncated } = event.payload;
+        if (sessionId === activeSessionId) {
+          finishToolCall(toolId, {
+            status: isError ? "error" : "completed",
+            result: preview,
+            truncated,
+            durationMs: durationMs ?? undefined,
+          });
+        }
+      }),
+    );
+
+    // Extended thinking — reasoning streams on its own channel
+    unlisteners.push(
+      listen<ThinkingStartEvent>("claude:thinking_start", (event) => {
+        const { sessionId } = event.payload;
+        setActivityState(sessionId, "thinking");
         if (sessionId === activeSessionId) {
-          appendToolInput(partialJson);
+          setStreaming(true);
+        }
+      }),
+    );
+
+    unlisteners.push(
+      listen<ThinkingStopEvent>("claude:thinking_stop", (event) => {
+        const { sessionId, parentToolUseId, durationMs, thinking, redacted } = event.payload;
+        if (sessionId === activeSessionId && !parentToolUseId) {
+          addThinking({ text: thinking ?? "", redacted, durationMs });
+        }
+      }),
+    );
+
+    // Content block finished — tool calls get their parsed input
+    unlisteners.push(
+      listen<ContentBlockCompleteEvent>("claude:content_block_complete", (event) => {
+        const { sessionId, parentToolUseId, block } = event.payload;
+        if (sessionId === activeSessionId && !parentToolUseId && block.type === "tool_use") {
+          setToolInput(block.id, block.input);
         }
       }),
     );
@@ -116,8 +197,9 @@ export function useStreamingChat() {
         if (sessionId === activeSessionId) {
           const streamingText = useChatStore.getState().streamingText;
           const toolCalls = flushPendingToolCalls();
+          const thinking = flushPendingThinking();
 
-          if (streamingText || toolCalls.length > 0) {
+          if (streamingText || toolCalls.length > 0 || thinking.length > 0) {
             addMessage({
               uuid: crypto.randomUUID(),
               parentUuid: null,
@@ -126,6 +208,7 @@ export function useStreamingChat() {
               timestamp: new Date().toISOString(),
               isSidechain: false,
               toolCalls: toolCalls.length > 0 ? toolCalls : undefined,
+              thinking: thinking.length > 0 ? thinking : undefined,
             });
           }
           resetStreamingText();
@@ -156,7 +239,7 @@ export function useStreamingChat() {
       listen<SessionStatusEvent>("claude:session_status", (event) => {
         const { sessionId, status } = event.payload;
         updateSession(sessionId, {
-          status: status as "active" | "paused" | "completed" | "error",
+          status: status as "active" | "paused" | "completed" | "error" | "cancelled",
         });
 
         // Map session status to activity state
@@ -169,18 +252,30 @@ export function useStreamingChat() {
           setActivityState(sessionId, "idle");
         }
 
+        // On cancel, drop the partial answer like an error does
+        if (status === "cancelled") {
+          setActivityState(sessionId, "idle");
+          if (sessionId === activeSessionId) {
+            resetStreamingText();
+            setStreaming(false);
+            settleToolCalls();
+          }
+        }
+
         // On error, clean up streaming state
         if (status === "error") {
           setActivityState(sessionId, "idle");
           if (sessionId === activeSessionId) {
             resetStreamingText();
             setStreaming(false);
+            settleToolCalls();
           }
         }
       }),
     );
 
-    // Usage updates
+    // Usage of each finished message, added to the session's totals (the
+    // backend keeps the same totals in the database)
     unlisteners.push(
       listen<UsageUpdateEvent>("claude:usage_update", (event) => {
         const { sessionId, usage } = event.payload;
@@ -227,8 +322,12 @@ export function useStreamingChat() {
     addMessage,
     incrementCompaction,
     startToolCall,
-    appendToolInput,
+    setToolInput,
     flushPendingToolCalls,
+    finishToolCall,
+    settleToolCalls,
+    addThinking,
+    flushPendingThinking,
     updateSession,
     setActivityState,
   ]);
@@ -0,0 +1,140 @@
+// Lines of the Claude CLI's `--output-format stream-json` output, as carried
+// raw by `claude:stream_event`. Mirrors `ClaudeStreamEvent` in
+// `src-tauri/src/claude/types.rs`; field names are the CLI's own. Each union
+// ends in an open variant for events added by newer CLIs.
+
+export type ClaudeStreamEvent =
+  | SystemEvent
+  | StreamEventLine
+  | ({ type: "assistant" } & MessageLine)
+  | ({ type: "user" } & MessageLine)
+  | ResultEvent
+  | { type: string; [key: string]: unknown };
+
+export type SystemEvent =
+  | ({ type: "system"; subtype: "init" } & SystemInit)
+  | ({ type: "system"; subtype: "compact_boundary" | "compaction" } & CompactBoundary)
+  | ({ type: "system"; subtype: "hook_started" | "hook_response" } & HookRun)
+  | { type: "system"; subtype: "status"; session_id?: string; status?: string | null }
+  | { type: "system"; subtype: string; [key: string]: unknown };
+
+export interface SystemInit {
+  session_id: string;
+  cwd?: string;
+  model?: string;
+  tools?: string[];
+  mcp_servers?: { name: string; status: string }[];
+  permissionMode?: string;
+  apiKeySource?: string;
+}
+
+export interface CompactBoundary {
+  session_id?: string;
+  /** Trigger and token count before compaction */
+  compact_metadata?: { trigger?: string; pre_tokens?: number };
+}
+
+/** A hook run; the output fields are only set on `hook_response` */
+export interface HookRun {
+  session_id?: string;
+  hook_id?: string;
+  hook_name: string;
+  hook_event?: string;
+  tool_name?: string;
+  output?: string;
+  stdout?: string;
+  stderr?: string;
+  exit_code?: number;
+  outcome?: "success" | "error" | "cancelled";
+}
+
+export interface StreamEventLine {
+  type: "stream_event";
+  event: ApiStreamEvent;
+  session_id?: string;
+  /** Set for events of a subagent, to the tool call that started it */
+  parent_tool_use_id?: string | null;
+  uuid?: string;
+}
+
+export interface MessageLine {
+  message: ApiMessage;
+  session_id?: string;
+  parent_tool_use_id?: string | null;
+  uuid?: string;
+}
+
+export interface ResultEvent {
+  type: "result";
+  /** `success`, `error_max_turns`, `error_during_execution`, ... */
+  subtype: string;
+  is_error: boolean;
+  duration_ms?: number;
+  duration_api_ms?: number;
+  num_turns?: number;
+  /** Final text of the turn */
+  result?: string;
+  session_id?: string;
+  total_cost_usd?: number;
+  usage?: ApiTokenUsage;
+  /** Tool calls the permission settings refused during the turn */
+  permission_denials?: { tool_name: string; tool_use_id?: string; tool_input?: unknown }[];
+}
+
+/** Inner events of `stream_event` lines, as the Messages API streams them */
+export type ApiStreamEvent =
+  | { type: "message_start"; message: ApiMessage }
+  | { type: "content_block_start"; index: number; content_block: ApiContentBlock }
+  | { type: "content_block_delta"; index: number; delta: ContentDelta }
+  | { type: "content_block_stop"; index: number }
+  | {
+      type: "message_delta";
+      delta: { stop_reason?: string | null; stop_sequence?: string | null };
+      usage?: ApiTokenUsage;
+    }
+  | { type: "message_stop" }
+  | { type: "ping" }
+  | { type: "error"; error: { type: string; message: string } }
+  | { type: string; [key: string]: unknown };
+
+export interface ApiMessage {
+  id?: string;
+  role: string;
+  model?: string;
+  /** Plain text for user prompts, content blocks otherwise */
+  content: string | ApiContentBlock[];
+  stop_reason?: string | null;
+  usage?: ApiTokenUsage;
+}
+
+export type ApiContentBlock =
+  | { type: "text"; text: string }
+  | { type: "thinking"; thinking: string; signature?: string }
+  | { type: "redacted_thinking"; data: string }
+  | { type: "tool_use"; id: string; name: string; input: unknown }
+  | { type: "server_tool_use"; id: string; name: string; input: unknown }
+  | {
+      type: "tool_result";
+      tool_use_id: string;
+      content?: string | ApiContentBlock[];
+      is_error?: boolean;
+    }
+  | { type: "image"; source: unknown }
+  | { type: "document"; source: unknown }
+  | { type: string; [key: string]: unknown };
+
+export type ContentDelta =
+  | { type: "text_delta"; text: string }
+  /** A fragment of a tool call's JSON input */
+  | { type: "input_json_delta"; partial_json: string }
+  | { type: "thinking_delta"; thinking: string }
+  | { type: "signature_delta"; signature: string }
+  | { type: "citations_delta"; citation: unknown }
+  | { type: string; [key: string]: unknown };
+
+export interface ApiTokenUsage {
+  input_tokens?: number;
+  output_tokens?: number;
+  cache_creation_input_tokens?: number;
+  cache_read_input_tokens?: number;
+}
@@ -1,6 +1,7 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
-import type { Session, ChatMessage, GitStatus, WorktreeInfo, DiffSummary, FileDiffContent, TodoItem, AgentTeam, McpServerInfo, CustomSkill } from "./types";
+import type { Session, ChatMessage, GitStatus, WorktreeInfo, DiffSummary, DiffFile, FileDiffContent, TodoItem, AgentTeam, McpServerInfo, CustomSkill, ThinkingBlock } from "./types";
+import type { ClaudeStreamEvent } from "./streamEvents";
 
 // --- Discovered session type (from Rust session_store) ---
 
@@ -12,6 +13,8 @@ export interface DiscoveredSession {
   messageCount: number;
   model: string | null;
   gitBranch: string | null;
+  /** Agent provider that can resume the session */
+  provider: string;
 }
 
 export interface ParsedMessage {
@@ -22,15 +25,34 @@ export interface ParsedMessage {
   timestamp: string;
   isSidechain: boolean;
   model: string | null;
+  /** Thinking blocks, kept out of `content` */
+  thinking: ThinkingBlock[];
 }
 
 // --- Tauri Commands (Frontend → Rust) ---
 
+/** Per-session CLI launch settings, reapplied whenever the session respawns */
+export interface LaunchSettings {
+  /** Agent provider ID; the Claude CLI when unset */
+  provider?: string | null;
+  /** Environment profile ID; the project's default profile when unset */
+  envProfile?: string | null;
+  model?: string | null;
+  fallbackModel?: string | null;
+  permissionMode?: "default" | "acceptEdits" | "plan" | "bypassPermissions" | null;
+  allowedTools?: string[];
+  disallowedTools?: string[];
+  appendSystemPrompt?: string | null;
+  additionalDirs?: string[];
+  maxTurns?: number | null;
+}
+
 export async function createSession(
   projectPath: string,
   model?: string,
+  launch?: LaunchSettings,
 ): Promise<Session> {
-  const session = await invoke<Omit<Session, "pinned" | "activityState" | "archived">>("create_session", { projectPath, model });
+  const session = await invoke<Omit<Session, "pinned" | "activityState" | "archived">>("create_session", { projectPath, model, launch });
   return { ...session, pinned: false, activityState: "idle", archived: false };
 }
 
@@ -38,36 +60,438 @@ export async function sendMessage(
   sessionId: string,
   message: string,
   projectPath: string,
-): Promise<void> {
+): Promise<QueuedPrompt> {
   return invoke("send_message", { sessionId, message, projectPath });
 }
 
+/** One part of a message: text, an image (file or pasted) or a document */
+export type ContentInput =
+  | { type: "text"; text: string }
+  | { type: "imageFile"; path: string }
+  | { type: "imageData"; mediaType: string; data: string }
+  | { type: "documentFile"; path: string };
+
+export async function sendContent(
+  sessionId: string,
+  content: ContentInput[],
+  projectPath: string,
+): Promise<QueuedPrompt> {
+  return invoke("send_content", { sessionId, content, projectPath });
+}
+
 export async function killSession(sessionId: string): Promise<void> {
   return invoke("kill_session", { sessionId });
 }
 
+export async function interruptSession(
+  sessionId: string,
+  graceMs?: number,
+): Promise<void> {
+  return invoke("interrupt_session", { sessionId, graceMs: graceMs ?? null });
+}
+
+// --- Prompt Queue Commands ---
+
+export interface QueuedPrompt {
+  id: string;
+  text: string;
+  projectPath: string;
+  queuedAt: string;
+  attempt: number;
+  /** Labels of attached images and documents */
+  attachments: string[];
+}
+
+export async function getQueuedPrompts(sessionId: string): Promise<QueuedPrompt[]> {
+  return invoke("get_queued_prompts", { sessionId });
+}
+
+export async function reorderQueuedPrompt(
+  sessionId: string,
+  promptId: string,
+  index: number,
+): Promise<QueuedPrompt[]> {
+  return invoke("reorder_queued_prompt", { sessionId, promptId, index });
+}
+
+export async function cancelQueuedPrompt(
+  sessionId: string,
+  promptId: string,
+): Promise<QueuedPrompt> {
+  return invoke("cancel_queued_prompt", { sessionId, promptId });
+}
+
+// --- Turn Scheduler Commands ---
+
+export type SessionPriority = "interactive" | "background";
+
+export interface SchedulerStatus {
+  maxConcurrent: number;
+  running: string[];
+  waiting: { sessionId: string; priority: SessionPriority }[];
+}
+
+export async function getSchedulerStatus(): Promise<SchedulerStatus> {
+  return invoke("get_scheduler_status");
+}
+
+export async function setMaxConcurrentTurns(maxConcurrent: number): Promise<SchedulerStatus> {
+  return invoke("set_max_concurrent_turns", { maxConcurrent });
+}
+
+export async function setSessionPriority(
+  sessionId: string,
+  priority: SessionPriority,
+): Promise<void> {
+  return invoke("set_session_priority", { sessionId, priority });
+}
+
+export type TimeoutAction = "interrupt" | "retry";
+
+export interface WatchdogSettings {
+  stallAfterSecs: number;
+  turnTimeoutSecs: number | null;
+  onTimeout: TimeoutAction;
+}
+
+export async function getWatchdogSettings(): Promise<WatchdogSettings> {
+  return invoke("get_watchdog_settings");
+}
+
+export async function setWatchdogSettings(settings: WatchdogSettings): Promise<void> {
+  return invoke("set_watchdog_settings", { settings });
+}
+
+export interface EventMetrics {
+  received: number;
+  emitted: number;
+  merged: number;
+  dropped: number;
+}
+
+export async function setRawEventMirroring(sessionId: string, enabled: boolean): Promise<void> {
+  return invoke("set_raw_event_mirroring", { sessionId, enabled });
+}
+
+export async function getEventMetrics(sessionId: string): Promise<EventMetrics> {
+  return invoke("get_event_metrics", { sessionId });
+}
+
 export async function detectClaudeCli(): Promise<string | null> {
   return invoke("detect_claude_cli");
 }
 
+export interface ClaudeCliInfo {
+  path: string;
+  version: string | null;
+  flags: string[];
+}
+
+export async function getClaudeCliInfo(): Promise<ClaudeCliInfo> {
+  return invoke("get_claude_cli_info");
+}
+
+export async function setClaudeCliPath(path: string | null): Promise<ClaudeCliInfo | null> {
+  return invoke("set_claude_cli_path", { path });
+}
+
+/** Sessions created in the app, restored from the database after a restart */
+export async function listSavedSessions(): Promise<Session[]> {
+  const sessions = await invoke<Omit<Session, "pinned" | "activityState" | "archived">[]>("list_saved_sessions");
+  return sessions.map((session) => ({ ...session, pinned: false, activityState: "idle", archived: false }));
+}
+
+/** A content block once streamed in full, from `claude:content_block_complete` */
+export type CompletedBlock =
+  | { type: "text"; text: string }
+  | { type: "thinking"; thinking: string; signature: string | null }
+  | { type: "redacted_thinking"; data: string }
+  /** `input` is `{ raw }` when the streamed JSON didn't parse */
+  | { type: "tool_use"; id: string; name: string; input: Record<string, unknown> }
+  | { type: "server_tool_use"; id: string; name: string; input: Record<string, unknown> };
+
+/** A CLI hook starting or finishing, e.g. a PreToolUse hook on Bash */
+export interface HookEvent {
+  hookId: string | null;
+  phase: "started" | "finished";
+  /** Lifecycle event, e.g. `PreToolUse` */
+  hookEvent: string;
+  /** Hook as configured, e.g. `PreToolUse:Bash` */
+  hookName: string;
+  toolName: string | null;
+  exitCode: number | null;
+  outcome: string | null;
+  stdout: string;
+  stderr: string;
+  /** e.g. `block` for exit status 2, or the hook's JSON decision */
+  decision: { decision: string; reason: string | null } | null;
+}
+
+export interface ActivityEntry {
+  id: number;
+  sessionId: string;
+  kind: "hook";
+  payload: HookEvent;
+  createdAt: string;
+}
+
+/** Latest entries of a session's activity log, oldest first */
+export async function getSessionActivity(sessionId: string, limit?: number): Promise<ActivityEntry[]> {
+  return invoke("get_session_activity", { sessionId, limit });
+}
+
+/** What a finished turn took, cost and was refused, from the CLI's result */
+export interface TurnSummary {
+  sessionId: string;
+  /** Queued prompt the turn ran */
+  promptId: string | null;
+  /** `success`, `error_max_turns`, `error_during_execution`, ... */
+  subtype: string;
+  isError: boolean;
+  durationMs: number | null;
+  /** Time spent waiting on the API */
+  durationApiMs: number | null;
+  /** Model round trips within the turn */
+  numTurns: number | null;
+  costUsd: number | null;
+  inputTokens: number;
+  outputTokens: number;
+  cacheReadTokens: number;
+  cacheCreationTokens: number;
+  /** Final text of the turn */
+  result: string | null;
+  permissionDenials: { toolName: string; toolUseId: string | null; toolInput: unknown }[];
+  finishedAt: string;
+}
+
+/** Latest turn summaries of a session, oldest first */
+export async function getTurnSummaries(sessionId: string, limit?: number): Promise<TurnSummary[]> {
+  return invoke("get_turn_summaries", { sessionId, limit });
+}
+
 export async function discoverSessions(): Promise<DiscoveredSession[]> {
   return invoke("discover_sessions");
 }
 
 export async function getSessionMessages(
   claudeSessionId: string,
+  provider?: string,
 ): Promise<ParsedMessage[]> {
-  return invoke("get_session_messages", { claudeSessionId });
+  return invoke("get_session_messages", { claudeSessionId, provider });
+}
+
+/** A coding agent sessions can be started with */
+export interface ProviderInfo {
+  id: string;
+  name: string;
+}
+
+export async function listProviders(): Promise<ProviderInfo[]> {
+  return invoke("list_providers");
 }
 
 export async function resumeSession(
   claudeSessionId: string,
   projectPath: string,
+  model?: string,
+  launch?: LaunchSettings,
+): Promise<Session> {
+  const session = await invoke<Omit<Session, "pinned" | "activityState" | "archived">>("resume_session", { claudeSessionId, projectPath, model, launch });
+  return { ...session, pinned: false, activityState: "idle", archived: false };
+}
+
+/** Branch a session's conversation at `messageUuid` into a new, paused session */
+export async function forkSession(
+  sessionId: string,
+  messageUuid: string,
+  projectPath: string,
+  launch?: LaunchSettings,
 ): Promise<Session> {
-  const session = await invoke<Omit<Session, "pinned" | "activityState" | "archived">>("resume_session", { claudeSessionId, projectPath });
+  const session = await invoke<Omit<Session, "pinned" | "activityState" | "archived">>("fork_session", { sessionId, messageUuid, projectPath, launch });
   return { ...session, pinned: false, activityState: "idle", archived: false };
 }
 
+// --- Scheduled Prompts ---
+
+export type ScheduleSpec =
+  | { kind: "once"; at: string }
+  | { kind: "cron"; expr: string };
+
+export interface ScheduleInput {
+  name: string;
+  /** Session to send the prompt to; omit to start a new session per run */
+  sessionId?: string | null;
+  projectPath: string;
+  prompt: string;
+  spec: ScheduleSpec;
+  launch?: LaunchSettings;
+  enabled?: boolean;
+}
+
+export interface PromptSchedule {
+  id: string;
+  name: string;
+  sessionId: string | null;
+  projectPath: string;
+  prompt: string;
+  spec: ScheduleSpec;
+  launch: LaunchSettings;
+  enabled: boolean;
+  nextRunAt: string | null;
+  lastRunAt: string | null;
+  createdAt: string;
+}
+
+export interface ScheduleRun {
+  id: string;
+  scheduleId: string;
+  sessionId: string | null;
+  startedAt: string;
+  finishedAt: string | null;
+  outcome: string;
+  isError: boolean;
+  inputTokens: number;
+  outputTokens: number;
+  costUsd: number | null;
+  diff: { files: DiffFile[]; totalAdditions: number; totalDeletions: number } | null;
+  error: string | null;
+}
+
+export async function listSchedules(): Promise<PromptSchedule[]> {
+  return invoke("list_schedules");
+}
+
+export async function createSchedule(input: ScheduleInput): Promise<PromptSchedule> {
+  return invoke("create_schedule", { input });
+}
+
+export async function updateSchedule(id: string, input: ScheduleInput): Promise<PromptSchedule> {
+  return invoke("update_schedule", { id, input });
+}
+
+export async function deleteSchedule(id: string): Promise<void> {
+  return invoke("delete_schedule", { id });
+}
+
+export async function runScheduleNow(id: string): Promise<ScheduleRun> {
+  return invoke("run_schedule_now", { id });
+}
+
+export async function listScheduleRuns(scheduleId: string, limit?: number): Promise<ScheduleRun[]> {
+  return invoke("list_schedule_runs", { scheduleId, limit });
+}
+
+export function onScheduleRunUpdated(
+  callback: (run: ScheduleRun) => void,
+): Promise<UnlistenFn> {
+  return listen("schedule:run_updated", (event) => {
+    callback(event.payload as ScheduleRun);
+  });
+}
+
+// --- Environment Profiles ---
+
+/** Secret values arrive as this; sending it back keeps the stored value */
+export const MASKED_ENV_VALUE = "••••••••";
+
+export interface EnvVar {
+  key: string;
+  value: string;
+  secret: boolean;
+}
+
+export interface EnvProfileInput {
+  name: string;
+  /** Project whose sessions use the profile unless they pick another */
+  projectPath: string | null;
+  vars: EnvVar[];
+  /** Directories put in front of PATH */
+  pathPrepend: string[];
+}
+
+export interface EnvProfile extends EnvProfileInput {
+  id: string;
+  createdAt: string;
+  updatedAt: string;
+}
+
+export async function listEnvProfiles(): Promise<EnvProfile[]> {
+  return invoke("list_env_profiles");
+}
+
+/** Create a profile, or update the one with `id` */
+export async function saveEnvProfile(input: EnvProfileInput, id?: string): Promise<EnvProfile> {
+  return invoke("save_env_profile", { id, input });
+}
+
+export async function deleteEnvProfile(id: string): Promise<void> {
+  return invoke("delete_env_profile", { id });
+}
+
+// --- Tool Approvals ---
+
+export type PermissionBehavior = "allow" | "deny";
+
+export interface PermissionSettings {
+  /** Ask before tool calls; applies to sessions started afterwards */
+  enabled: boolean;
+  timeoutSecs: number;
+  /** Answer given when a request times out */
+  onTimeout: PermissionBehavior;
+}
+
+/** A tool call waiting for the user */
+export interface PermissionRequest {
+  requestId: string;
+  sessionId: string;
+  projectPath: string;
+  toolName: string;
+  toolUseId: string | null;
+  input: Record<string, unknown>;
+  requestedAt: string;
+  timeoutSecs: number;
+}
+
+/** Allow (with edited input, or always for the project) or deny a tool call */
+export type PermissionResponse =
+  | { behavior: "allow"; updatedInput?: Record<string, unknown>; always?: boolean }
+  | { behavior: "deny"; message?: string };
+
+export interface PermissionRule {
+  id: string;
+  projectPath: string;
+  toolName: string;
+  createdAt: string;
+}
+
+export async function getPermissionSettings(): Promise<PermissionSettings> {
+  return invoke("get_permission_settings");
+}
+
+export async function setPermissionSettings(settings: PermissionSettings): Promise<void> {
+  return invoke("set_permission_settings", { settings });
+}
+
+export async function listPendingPermissions(): Promise<PermissionRequest[]> {
+  return invoke("list_pending_permissions");
+}
+
+/** Returns the rule added when allowing with `always` */
+export async function respondToPermission(
+  requestId: string,
+  response: PermissionResponse,
+): Promise<PermissionRule | null> {
+  return invoke("respond_to_permission", { requestId, response });
+}
+
+export async function listPermissionRules(projectPath?: string): Promise<PermissionRule[]> {
+  return invoke("list_permission_rules", { projectPath });
+}
+
+export async function deletePermissionRule(id: string): Promise<void> {
+  return invoke("delete_permission_rule", { id });
+}
+
 // --- Git & Worktree Commands ---
 
 export async function getGitStatus(path: string): Promise<GitStatus> {
@@ -262,11 +686,12 @@ export async function discoverCustomSkills(projectPath?: string): Promise<Custom
 
 // --- Tauri Event Listeners (Rust → Frontend) ---
 
+/** Raw CLI output lines, only sent while a session's stream is mirrored */
 export function onClaudeStreamEvent(
-  callback: (event: { sessionId: string; data: string }) => void,
+  callback: (event: { sessionId: string; event: ClaudeStreamEvent }) => void,
 ): Promise<UnlistenFn> {
   return listen("claude:stream_event", (event) => {
-    callback(event.payload as { sessionId: string; data: string });
+    callback(event.payload as { sessionId: string; event: ClaudeStreamEvent });
   });
 }
 
@@ -288,29 +713,122 @@ export function onSessionStatusChanged(
   });
 }
 
-export function onClaudeStderr(
-  callback: (event: { sessionId: string; text: string }) => void,
-): Promise<UnlistenFn> {
-  return listen("claude:stderr", (event) => {
-    callback(event.payload as { sessionId: string; text: string });
-  });
-}
-
-export function onUsageUpdate(
+export function onQueueUpdated(
   callback: (event: {
     sessionId: string;
-    usage: {
-      inputTokens: number;
-      outputTokens: number;
-    };
+    depth: number;
+    busy: boolean;
+    prompts: QueuedPrompt[];
   }) => void,
 ): Promise<UnlistenFn> {
-  return listen("claude:usage_update", (event) => {
+  return listen("claude:queue_updated", (event) => {
     callback(
       event.payload as {
         sessionId: string;
-        usage: { inputTokens: number; outputTokens: number };
+        depth: number;
+        busy: boolean;
+        prompts: QueuedPrompt[];
       },
     );
   });
 }
+
+export interface ScheduleUpdate {
+  sessionId: string;
+  /** Place in line for a turn slot (0 = next), or null once no longer waiting */
+  position: number | null;
+  waiting: number;
+  running: number;
+  maxConcurrent: number;
+}
+
+export function onScheduleUpdated(
+  callback: (event: ScheduleUpdate) => void,
+): Promise<UnlistenFn> {
+  return listen("claude:schedule_updated", (event) => {
+    callback(event.payload as ScheduleUpdate);
+  });
+}
+
+export interface SessionStall {
+  sessionId: string;
+  stalled: boolean;
+  idleSecs: number;
+  turnSecs: number | null;
+  timedOut: boolean;
+  action: TimeoutAction | null;
+  attempt: number | null;
+}
+
+export function onSessionStalled(
+  callback: (event: SessionStall) => void,
+): Promise<UnlistenFn> {
+  return listen("claude:session_stalled", (event) => {
+    callback(event.payload as SessionStall);
+  });
+}
+
+export function onPermissionRequest(
+  callback: (request: PermissionRequest) => void,
+): Promise<UnlistenFn> {
+  return listen("claude:permission_request", (event) => {
+    callback(event.payload as PermissionRequest);
+  });
+}
+
+export interface PermissionResolved {
+  sessionId: string;
+  requestId: string;
+  behavior: PermissionBehavior;
+  resolution: "user" | "rule" | "timeout" | "cancelled";
+}
+
+export function onPermissionResolved(
+  callback: (event: PermissionResolved) => void,
+): Promise<UnlistenFn> {
+  return listen("claude:permission_resolved", (event) => {
+    callback(event.payload as PermissionResolved);
+  });
+}
+
+export function onHookEvent(
+  callback: (event: HookEvent & { sessionId: string }) => void,
+): Promise<UnlistenFn> {
+  return listen("claude:hook_event", (event) => {
+    callback(event.payload as HookEvent & { sessionId: string });
+  });
+}
+
+export function onTurnSummary(callback: (summary: TurnSummary) => void): Promise<UnlistenFn> {
+  return listen("claude:turn_summary", (event) => {
+    callback(event.payload as TurnSummary);
+  });
+}
+
+export function onClaudeStderr(
+  callback: (event: { sessionId: string; text: string }) => void,
+): Promise<UnlistenFn> {
+  return listen("claude:stderr", (event) => {
+    callback(event.payload as { sessionId: string; text: string });
+  });
+}
+
+/** Usage of one API message, counted once when the message is over */
+export interface UsageUpdate {
+  sessionId: string;
+  messageId: string | null;
+  /** Model that produced the message, e.g. a subagent's */
+  model: string | null;
+  usage: {
+    inputTokens: number;
+    outputTokens: number;
+    cacheCreationInputTokens: number;
+    cacheReadInputTokens: number;
+  };
+}
+
+export function onUsageUpdate(callback: (event: UsageUpdate) => void): Promise<UnlistenFn> {
+  return listen("claude:usage_update", (event) => {
+    callback(event.payload as UsageUpdate);
+  });
+}
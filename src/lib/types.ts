@@ -4,7 +4,7 @@ export interface Session {
   name: string | null;
   projectPath: string;
   worktreePath: string | null;
-  status: "active" | "paused" | "completed" | "error" | "waiting_input";
+  status: "active" | "paused" | "completed" | "error" | "waiting_input" | "cancelled";
   model: string | null;
   createdAt: string;
   updatedAt: string;
@@ -26,6 +26,8 @@ export interface ChatMessage {
   timestamp: string;
   isSidechain: boolean;
   toolCalls?: ToolCall[];
+  /** Reasoning from the message's thinking blocks */
+  thinking?: ThinkingBlock[];
   usage?: TokenUsage;
   isStreaming?: boolean;
 }
@@ -38,11 +40,22 @@ export interface ContentBlock {
   input?: Record<string, unknown>;
 }
 
+export interface ThinkingBlock {
+  /** Empty when the API redacted the reasoning */
+  text: string;
+  redacted: boolean;
+  /** How long the block took to stream; not known for past transcripts */
+  durationMs?: number;
+}
+
 export interface ToolCall {
   id: string;
   name: string;
   input: Record<string, unknown>;
+  /** Start of the result, when `truncated` */
   result?: string;
+  truncated?: boolean;
+  durationMs?: number;
   status: "running" | "completed" | "error";
 }
 
@@ -1,5 +1,5 @@
 import { create } from "zustand";
-import type { ChatMessage, ToolCall } from "../lib/types";
+import type { ChatMessage, ThinkingBlock, ToolCall } from "../lib/types";
 
 interface ChatState {
   messages: ChatMessage[];
@@ -8,7 +8,8 @@ interface ChatState {
   compactionCount: number;
   planMode: boolean;
   pendingToolCalls: ToolCall[];
-  currentToolInputBuffer: string;
+  /** Thinking blocks of the message being streamed */
+  pendingThinking: ThinkingBlock[];
   searchQuery: string;
   searchMatchIds: string[];
   searchCurrentIndex: number;
@@ -23,8 +24,12 @@ interface ChatState {
   setPlanMode: (enabled: boolean) => void;
   removeLastMessages: (count: number) => void;
   startToolCall: (id: string, name: string) => void;
-  appendToolInput: (partialJson: string) => void;
+  setToolInput: (id: string, input: Record<string, unknown>) => void;
   flushPendingToolCalls: () => ToolCall[];
+  finishToolCall: (id: string, update: Pick<ToolCall, "status" | "result" | "truncated" | "durationMs">) => void;
+  settleToolCalls: () => void;
+  addThinking: (block: ThinkingBlock) => void;
+  flushPendingThinking: () => ThinkingBlock[];
   setSearchQuery: (query: string, messages: ChatMessage[]) => void;
   clearSearch: () => void;
   nextMatch: () => void;
@@ -38,7 +43,7 @@ export const useChatStore = create<ChatState>((set, get) => ({
   compactionCount: 0,
   planMode: false,
   pendingToolCalls: [],
-  currentToolInputBuffer: "",
+  pendingThinking: [],
   searchQuery: "",
   searchMatchIds: [],
   searchCurrentIndex: -1,
@@ -68,7 +73,7 @@ export const useChatStore = create<ChatState>((set, get) => ({
       streamingText: "",
       compactionCount: 0,
       pendingToolCalls: [],
-      currentToolInputBuffer: "",
+      pendingThinking: [],
       searchQuery: "",
       searchMatchIds: [],
       searchCurrentIndex: -1,
@@ -84,45 +89,63 @@ export const useChatStore = create<ChatState>((set, get) => ({
       messages: state.messages.slice(0, Math.max(0, state.messages.length - count)),
     })),
 
-  startToolCall: (id, name) => {
-    const state = get();
-    // Finalize the previous tool's input buffer if any
-    const updated = [...state.pendingToolCalls];
-    if (updated.length > 0 && state.currentToolInputBuffer) {
-      const last = updated[updated.length - 1];
-      try {
-        last.input = JSON.parse(state.currentToolInputBuffer);
-      } catch {
-        last.input = { raw: state.currentToolInputBuffer };
-      }
-    }
-    updated.push({ id, name, input: {}, status: "running" });
-    set({ pendingToolCalls: updated, currentToolInputBuffer: "" });
-  },
+  startToolCall: (id, name) =>
+    set((state) => ({
+      pendingToolCalls: [...state.pendingToolCalls, { id, name, input: {}, status: "running" }],
+    })),
 
-  appendToolInput: (partialJson) =>
-    set((state) => ({ currentToolInputBuffer: state.currentToolInputBuffer + partialJson })),
+  // The backend assembles each call's input and sends it once complete
+  setToolInput: (id, input) =>
+    set((state) => ({
+      pendingToolCalls: state.pendingToolCalls.map((tool) =>
+        tool.id === id ? { ...tool, input } : tool,
+      ),
+    })),
 
+  // Calls stay running until their result arrives, usually after the message completed
   flushPendingToolCalls: () => {
-    const state = get();
-    const tools = [...state.pendingToolCalls];
-    // Finalize the last tool's input
-    if (tools.length > 0 && state.currentToolInputBuffer) {
-      const last = tools[tools.length - 1];
-      try {
-        last.input = JSON.parse(state.currentToolInputBuffer);
-      } catch {
-        last.input = { raw: state.currentToolInputBuffer };
-      }
-    }
-    // Mark all as completed
-    for (const tool of tools) {
-      tool.status = "completed";
-    }
-    set({ pendingToolCalls: [], currentToolInputBuffer: "" });
+    const tools = get().pendingToolCalls;
+    set({ pendingToolCalls: [] });
     return tools;
   },
 
+  finishToolCall: (id, update) =>
+    set((state) => {
+      const finish = (tool: ToolCall) => (tool.id === id ? { ...tool, ...update } : tool);
+      return {
+        pendingToolCalls: state.pendingToolCalls.map(finish),
+        messages: state.messages.map((m) =>
+          m.toolCalls?.some((tool) => tool.id === id)
+            ? { ...m, toolCalls: m.toolCalls.map(finish) }
+            : m,
+        ),
+      };
+    }),
+
+  // A turn that was cancelled or failed leaves its unanswered calls unfinished
+  settleToolCalls: () =>
+    set((state) => {
+      const settle = (tool: ToolCall): ToolCall =>
+        tool.status === "running" ? { ...tool, status: "error" } : tool;
+      return {
+        pendingToolCalls: state.pendingToolCalls.map(settle),
+        messages: state.messages.map((m) =>
+          m.toolCalls?.some((tool) => tool.status === "running")
+            ? { ...m, toolCalls: m.toolCalls.map(settle) }
+            : m,
+        ),
+      };
+    }),
+
+  addThinking: (block) =>
+    set((state) => ({ pendingThinking: [...state.pendingThinking, block] })),
+
+  flushPendingThinking: () => {
+    const thinking = get().pendingThinking;
+    set({ pendingThinking: [] });
+    return thinking;
+  },
+
   setSearchQuery: (query, messages) => {
     if (!query.trim()) {
       set({ searchQuery: "", searchMatchIds: [], searchCurrentIndex: -1 });
@@ -138,7 +161,9 @@ export const useChatStore = create<ChatState>((set, get) => ({
                 .filter((b) => b.type === "text" && b.text)
                 .map((b) => b.text!)
                 .join(" ");
-        return text.toLowerCase().includes(lowerQuery);
+        // Past reasoning is searchable too
+        const thinking = (m.thinking ?? []).map((t) => t.text).join(" ");
+        return `${text} ${thinking}`.toLowerCase().includes(lowerQuery);
       })
       .map((m) => m.uuid);
     set({